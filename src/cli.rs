@@ -1,10 +1,10 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand};
 
 #[derive(Debug, Parser)]
 #[command(name = "subx")]
-#[command(about = "Convert between SRT, VTT, TXT, TSV, and JSON transcript formats.")]
+#[command(about = "Convert between subtitle and transcript formats (see `list-formats`).")]
 pub struct Args {
     /// Path to config TOML (defaults to ./config.toml if present)
     #[arg(long)]
@@ -24,9 +24,20 @@ pub enum Command {
     Convert(ConvertCmd),
     /// Print the effective default config as TOML and exit
     PrintDefaultConfig,
+    /// List registered formats with their extensions and capabilities
+    ListFormats,
+    /// List the subtitle tracks inside a media container (Matroska/WebM, MP4),
+    /// the tiers of a TextGrid/EAF annotation file or the classes of a SAMI file
+    ListTracks(ListTracksCmd),
 }
 
 #[derive(Debug, Parser)]
+pub struct ListTracksCmd {
+    /// Container, annotation or SAMI file path
+    pub input: String,
+}
+
+#[derive(Debug, Clone, Parser)]
 pub struct ConvertCmd {
     /// Input file path, or '-' for stdin
     pub input: String,
@@ -35,13 +46,13 @@ pub struct ConvertCmd {
     #[arg(short, long)]
     pub output: Option<String>,
 
-    /// Target format
-    #[arg(long, value_enum)]
-    pub to: Format,
+    /// Target format name (see `list-formats`)
+    #[arg(long)]
+    pub to: String,
 
     /// Force input format (otherwise inferred from extension or content)
-    #[arg(long, value_enum)]
-    pub from: Option<Format>,
+    #[arg(long)]
+    pub from: Option<String>,
 
     /// Write to stdout instead of a file
     #[arg(long)]
@@ -50,27 +61,17 @@ pub struct ConvertCmd {
     /// Allow overwriting output file
     #[arg(long)]
     pub overwrite: bool,
-}
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
-pub enum Format {
-    Srt,
-    Vtt,
-    Ass,
-    Txt,
-    Tsv,
-    Json,
-}
+    /// Frame rate for frame-based formats (MicroDVD); overrides file headers and config
+    #[arg(long)]
+    pub fps: Option<f64>,
+
+    /// Subtitle track, annotation tier or SAMI class to read from the input (see `list-tracks`)
+    #[arg(long)]
+    pub track: Option<u64>,
 
-impl Format {
-    pub fn extension(self) -> &'static str {
-        match self {
-            Format::Srt => "srt",
-            Format::Vtt => "vtt",
-            Format::Ass => "ass",
-            Format::Txt => "txt",
-            Format::Tsv => "tsv",
-            Format::Json => "json",
-        }
-    }
+    /// Write every text track, tier or SAMI class to its own file; `{track}`
+    /// and `{lang}` in --output name each one (default: <input>.<track>.<ext>)
+    #[arg(long, conflicts_with_all = ["track", "stdout"])]
+    pub split: bool,
 }
@@ -1,24 +1,14 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{collections::BTreeMap, fs, path::Path};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     pub logging: Logging,
     pub policy: Policy,
     pub formats: Formats,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            logging: Logging::default(),
-            policy: Policy::default(),
-            formats: Formats::default(),
-        }
-    }
-}
-
 impl Config {
     pub fn load(path_opt: Option<&Path>) -> Result<Self> {
         let default_path = Path::new("config.toml");
@@ -93,7 +83,7 @@ impl Default for Policy {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Formats {
     pub ass: AssCfg,
     pub srt: SrtCfg,
@@ -101,19 +91,46 @@ pub struct Formats {
     pub txt: TxtCfg,
     pub tsv: TsvCfg,
     pub json: JsonCfg,
-}
-
-impl Default for Formats {
-    fn default() -> Self {
-        Self {
-            ass: AssCfg::default(),
-            srt: SrtCfg::default(),
-            vtt: VttCfg::default(),
-            txt: TxtCfg::default(),
-            tsv: TsvCfg::default(),
-            json: JsonCfg::default(),
-        }
-    }
+    #[serde(default)]
+    pub lrc: LrcCfg,
+    #[serde(default)]
+    pub microdvd: MicroDvdCfg,
+    #[serde(default)]
+    pub sami: SamiCfg,
+    #[serde(default)]
+    pub scc: SccCfg,
+    #[serde(default)]
+    pub stl: StlCfg,
+    #[serde(default)]
+    pub mkv: MkvCfg,
+    #[serde(default)]
+    pub mp4: Mp4Cfg,
+    #[serde(default)]
+    pub ctm: CtmCfg,
+    #[serde(default)]
+    pub stm: StmCfg,
+    #[serde(default)]
+    pub rttm: RttmCfg,
+    #[serde(default)]
+    pub textgrid: TextGridCfg,
+    #[serde(default)]
+    pub eaf: EafCfg,
+    #[serde(default)]
+    pub speech_marks: SpeechMarksCfg,
+    #[serde(default)]
+    pub ssml: SsmlCfg,
+    #[serde(default)]
+    pub epub: EpubCfg,
+    #[serde(default)]
+    pub html: HtmlCfg,
+    #[serde(default)]
+    pub markdown: MarkdownCfg,
+    #[serde(default)]
+    pub docx: DocxCfg,
+    /// `[formats.*]` tables with no field above, kept only so they can be
+    /// reported as matching no registered format.
+    #[serde(flatten, default)]
+    pub extra: toml::Table,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -143,18 +160,44 @@ impl Default for VttCfg {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TxtCfg {
-    pub mode: String,
+    pub mode: TxtMode,
+    /// Recognise `Speaker: text` lines when reading.
+    pub speaker_labels: bool,
+    /// `paragraph` mode: a pause this long starts a new paragraph, as does a
+    /// speaker change.
+    pub paragraph_gap_ms: i64,
+    /// `interval_timestamps` mode: time between `[hh:mm:ss]` markers.
+    pub interval_ms: i64,
 }
 
 impl Default for TxtCfg {
     fn default() -> Self {
         Self {
-            mode: "timestamp_range".to_string(),
+            mode: TxtMode::TimestampRange,
+            speaker_labels: true,
+            paragraph_gap_ms: 2000,
+            interval_ms: 30_000,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxtMode {
+    /// One cue per line, text only.
+    TextOnly,
+    /// One cue per line as `[start --> end] text`.
+    TimestampRange,
+    /// A dialogue script: one `Speaker: text` line per speaker turn.
+    Speaker,
+    /// Prose paragraphs split on speaker changes and pauses.
+    Paragraph,
+    /// Prose with a `[hh:mm:ss]` marker every `interval_ms`.
+    IntervalTimestamps,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TsvCfg {
     pub time_units: String,
@@ -179,6 +222,14 @@ impl Default for TsvCfg {
 pub struct JsonCfg {
     pub time_units: String,
     pub wrapped: bool,
+    /// Cue grouping for word-level speech-recognition output (JSON services,
+    /// CTM and RTTM words).
+    #[serde(default)]
+    pub asr: AsrCfg,
+    /// Field layout of an in-house JSON shape, used for reading it and for
+    /// writing JSON in its place.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<JsonMappingCfg>,
 }
 
 impl Default for JsonCfg {
@@ -186,10 +237,606 @@ impl Default for JsonCfg {
         Self {
             time_units: "seconds".to_string(),
             wrapped: true,
+            asr: AsrCfg::default(),
+            mapping: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CtmCfg {
+    /// File (waveform) ID written in the first column.
+    pub file_id: String,
+    pub channel: String,
+}
+
+impl Default for CtmCfg {
+    fn default() -> Self {
+        Self {
+            file_id: "transcript".to_string(),
+            channel: "1".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StmCfg {
+    pub file_id: String,
+    pub channel: String,
+    /// Speaker ID for cues without one.
+    pub default_speaker: String,
+}
+
+impl Default for StmCfg {
+    fn default() -> Self {
+        Self {
+            file_id: "transcript".to_string(),
+            channel: "1".to_string(),
+            default_speaker: "unknown".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RttmCfg {
+    pub file_id: String,
+    pub channel: String,
+    /// Speaker name for cues without one.
+    pub default_speaker: String,
+}
+
+impl Default for RttmCfg {
+    fn default() -> Self {
+        Self {
+            file_id: "transcript".to_string(),
+            channel: "1".to_string(),
+            default_speaker: "unknown".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextGridCfg {
+    /// Interval tier to read by name; all tiers are merged when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    /// Tier to read by number, as shown by `list-tracks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<u64>,
+    /// Write one tier per speaker instead of a single tier.
+    pub tier_per_speaker: bool,
+    /// Tier name for cues without a speaker.
+    pub default_tier: String,
+    /// Write Praat's short text form instead of the long one.
+    pub short_form: bool,
+}
+
+impl Default for TextGridCfg {
+    fn default() -> Self {
+        Self {
+            tier: None,
+            track: None,
+            tier_per_speaker: true,
+            default_tier: "transcript".to_string(),
+            short_form: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EafCfg {
+    /// Time-aligned tier to read by ID; all tiers are merged when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    /// Tier to read by number, as shown by `list-tracks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<u64>,
+    /// Write one tier per speaker instead of a single tier.
+    pub tier_per_speaker: bool,
+    /// Tier ID for cues without a speaker.
+    pub default_tier: String,
+    pub author: String,
+    /// Media file linked in the header; falls back to the one read from an
+    /// input EAF.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_url: Option<String>,
+}
+
+impl Default for EafCfg {
+    fn default() -> Self {
+        Self {
+            tier: None,
+            track: None,
+            tier_per_speaker: true,
+            default_tier: "transcript".to_string(),
+            author: String::new(),
+            media_url: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpeechMarksCfg {
+    /// SSML document that was synthesized, used to put the text after each
+    /// `<mark>` into the cues read from a timepoint list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssml_source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SsmlCfg {
+    /// Element wrapping each cue: "s" (sentence) or "p" (paragraph).
+    pub cue_element: SsmlCueElement,
+    /// Gaps shorter than this get no `<break>`.
+    pub min_break_ms: i64,
+    pub max_break_ms: i64,
+    /// Write a `<mark>` named `<mark_prefix><cue number>` before each cue.
+    pub marks: bool,
+    pub mark_prefix: String,
+    /// Speaker name to TTS voice name; unmapped speakers use the default voice.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub voices: BTreeMap<String, String>,
+}
+
+impl Default for SsmlCfg {
+    fn default() -> Self {
+        Self {
+            cue_element: SsmlCueElement::S,
+            min_break_ms: 100,
+            max_break_ms: 10_000,
+            marks: true,
+            mark_prefix: "cue-".to_string(),
+            voices: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SsmlCueElement {
+    /// `<s>`, a sentence.
+    S,
+    /// `<p>`, a paragraph.
+    P,
+}
+
+/// Shared by the SMIL media overlay and XHTML content document writers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpubCfg {
+    /// XHTML document the overlay points into, as referenced in the package.
+    pub text_href: String,
+    /// Narration audio, as referenced in the package.
+    pub audio_src: String,
+    /// Fragment IDs are `<id_prefix><cue number>`.
+    pub id_prefix: String,
+    /// Language of the XHTML document; the transcript's own language wins
+    /// when known.
+    pub lang: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stylesheet: Option<String>,
+}
+
+impl Default for EpubCfg {
+    fn default() -> Self {
+        Self {
+            text_href: "chapter.xhtml".to_string(),
+            audio_src: "chapter.mp3".to_string(),
+            id_prefix: "cue-".to_string(),
+            lang: "en".to_string(),
+            title: None,
+            stylesheet: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlCfg {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Page language; the transcript's own language wins when known.
+    pub lang: String,
+    /// Audio or video to embed; the active cue is highlighted while it plays.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_src: Option<String>,
+    /// "audio" or "video".
+    pub media_element: HtmlMediaElement,
+    /// A pause this long starts a new paragraph, as does a speaker change.
+    pub paragraph_gap_ms: i64,
+    pub id_prefix: String,
+    /// Scroll the active cue into view during playback.
+    pub autoscroll: bool,
+    /// Stylesheet replacing the built-in one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub css: Option<String>,
+    /// HTML file replacing the built-in page layout, with `{{title}}`,
+    /// `{{lang}}`, `{{css}}`, `{{media}}`, `{{transcript}}` and `{{script}}`
+    /// placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl Default for HtmlCfg {
+    fn default() -> Self {
+        Self {
+            title: None,
+            lang: "en".to_string(),
+            media_src: None,
+            media_element: HtmlMediaElement::Audio,
+            paragraph_gap_ms: 2000,
+            id_prefix: "cue-".to_string(),
+            autoscroll: true,
+            css: None,
+            template: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HtmlMediaElement {
+    Audio,
+    Video,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownCfg {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// A pause this long starts a new paragraph, as does a speaker change.
+    pub paragraph_gap_ms: i64,
+    /// A pause this long starts a new section for the table of contents.
+    pub section_gap_ms: i64,
+    /// Put `[hh:mm:ss]` markers at the start of paragraphs.
+    pub timestamps: bool,
+    /// Minimum time between markers; 0 marks every paragraph.
+    pub marker_interval_ms: i64,
+    /// Table of contents: "none", "speakers" (one entry per speaker turn) or
+    /// "sections" (one entry per stretch between long pauses).
+    pub toc: MarkdownToc,
+}
+
+impl Default for MarkdownCfg {
+    fn default() -> Self {
+        Self {
+            title: None,
+            paragraph_gap_ms: 2000,
+            section_gap_ms: 10_000,
+            timestamps: true,
+            marker_interval_ms: 60_000,
+            toc: MarkdownToc::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownToc {
+    None,
+    /// One entry per speaker turn.
+    Speakers,
+    /// One entry per stretch between long pauses.
+    Sections,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocxCfg {
+    pub layout: DocxLayout,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub font: String,
+    pub font_size_pt: f64,
+    /// `paragraph` layout: open each paragraph with its timecode.
+    pub timecodes: bool,
+    /// Timecodes to the millisecond instead of the second.
+    pub timecode_ms: bool,
+    /// `paragraph` layout: a pause this long starts a new paragraph, as does
+    /// a speaker change.
+    pub paragraph_gap_ms: i64,
+    /// Hex colours of the speaker styles, assigned in order of appearance.
+    pub speaker_colors: Vec<String>,
+}
+
+impl Default for DocxCfg {
+    fn default() -> Self {
+        Self {
+            layout: DocxLayout::Table,
+            title: None,
+            font: "Calibri".to_string(),
+            font_size_pt: 11.0,
+            timecodes: true,
+            timecode_ms: false,
+            paragraph_gap_ms: 2000,
+            speaker_colors: ["1F4E79", "833C0B", "375623", "7030A0", "C00000", "0070C0"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocxLayout {
+    /// Timecode, speaker and text columns, one row per cue.
+    Table,
+    /// Running paragraphs with speaker names.
+    Paragraph,
+}
+
+/// Dotted paths (`data.items`, `timing.0.start`) into a custom JSON shape.
+/// Fields left out take the defaults below.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonMappingCfg {
+    /// Path to the cue array; empty when the document is the array itself.
+    pub cues: String,
+    pub start: String,
+    pub end: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// Path, within a cue, to its word array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<String>,
+    pub word_start: String,
+    pub word_end: String,
+    pub word_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub word_confidence: Option<String>,
+    /// "seconds", "ms", or "timestamp" (`HH:MM:SS.mmm` strings).
+    pub time_units: String,
+}
+
+impl Default for JsonMappingCfg {
+    fn default() -> Self {
+        Self {
+            cues: String::new(),
+            start: "start".to_string(),
+            end: "end".to_string(),
+            text: "text".to_string(),
+            speaker: None,
+            words: None,
+            word_start: "start".to_string(),
+            word_end: "end".to_string(),
+            word_text: "text".to_string(),
+            word_confidence: None,
+            time_units: "seconds".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsrCfg {
+    /// Longest cue text, in characters.
+    pub max_chars: usize,
+    pub max_duration_ms: i64,
+    /// A pause longer than this starts a new cue.
+    pub max_gap_ms: i64,
+    /// End a cue after sentence-final punctuation.
+    pub split_sentences: bool,
+}
+
+impl Default for AsrCfg {
+    fn default() -> Self {
+        Self {
+            max_chars: 84,
+            max_duration_ms: 7000,
+            max_gap_ms: 1500,
+            split_sentences: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LrcCfg {
+    /// Emit `<mm:ss.xx>` word tags when cues carry word timings.
+    pub enhanced: bool,
+    /// Emit an empty time tag where a cue ends before the next one starts.
+    pub end_markers: bool,
+}
+
+impl Default for LrcCfg {
+    fn default() -> Self {
+        Self {
+            enhanced: true,
+            end_markers: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicroDvdCfg {
+    /// Frame rate used to convert frame numbers; unset means it must come
+    /// from the `{1}{1}<fps>` header or `--fps`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps: Option<f64>,
+    /// Let a `{1}{1}<fps>` header in the input override `fps`.
+    pub trust_header: bool,
+    /// Start MicroDVD output with a `{1}{1}<fps>` header line.
+    pub write_fps_header: bool,
+}
+
+impl Default for MicroDvdCfg {
+    fn default() -> Self {
+        Self {
+            fps: None,
+            trust_header: true,
+            write_fps_header: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamiCfg {
+    /// Language class to read (e.g. "KRCC"); unset reads the first declared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// Class to read by number, as shown by `list-tracks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<u64>,
+    /// Class name, display name and language of the class written out; the
+    /// transcript's own language wins over `lang` when known.
+    pub out_class: String,
+    pub out_name: String,
+    pub lang: String,
+    /// CSS declarations for the `P` rule of the written style block.
+    pub p_style: String,
+    /// Extra CSS rules appended to the written style block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_css: Option<String>,
+}
+
+impl Default for SamiCfg {
+    fn default() -> Self {
+        Self {
+            class: None,
+            track: None,
+            out_class: "ENUSCC".to_string(),
+            out_name: "English".to_string(),
+            lang: "en-US".to_string(),
+            p_style: "margin-left:8pt; margin-right:8pt; margin-bottom:2pt; margin-top:2pt; \
+                      text-align:center; font-size:20pt; font-family:Arial; \
+                      font-weight:normal; color:white;"
+                .to_string(),
+            extra_css: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SccCfg {
+    /// CEA-608 caption channel: 1-4 when reading, 1 or 2 when writing.
+    pub channel: u8,
+    /// Fail on characters outside the CEA-608 set instead of dropping them.
+    pub strict: bool,
+}
+
+impl Default for SccCfg {
+    fn default() -> Self {
+        Self {
+            channel: 1,
+            strict: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StlCfg {
+    /// Subtract the GSI start-of-programme timecode (often 10:00:00:00)
+    /// from every cue when reading.
+    pub offset_start_of_programme: bool,
+    /// Output frame rate: 25 (`STL25.01`) or 30 (`STL30.01`).
+    pub frame_rate: u32,
+    /// Output character code table: "00" Latin (ISO 6937), "01" Cyrillic,
+    /// "02" Arabic, "03" Greek, "04" Hebrew.
+    pub character_table: StlCharacterTable,
+    /// Output display standard code: "0" open, "1"/"2" teletext level 1/2.
+    pub display_standard: StlDisplayStandard,
+    /// Output justification: none, left, centered or right.
+    pub justification: StlJustification,
+    /// Row of the last subtitle line (teletext rows run 1-23).
+    pub vertical_position: u8,
+    pub max_chars_per_row: usize,
+}
+
+impl Default for StlCfg {
+    fn default() -> Self {
+        Self {
+            offset_start_of_programme: true,
+            frame_rate: 25,
+            character_table: StlCharacterTable::Latin,
+            display_standard: StlDisplayStandard::Level1,
+            justification: StlJustification::Centered,
+            vertical_position: 22,
+            max_chars_per_row: 40,
+        }
+    }
+}
+
+/// GSI `CCT` codes, written as in the GSI block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StlCharacterTable {
+    /// ISO 6937.
+    #[serde(rename = "00", alias = "latin")]
+    Latin,
+    /// ISO 8859-5.
+    #[serde(rename = "01", alias = "cyrillic")]
+    Cyrillic,
+    /// ISO 8859-6.
+    #[serde(rename = "02", alias = "arabic")]
+    Arabic,
+    /// ISO 8859-7.
+    #[serde(rename = "03", alias = "greek")]
+    Greek,
+    /// ISO 8859-8.
+    #[serde(rename = "04", alias = "hebrew")]
+    Hebrew,
+}
+
+impl StlCharacterTable {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Latin => "00",
+            Self::Cyrillic => "01",
+            Self::Arabic => "02",
+            Self::Greek => "03",
+            Self::Hebrew => "04",
         }
     }
 }
 
+/// GSI `DSC` codes, written as in the GSI block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StlDisplayStandard {
+    /// Open subtitling.
+    #[serde(rename = "0", alias = "open")]
+    Open,
+    /// Teletext level 1.
+    #[serde(rename = "1", alias = "level1")]
+    Level1,
+    /// Teletext level 2.
+    #[serde(rename = "2", alias = "level2")]
+    Level2,
+}
+
+impl StlDisplayStandard {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Open => "0",
+            Self::Level1 => "1",
+            Self::Level2 => "2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StlJustification {
+    /// Left as the source laid it out.
+    None,
+    Left,
+    #[serde(alias = "center")]
+    Centered,
+    Right,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MkvCfg {
+    /// Subtitle track number to extract (see `list-tracks`); unset picks the
+    /// default text track, then the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Mp4Cfg {
+    /// Subtitle track ID to extract (see `list-tracks`); unset picks the
+    /// enabled text track, then the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssCfg {
     pub play_res_x: u32,
@@ -0,0 +1,205 @@
+use serde_json::Value;
+
+use crate::{
+    config::AsrCfg,
+    model::{Cue, Word},
+};
+
+/// One recognised token from a speech-recognition service.
+#[derive(Debug, Clone)]
+pub struct AsrWord {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    pub speaker: Option<String>,
+    pub confidence: Option<f64>,
+    /// Punctuation token that attaches to the preceding word.
+    pub punctuation: bool,
+}
+
+impl AsrWord {
+    pub fn to_word(&self) -> Word {
+        Word {
+            start_ms: self.start_ms,
+            end_ms: self.end_ms,
+            text: self.text.clone(),
+            confidence: self.confidence,
+        }
+    }
+}
+
+/// Regroups word-level output into readable cues, breaking on speaker
+/// changes, sentence ends, long pauses and the configured size limits.
+pub fn group_words(words: &[AsrWord], cfg: &AsrCfg) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut cur: Option<Cue> = None;
+
+    for w in words {
+        if w.punctuation {
+            if let Some(c) = cur.as_mut() {
+                c.text.push_str(&w.text);
+                if let Some(last) = c.words.last_mut() {
+                    last.text.push_str(&w.text);
+                }
+                if cfg.split_sentences && ends_sentence(&w.text) {
+                    cues.extend(cur.take());
+                }
+            }
+            continue;
+        }
+
+        if let Some(c) = &cur {
+            let breaks = c.speaker != w.speaker
+                || w.start_ms - c.end_ms > cfg.max_gap_ms
+                || w.end_ms - c.start_ms > cfg.max_duration_ms
+                || c.text.chars().count() + 1 + w.text.chars().count() > cfg.max_chars;
+            if breaks {
+                cues.extend(cur.take());
+            }
+        }
+
+        let c = cur.get_or_insert_with(|| Cue {
+            start_ms: w.start_ms,
+            end_ms: w.end_ms,
+            text: String::new(),
+            speaker: w.speaker.clone(),
+            words: Vec::new(),
+        });
+        if !c.text.is_empty() {
+            c.text.push(' ');
+        }
+        c.text.push_str(&w.text);
+        c.end_ms = c.end_ms.max(w.end_ms);
+        c.words.push(w.to_word());
+        if cfg.split_sentences && ends_sentence(&w.text) {
+            cues.extend(cur.take());
+        }
+    }
+    cues.extend(cur);
+    cues
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.ends_with(['.', '?', '!', '…', '。', '？', '！'])
+}
+
+/// Speaker name for a diarized speaker and/or the audio channel of a
+/// multichannel recording.
+pub fn speaker_label(speaker: Option<&Value>, channel: Option<&Value>) -> Option<String> {
+    match (speaker.and_then(label), channel.and_then(label)) {
+        // Some services report the channel as the speaker in multichannel mode.
+        (Some(s), Some(c)) if s == c => Some(format!("Channel {c}")),
+        (Some(s), Some(c)) => Some(format!("Channel {c} Speaker {s}")),
+        (Some(s), None) => Some(format!("Speaker {s}")),
+        (None, Some(c)) => Some(format!("Channel {c}")),
+        (None, None) => None,
+    }
+}
+
+fn label(v: &Value) -> Option<String> {
+    match v {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// A JSON number or numeric string.
+pub fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Seconds given as a JSON number or numeric string, in milliseconds.
+pub fn seconds_to_ms(v: &Value) -> Option<i64> {
+    number(v).map(|secs| (secs * 1000.0).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn w(start_ms: i64, end_ms: i64, text: &str, speaker: Option<&str>) -> AsrWord {
+        AsrWord {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            speaker: speaker.map(str::to_string),
+            confidence: None,
+            punctuation: false,
+        }
+    }
+
+    fn texts(cues: &[Cue]) -> Vec<&str> {
+        cues.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn breaks_on_speaker_gap_and_sentence() {
+        let cfg = AsrCfg::default();
+        let words = [
+            w(0, 100, "one", Some("A")),
+            w(100, 200, "two.", Some("A")),
+            w(200, 300, "three", Some("A")),
+            w(300, 400, "four", Some("B")),
+            w(5000, 5100, "five", Some("B")),
+        ];
+        assert_eq!(
+            texts(&group_words(&words, &cfg)),
+            vec!["one two.", "three", "four", "five"]
+        );
+    }
+
+    #[test]
+    fn respects_size_limits() {
+        let cfg = AsrCfg {
+            max_chars: 9,
+            max_duration_ms: 1000,
+            split_sentences: false,
+            ..AsrCfg::default()
+        };
+        let words = [
+            w(0, 100, "abc", None),
+            w(100, 200, "def.", None),
+            w(200, 300, "ghi", None),
+            w(300, 1300, "jkl", None),
+        ];
+        assert_eq!(
+            texts(&group_words(&words, &cfg)),
+            vec!["abc def.", "ghi", "jkl"]
+        );
+    }
+
+    #[test]
+    fn leading_punctuation_is_dropped() {
+        let cfg = AsrCfg::default();
+        let mut comma = w(0, 0, ",", None);
+        comma.punctuation = true;
+        let cues = group_words(&[comma.clone(), w(0, 100, "hi", None), comma], &cfg);
+        assert_eq!(texts(&cues), vec!["hi,"]);
+        assert_eq!(cues[0].words[0].text, "hi,");
+    }
+
+    #[test]
+    fn speaker_labels_and_numbers() {
+        assert_eq!(
+            speaker_label(Some(&json!(1)), None).as_deref(),
+            Some("Speaker 1")
+        );
+        assert_eq!(
+            speaker_label(Some(&json!("A")), Some(&json!(0))).as_deref(),
+            Some("Channel 0 Speaker A")
+        );
+        assert_eq!(
+            speaker_label(Some(&json!(0)), Some(&json!(0))).as_deref(),
+            Some("Channel 0")
+        );
+        assert_eq!(speaker_label(Some(&json!("")), None), None);
+        assert_eq!(seconds_to_ms(&json!("1.2345")), Some(1235));
+        assert_eq!(seconds_to_ms(&json!(null)), None);
+    }
+}
@@ -1,43 +1,103 @@
-use crate::{config::Config, model::Transcript};
+use crate::{
+    config::Config,
+    formats::{
+        plain::plain_to_transcript,
+        registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+    },
+    model::{Cue, Meta, Transcript},
+};
 use anyhow::{Result, anyhow};
 use aspasia::{AssSubtitle, PlainSubtitle};
+use std::io::Write;
 
-pub fn write_ass(t: &Transcript, cfg: &Config) -> String {
-    let ass_cfg = &cfg.formats.ass;
-    let mut out = String::new();
-
-    out.push_str("[Script Info]\n");
-    out.push_str("ScriptType: v4.00+\n");
-    out.push_str(&format!("PlayResX: {}\n", ass_cfg.play_res_x));
-    out.push_str(&format!("PlayResY: {}\n\n", ass_cfg.play_res_y));
-
-    out.push_str("[V4+ Styles]\n");
-    out.push_str("Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n");
-    out.push_str(&format_style(ass_cfg));
-    out.push_str("\n[Events]\n");
-    out.push_str(
-        "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
-    );
-
-    for cue in &t.cues {
-        let start = format_ass_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms);
-        let end = format_ass_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms);
-        let text = escape_ass_text(&cue_text_for_export(cue.text.as_str(), cfg));
-
-        out.push_str(&format!(
-            "Dialogue: {layer},{start},{end},{style},,{margin_l},{margin_r},{margin_v},,{text}\n",
-            layer = ass_cfg.event_layer,
-            start = start,
-            end = end,
-            style = ass_cfg.style_name,
-            margin_l = pad_margin(ass_cfg.margin_l),
-            margin_r = pad_margin(ass_cfg.margin_r),
-            margin_v = pad_margin(ass_cfg.margin_v),
-            text = text
-        ));
+pub struct Ass;
+
+impl FormatReader for Ass {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_ass(input)
     }
+}
+
+impl FormatWriter for Ass {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        write_ass_header(out, cfg)?;
+        Ok(Box::new(AssSink { cfg, out }))
+    }
+}
+
+struct AssSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+}
+
+impl CueSink for AssSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        write_ass_event(self.out, cue, self.cfg)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
 
-    out
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("ass", &["ass", "ssa"])
+        .config_section("ass")
+        .sniff(|raw| {
+            raw.trim_start_matches('\u{feff}')
+                .starts_with("[Script Info]")
+        })
+        .reader(Ass)
+        .writer(Ass)
+}
+
+fn write_ass_header(out: &mut dyn Write, cfg: &Config) -> Result<()> {
+    let ass_cfg = &cfg.formats.ass;
+
+    writeln!(out, "[Script Info]")?;
+    writeln!(out, "ScriptType: v4.00+")?;
+    writeln!(out, "PlayResX: {}", ass_cfg.play_res_x)?;
+    writeln!(out, "PlayResY: {}\n", ass_cfg.play_res_y)?;
+
+    writeln!(out, "[V4+ Styles]")?;
+    writeln!(
+        out,
+        "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding"
+    )?;
+    out.write_all(format_style(ass_cfg).as_bytes())?;
+    writeln!(out, "\n[Events]")?;
+    writeln!(
+        out,
+        "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text"
+    )?;
+    Ok(())
+}
+
+fn write_ass_event(out: &mut dyn Write, cue: &Cue, cfg: &Config) -> Result<()> {
+    let ass_cfg = &cfg.formats.ass;
+    let start = format_ass_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms);
+    let end = format_ass_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms);
+    let text = escape_ass_text(&cue_text_for_export(cue.text.as_str(), cfg));
+
+    writeln!(
+        out,
+        "Dialogue: {layer},{start},{end},{style},,{margin_l},{margin_r},{margin_v},,{text}",
+        layer = ass_cfg.event_layer,
+        start = start,
+        end = end,
+        style = ass_cfg.style_name,
+        margin_l = pad_margin(ass_cfg.margin_l),
+        margin_r = pad_margin(ass_cfg.margin_r),
+        margin_v = pad_margin(ass_cfg.margin_v),
+        text = text
+    )?;
+    Ok(())
 }
 
 fn cue_text_for_export(text: &str, cfg: &Config) -> String {
@@ -106,7 +166,7 @@ fn format_ass_timestamp(ms_in: i64) -> String {
     let ms = ms_in.max(0);
     let total_centis = (ms / 10).max(0);
     let centis = total_centis % 100;
-    let total_seconds = (total_centis / 100) as i64;
+    let total_seconds = total_centis / 100;
     let seconds = total_seconds % 60;
     let total_minutes = total_seconds / 60;
     let minutes = total_minutes % 60;
@@ -130,27 +190,35 @@ pub fn parse_ass(input: &str) -> Result<Transcript> {
     Err(anyhow!("failed to parse as ASS"))
 }
 
-fn plain_to_transcript(plain: &PlainSubtitle) -> Transcript {
-    use crate::model::Cue;
-
-    let cues: Vec<Cue> = plain
-        .events()
-        .iter()
-        .map(|e| Cue {
-            start_ms: moment_to_ms(&e.start),
-            end_ms: moment_to_ms(&e.end),
-            text: e.text.clone(),
-            speaker: None,
-        })
-        .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    #[test]
+    fn roundtrip() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![cue(1230, 4560, "Hello"), cue(5000, 6000, "World")]);
+        let out = render(&Ass, &t, &cfg);
+        assert!(out.contains("[Events]"));
+        assert!(out.contains("Dialogue: 0,0:00:01.23,0:00:04.56,Default,,0010,0010,0010,,Hello\n"));
+        assert_eq!(timings(&parse_ass(&out).unwrap()), timings(&t));
+    }
 
-    Transcript::new(cues)
-}
+    #[test]
+    fn timestamps_truncate_to_centiseconds() {
+        assert_eq!(format_ass_timestamp(3_723_459), "1:02:03.45");
+        assert_eq!(format_ass_timestamp(-5), "0:00:00.00");
+    }
 
-fn moment_to_ms(m: &aspasia::timing::Moment) -> i64 {
-    let h = m.hours();
-    let min = m.minutes();
-    let s = m.seconds();
-    let ms = m.ms();
-    (((h * 60 + min) * 60 + s) * 1000 + ms) as i64
+    #[test]
+    fn header_follows_config() {
+        let mut cfg = Config::default();
+        cfg.formats.ass.play_res_x = 1280;
+        cfg.formats.ass.style_name = "Main".to_string();
+        let out = render(&Ass, &Transcript::new(vec![cue(0, 1000, "Hi")]), &cfg);
+        assert!(out.contains("PlayResX: 1280"));
+        assert!(out.contains("Style: Main,Arial,38.0,"));
+        assert!(out.contains(",Main,,"));
+    }
 }
@@ -0,0 +1,195 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    formats::asr::{AsrWord, group_words, number, speaker_label},
+    model::{Cue, Meta, Transcript},
+};
+
+/// AssemblyAI transcript: top-level `text` and `words` (times in ms), plus
+/// `utterances` when speaker labels were requested.
+pub fn looks_like(v: &Value) -> bool {
+    v.get("words").is_some_and(Value::is_array) && v.get("text").is_some_and(Value::is_string)
+}
+
+pub fn parse(v: &Value, cfg: &Config) -> Result<Transcript> {
+    let multichannel = v.get("multichannel").and_then(Value::as_bool) == Some(true)
+        || v.get("audio_channels").and_then(Value::as_u64).unwrap_or(1) > 1;
+
+    let cues = match v.get("utterances").and_then(Value::as_array) {
+        Some(utterances) if !utterances.is_empty() => {
+            let mut cues = Vec::with_capacity(utterances.len());
+            for (i, u) in utterances.iter().enumerate() {
+                let channel = u.get("channel").filter(|_| multichannel);
+                let words = parse_words(u.get("words"), multichannel)?;
+                cues.push(Cue {
+                    start_ms: time(u, "start")
+                        .ok_or_else(|| anyhow!("AssemblyAI: utterance {i} missing start"))?,
+                    end_ms: time(u, "end")
+                        .ok_or_else(|| anyhow!("AssemblyAI: utterance {i} missing end"))?,
+                    text: u
+                        .get("text")
+                        .and_then(Value::as_str)
+                        .unwrap_or("")
+                        .to_string(),
+                    speaker: speaker_label(u.get("speaker"), channel),
+                    words: words.iter().map(AsrWord::to_word).collect(),
+                });
+            }
+            cues
+        }
+        _ => {
+            let words = parse_words(v.get("words"), multichannel)?;
+            let mut cues = Vec::new();
+            if multichannel {
+                // Words of different channels interleave; group each alone.
+                let mut channels: Vec<&Option<String>> = Vec::new();
+                for w in &words {
+                    if !channels.contains(&&w.speaker) {
+                        channels.push(&w.speaker);
+                    }
+                }
+                for ch in channels {
+                    let mine: Vec<AsrWord> =
+                        words.iter().filter(|w| &w.speaker == ch).cloned().collect();
+                    cues.extend(group_words(&mine, &cfg.formats.json.asr));
+                }
+                cues.sort_by_key(|c| c.start_ms);
+            } else {
+                cues = group_words(&words, &cfg.formats.json.asr);
+            }
+            cues
+        }
+    };
+
+    let mut meta = Meta {
+        language: v
+            .get("language_code")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        ..Meta::default()
+    };
+    if let Some(id) = v.get("id").and_then(Value::as_str) {
+        meta.tags.insert("id".to_string(), id.to_string());
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+fn time(v: &Value, key: &str) -> Option<i64> {
+    v.get(key).and_then(number).map(|ms| ms.round() as i64)
+}
+
+fn parse_words(words: Option<&Value>, multichannel: bool) -> Result<Vec<AsrWord>> {
+    let Some(words) = words.filter(|w| !w.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let words = words
+        .as_array()
+        .ok_or_else(|| anyhow!("AssemblyAI: words must be an array"))?;
+    let mut out = Vec::with_capacity(words.len());
+    for (i, w) in words.iter().enumerate() {
+        let channel = w.get("channel").filter(|_| multichannel);
+        out.push(AsrWord {
+            start_ms: time(w, "start")
+                .ok_or_else(|| anyhow!("AssemblyAI: word {i} missing start"))?,
+            end_ms: time(w, "end").ok_or_else(|| anyhow!("AssemblyAI: word {i} missing end"))?,
+            text: w
+                .get("text")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("AssemblyAI: word {i} has no text"))?
+                .to_string(),
+            speaker: speaker_label(w.get("speaker"), channel),
+            confidence: w.get("confidence").and_then(number),
+            punctuation: false,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{json::parse_json, testutil::timings};
+    use serde_json::json;
+
+    #[test]
+    fn utterances_with_speakers() {
+        let v = json!({
+            "id": "tx-1",
+            "language_code": "en_us",
+            "text": "Hello there. Hi.",
+            "words": [],
+            "utterances": [
+                {"speaker": "A", "start": 0, "end": 900, "text": "Hello there.",
+                 "words": [{"text": "Hello", "start": 0, "end": 400, "confidence": 0.97, "speaker": "A"}]},
+                {"speaker": "B", "start": 1200, "end": 1500, "text": "Hi.", "words": null}
+            ]
+        });
+        let t = parse_json(&v.to_string(), &Config::default()).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 900, "Hello there."), (1200, 1500, "Hi.")]
+        );
+        let speakers: Vec<_> = t.cues.iter().map(|c| c.speaker.as_deref()).collect();
+        assert_eq!(speakers, vec![Some("Speaker A"), Some("Speaker B")]);
+        assert_eq!(t.cues[0].words[0].confidence, Some(0.97));
+        assert_eq!(t.meta.language.as_deref(), Some("en_us"));
+        assert_eq!(t.meta.tags.get("id").map(String::as_str), Some("tx-1"));
+    }
+
+    #[test]
+    fn words_are_regrouped_without_utterances() {
+        let v = json!({
+            "text": "One. Two",
+            "utterances": [],
+            "words": [
+                {"text": "One.", "start": 0, "end": 300},
+                {"text": "Two", "start": 400, "end": 700}
+            ]
+        });
+        let t = parse(&v, &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(0, 300, "One."), (400, 700, "Two")]);
+    }
+
+    #[test]
+    fn multichannel_words_group_per_channel() {
+        let v = json!({
+            "text": "",
+            "multichannel": true,
+            "words": [
+                {"text": "left", "start": 0, "end": 300, "channel": "1"},
+                {"text": "right", "start": 100, "end": 400, "channel": "2"},
+                {"text": "again", "start": 500, "end": 800, "channel": "1"}
+            ]
+        });
+        let t = parse(&v, &Config::default()).unwrap();
+        let cues: Vec<_> = t
+            .cues
+            .iter()
+            .map(|c| (c.text.as_str(), c.speaker.as_deref()))
+            .collect();
+        assert_eq!(
+            cues,
+            vec![
+                ("left again", Some("Channel 1")),
+                ("right", Some("Channel 2"))
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_words_are_errors() {
+        let cfg = Config::default();
+        assert!(parse(&json!({"text": "", "words": {}}), &cfg).is_err());
+        assert!(
+            parse(
+                &json!({"text": "", "words": [{"start": 0, "end": 1}]}),
+                &cfg
+            )
+            .is_err()
+        );
+        assert!(!looks_like(&json!({"text": "", "segments": []})));
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct Audacity;
+
+impl FormatReader for Audacity {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_labels(input)
+    }
+}
+
+impl FormatWriter for Audacity {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(LabelSink { cfg, out }))
+    }
+}
+
+/// Audacity exports labels as `.txt`, which stays plain text; labels are
+/// picked with `--from`/`--to audacity`, or by the sniffer for stdin.
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("audacity", &[])
+        .sniff(|raw| {
+            raw.lines()
+                .find(|l| !l.trim().is_empty())
+                .is_some_and(|l| label_times(l).is_some())
+        })
+        .reader(Audacity)
+        .writer(Audacity)
+}
+
+/// `start<TAB>end[<TAB>label]` in seconds; a spectral selection adds a
+/// `\<TAB>low<TAB>high` line with its frequency range in Hz.
+pub fn parse_labels(input: &str) -> Result<Transcript> {
+    let mut cues = Vec::new();
+    for (i, line) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(range) = line.strip_prefix('\\') {
+            let freqs: Vec<&str> = range.split('\t').map(str::trim).collect();
+            if cues.is_empty() || !freqs[1..].iter().all(|f| f.parse::<f64>().is_ok()) {
+                return Err(anyhow!("line {}: bad frequency range '{line}'", i + 1));
+            }
+            continue;
+        }
+        let (start_ms, end_ms) = label_times(line)
+            .ok_or_else(|| anyhow!("line {}: expected start<TAB>end<TAB>label", i + 1))?;
+        cues.push(Cue {
+            start_ms,
+            end_ms,
+            text: line.splitn(3, '\t').nth(2).unwrap_or("").trim().to_string(),
+            speaker: None,
+            words: Vec::new(),
+        });
+    }
+    Ok(Transcript::new(cues))
+}
+
+fn label_times(line: &str) -> Option<(i64, i64)> {
+    let mut fields = line.split('\t');
+    let start: f64 = fields.next()?.trim().parse().ok()?;
+    let end: f64 = fields.next()?.trim().parse().ok()?;
+    let ms = |secs: f64| (secs * 1000.0).round() as i64;
+    Some((ms(start), ms(end)))
+}
+
+struct LabelSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+}
+
+impl CueSink for LabelSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        // A label is one line of text.
+        let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let label = match &cue.speaker {
+            Some(s) => format!("{s}: {text}"),
+            None => text,
+        };
+        writeln!(
+            self.out,
+            "{:.6}\t{:.6}\t{label}",
+            (cue.start_ms + offset).max(0) as f64 / 1000.0,
+            (cue.end_ms + offset).max(0) as f64 / 1000.0
+        )?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, spoken, timings};
+
+    fn sniff(raw: &str) -> bool {
+        (entry().sniff.unwrap())(raw)
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut cfg = Config::default();
+        cfg.policy.timestamp_offset_ms = 250;
+        let t = Transcript::new(vec![
+            cue(0, 1500, "Two\nlines"),
+            spoken(2000, 2000, "Ann", "point"),
+        ]);
+        let out = render(&Audacity, &t, &cfg);
+        assert_eq!(
+            out,
+            "0.250000\t1.750000\tTwo lines\n2.250000\t2.250000\tAnn: point\n"
+        );
+        assert_eq!(
+            timings(&parse_labels(&out).unwrap()),
+            vec![(250, 1750, "Two lines"), (2250, 2250, "Ann: point")]
+        );
+    }
+
+    #[test]
+    fn spectral_lines_and_empty_labels() {
+        let raw = "\u{feff}1.5\t2\r\n\\\t100.5\t2000\r\n\r\n3\t4\tlabel\twith tab\n";
+        assert_eq!(
+            timings(&parse_labels(raw).unwrap()),
+            vec![(1500, 2000, ""), (3000, 4000, "label\twith tab")]
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        assert!(parse_labels("\\\t100\t200\n").is_err());
+        assert!(parse_labels("1\t2\tok\n\\\tlow\thigh\n").is_err());
+        let err = parse_labels("1\t2\tok\n1 2 spaced\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn sniff_needs_tab_separated_seconds() {
+        assert!(sniff("\n0.000000\t1.500000\tHello\n"));
+        assert!(sniff("12\t13\n"));
+        assert!(!sniff("1 2 3 go\n"));
+        assert!(!sniff("42\n"));
+        assert!(!sniff("start\tend\ttext\n0\t1\thi\n"));
+        assert!(!sniff("Chapter 1\tIntro\n"));
+        assert!(!sniff(""));
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{
+    config::Config,
+    formats::asr::{AsrWord, group_words, number, seconds_to_ms},
+    model::{Meta, Transcript},
+};
+
+/// Amazon Transcribe job output: `results.items`, or per-channel items under
+/// `results.channel_labels` when channel identification was on.
+pub fn looks_like(v: &Value) -> bool {
+    v.get("results")
+        .is_some_and(|r| r.get("items").is_some() || r.get("channel_labels").is_some())
+}
+
+pub fn parse(v: &Value, cfg: &Config) -> Result<Transcript> {
+    let results = &v["results"];
+
+    // Older outputs only carry speakers in `speaker_labels.segments`, keyed
+    // by the item timings.
+    let mut speakers: HashMap<(i64, i64), String> = HashMap::new();
+    if let Some(segments) = results
+        .pointer("/speaker_labels/segments")
+        .and_then(Value::as_array)
+    {
+        for item in segments
+            .iter()
+            .filter_map(|s| s.get("items").and_then(Value::as_array))
+            .flatten()
+        {
+            if let (Some(start), Some(end), Some(label)) = (
+                item.get("start_time").and_then(seconds_to_ms),
+                item.get("end_time").and_then(seconds_to_ms),
+                item.get("speaker_label").and_then(Value::as_str),
+            ) {
+                speakers.insert((start, end), label.to_string());
+            }
+        }
+    }
+
+    let mut cues = Vec::new();
+    match results
+        .pointer("/channel_labels/channels")
+        .and_then(Value::as_array)
+    {
+        Some(channels) => {
+            for ch in channels {
+                let label = ch.get("channel_label").and_then(Value::as_str);
+                let items = ch.get("items").unwrap_or(&Value::Null);
+                let words = parse_items(items, &speakers, label)?;
+                cues.extend(group_words(&words, &cfg.formats.json.asr));
+            }
+            cues.sort_by_key(|c| c.start_ms);
+        }
+        None => {
+            let words = parse_items(&results["items"], &speakers, None)?;
+            cues = group_words(&words, &cfg.formats.json.asr);
+        }
+    }
+
+    let language = results
+        .get("language_code")
+        .or_else(|| results.pointer("/language_identification/0/code"))
+        .or_else(|| v.get("language_code"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let mut meta = Meta {
+        language,
+        ..Meta::default()
+    };
+    if let Some(job) = v.get("jobName").and_then(Value::as_str) {
+        meta.tags.insert("job_name".to_string(), job.to_string());
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+fn parse_items(
+    items: &Value,
+    speakers: &HashMap<(i64, i64), String>,
+    channel: Option<&str>,
+) -> Result<Vec<AsrWord>> {
+    let items = items
+        .as_array()
+        .ok_or_else(|| anyhow!("AWS Transcribe: results.items must be an array"))?;
+    let mut words = Vec::with_capacity(items.len());
+    let mut last_end = 0;
+
+    for (i, item) in items.iter().enumerate() {
+        let text = item
+            .pointer("/alternatives/0/content")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("AWS Transcribe: item {i} has no alternatives"))?;
+        let punctuation = item.get("type").and_then(Value::as_str) == Some("punctuation");
+        let confidence = item
+            .pointer("/alternatives/0/confidence")
+            .and_then(number)
+            .filter(|_| !punctuation);
+        // Punctuation items carry no timings of their own.
+        let start_ms = item
+            .get("start_time")
+            .and_then(seconds_to_ms)
+            .unwrap_or(last_end);
+        let end_ms = item
+            .get("end_time")
+            .and_then(seconds_to_ms)
+            .unwrap_or(start_ms);
+        last_end = end_ms;
+
+        let speaker = item
+            .get("speaker_label")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| speakers.get(&(start_ms, end_ms)).cloned())
+            .or_else(|| channel.map(str::to_string));
+
+        words.push(AsrWord {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            speaker,
+            confidence,
+            punctuation,
+        });
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{json::parse_json, testutil::timings};
+    use serde_json::json;
+
+    fn word(start: &str, end: &str, content: &str, speaker: Option<&str>) -> Value {
+        let mut item = json!({
+            "type": "pronunciation",
+            "start_time": start,
+            "end_time": end,
+            "alternatives": [{"content": content, "confidence": "0.9"}]
+        });
+        if let Some(s) = speaker {
+            item["speaker_label"] = json!(s);
+        }
+        item
+    }
+
+    fn punct(content: &str) -> Value {
+        json!({"type": "punctuation", "alternatives": [{"content": content, "confidence": "0.0"}]})
+    }
+
+    #[test]
+    fn items_with_punctuation_and_speakers() {
+        let v = json!({
+            "jobName": "call-42",
+            "results": {
+                "language_code": "en-US",
+                "items": [
+                    word("0.0", "0.4", "Hello", Some("spk_0")),
+                    punct(","),
+                    word("0.5", "0.9", "there", Some("spk_0")),
+                    punct("."),
+                    word("1.2", "1.5", "Hi", Some("spk_1")),
+                ]
+            }
+        });
+        let cfg = Config::default();
+        let t = parse_json(&v.to_string(), &cfg).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 900, "Hello, there."), (1200, 1500, "Hi")]
+        );
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("spk_0"));
+        assert_eq!(t.cues[1].speaker.as_deref(), Some("spk_1"));
+        let words: Vec<_> = t.cues[0]
+            .words
+            .iter()
+            .map(|w| (w.text.as_str(), w.confidence))
+            .collect();
+        assert_eq!(words, vec![("Hello,", Some(0.9)), ("there.", Some(0.9))]);
+        assert_eq!(t.meta.language.as_deref(), Some("en-US"));
+        assert_eq!(
+            t.meta.tags.get("job_name").map(String::as_str),
+            Some("call-42")
+        );
+    }
+
+    #[test]
+    fn speakers_from_label_segments() {
+        let v = json!({
+            "results": {
+                "speaker_labels": {"segments": [{"items": [
+                    {"start_time": "0.0", "end_time": "0.4", "speaker_label": "spk_1"}
+                ]}]},
+                "items": [word("0.0", "0.4", "Hello", None), word("0.5", "0.9", "you", None)]
+            }
+        });
+        let t = parse(&v, &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(0, 400, "Hello"), (500, 900, "you")]);
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("spk_1"));
+        assert_eq!(t.cues[1].speaker, None);
+    }
+
+    #[test]
+    fn channels_are_merged_by_time() {
+        let v = json!({
+            "results": {"channel_labels": {"channels": [
+                {"channel_label": "ch_0", "items": [word("2.0", "2.5", "Later", None)]},
+                {"channel_label": "ch_1", "items": [word("0.0", "0.5", "First", None)]}
+            ]}}
+        });
+        let t = parse(&v, &Config::default()).unwrap();
+        let cues: Vec<_> = t
+            .cues
+            .iter()
+            .map(|c| (c.text.as_str(), c.speaker.as_deref()))
+            .collect();
+        assert_eq!(cues, vec![("First", Some("ch_1")), ("Later", Some("ch_0"))]);
+    }
+
+    #[test]
+    fn malformed_items_are_errors() {
+        let cfg = Config::default();
+        assert!(parse(&json!({"results": {"items": {}}}), &cfg).is_err());
+        assert!(parse(&json!({"results": {"items": [{"start_time": "0"}]}}), &cfg).is_err());
+        assert!(!looks_like(&json!({"results": []})));
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    formats::asr::{AsrWord, group_words, number, seconds_to_ms, speaker_label},
+    model::{Cue, Meta, Transcript},
+};
+
+/// Deepgram pre-recorded response: `results.channels[].alternatives[]`.
+pub fn looks_like(v: &Value) -> bool {
+    v.pointer("/results/channels").is_some_and(Value::is_array)
+}
+
+/// Prefers Deepgram's own segmentation: utterances, then paragraph
+/// sentences, then regrouped words.
+pub fn parse(v: &Value, cfg: &Config) -> Result<Transcript> {
+    let results = &v["results"];
+    let channels = results["channels"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Deepgram: results.channels must be an array"))?;
+    let multichannel = channels.len() > 1;
+
+    let mut cues = Vec::new();
+    if let Some(utterances) = results.get("utterances").and_then(Value::as_array) {
+        for (i, u) in utterances.iter().enumerate() {
+            let channel = u.get("channel").filter(|_| multichannel);
+            let words = parse_words(u.get("words"), channel)?;
+            cues.push(Cue {
+                start_ms: time(u, "start")
+                    .ok_or_else(|| anyhow!("Deepgram: utterance {i} missing start"))?,
+                end_ms: time(u, "end")
+                    .ok_or_else(|| anyhow!("Deepgram: utterance {i} missing end"))?,
+                text: u
+                    .get("transcript")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                speaker: speaker_label(u.get("speaker"), channel),
+                words: words.iter().map(AsrWord::to_word).collect(),
+            });
+        }
+    } else {
+        for (i, ch) in channels.iter().enumerate() {
+            let alt = ch
+                .pointer("/alternatives/0")
+                .ok_or_else(|| anyhow!("Deepgram: channel {i} has no alternatives"))?;
+            let index = Value::from(i);
+            let channel = multichannel.then_some(&index);
+            let words = parse_words(alt.get("words"), channel)?;
+
+            match alt
+                .pointer("/paragraphs/paragraphs")
+                .and_then(Value::as_array)
+            {
+                Some(paragraphs) => {
+                    for p in paragraphs {
+                        let speaker = speaker_label(p.get("speaker"), channel);
+                        for s in p
+                            .get("sentences")
+                            .and_then(Value::as_array)
+                            .into_iter()
+                            .flatten()
+                        {
+                            let (Some(start_ms), Some(end_ms)) = (time(s, "start"), time(s, "end"))
+                            else {
+                                continue;
+                            };
+                            cues.push(Cue {
+                                start_ms,
+                                end_ms,
+                                text: s
+                                    .get("text")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or("")
+                                    .to_string(),
+                                speaker: speaker.clone(),
+                                words: words
+                                    .iter()
+                                    .filter(|w| w.start_ms >= start_ms && w.end_ms <= end_ms)
+                                    .map(AsrWord::to_word)
+                                    .collect(),
+                            });
+                        }
+                    }
+                }
+                None => cues.extend(group_words(&words, &cfg.formats.json.asr)),
+            }
+        }
+        cues.sort_by_key(|c| c.start_ms);
+    }
+
+    let mut meta = Meta {
+        language: channels
+            .first()
+            .and_then(|c| c.get("detected_language"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        ..Meta::default()
+    };
+    if let Some(id) = v.pointer("/metadata/request_id").and_then(Value::as_str) {
+        meta.tags.insert("request_id".to_string(), id.to_string());
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+fn time(v: &Value, key: &str) -> Option<i64> {
+    v.get(key).and_then(seconds_to_ms)
+}
+
+fn parse_words(words: Option<&Value>, channel: Option<&Value>) -> Result<Vec<AsrWord>> {
+    let Some(words) = words else {
+        return Ok(Vec::new());
+    };
+    let words = words
+        .as_array()
+        .ok_or_else(|| anyhow!("Deepgram: words must be an array"))?;
+    let mut out = Vec::with_capacity(words.len());
+    for (i, w) in words.iter().enumerate() {
+        let text = w
+            .get("punctuated_word")
+            .or_else(|| w.get("word"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Deepgram: word {i} has no text"))?;
+        out.push(AsrWord {
+            start_ms: time(w, "start")
+                .ok_or_else(|| anyhow!("Deepgram: word {i} missing start"))?,
+            end_ms: time(w, "end").ok_or_else(|| anyhow!("Deepgram: word {i} missing end"))?,
+            text: text.to_string(),
+            speaker: speaker_label(w.get("speaker"), channel),
+            confidence: w.get("confidence").and_then(number),
+            punctuation: false,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{json::parse_json, testutil::timings};
+    use serde_json::json;
+
+    fn words() -> Value {
+        json!([
+            {"word": "hello", "punctuated_word": "Hello,", "start": 0.0, "end": 0.4, "confidence": 0.98, "speaker": 0},
+            {"word": "there", "punctuated_word": "there.", "start": 0.5, "end": 0.9, "confidence": 0.9, "speaker": 0},
+            {"word": "hi", "start": 1.2, "end": 1.5, "confidence": 0.8, "speaker": 1}
+        ])
+    }
+
+    #[test]
+    fn utterances_win() {
+        let v = json!({
+            "metadata": {"request_id": "req-1"},
+            "results": {
+                "channels": [{"detected_language": "en", "alternatives": [{"words": words()}]}],
+                "utterances": [
+                    {"start": 0.0, "end": 0.9, "transcript": "Hello, there.", "speaker": 0, "channel": 0,
+                     "words": words().as_array().unwrap()[..2]},
+                    {"start": 1.2, "end": 1.5, "transcript": "hi", "speaker": 1, "channel": 0, "words": []}
+                ]
+            }
+        });
+        let t = parse_json(&v.to_string(), &Config::default()).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 900, "Hello, there."), (1200, 1500, "hi")]
+        );
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("Speaker 0"));
+        assert_eq!(t.cues[0].words[0].confidence, Some(0.98));
+        assert_eq!(t.meta.language.as_deref(), Some("en"));
+        assert_eq!(
+            t.meta.tags.get("request_id").map(String::as_str),
+            Some("req-1")
+        );
+    }
+
+    #[test]
+    fn paragraph_sentences_then_words() {
+        let paragraphs = json!({"paragraphs": [{"speaker": 0, "sentences": [
+            {"text": "Hello, there.", "start": 0.0, "end": 0.9},
+            {"text": "No times"}
+        ]}]});
+        let v = json!({"results": {"channels": [{"alternatives": [
+            {"words": words(), "paragraphs": paragraphs}
+        ]}]}});
+        let t = parse(&v, &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(0, 900, "Hello, there.")]);
+        assert_eq!(t.cues[0].words.len(), 2);
+
+        let v = json!({"results": {"channels": [{"alternatives": [{"words": words()}]}]}});
+        let t = parse(&v, &Config::default()).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 900, "Hello, there."), (1200, 1500, "hi")]
+        );
+        assert_eq!(t.cues[1].speaker.as_deref(), Some("Speaker 1"));
+    }
+
+    #[test]
+    fn multichannel_labels_channels() {
+        let v = json!({"results": {"channels": [
+            {"alternatives": [{"words": [{"word": "late", "start": 2.0, "end": 2.5}]}]},
+            {"alternatives": [{"words": [{"word": "early", "start": 0.0, "end": 0.5, "speaker": 0}]}]}
+        ]}});
+        let t = parse(&v, &Config::default()).unwrap();
+        let cues: Vec<_> = t
+            .cues
+            .iter()
+            .map(|c| (c.text.as_str(), c.speaker.as_deref()))
+            .collect();
+        assert_eq!(
+            cues,
+            vec![
+                ("early", Some("Channel 1 Speaker 0")),
+                ("late", Some("Channel 0"))
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_responses_are_errors() {
+        let cfg = Config::default();
+        assert!(parse(&json!({"results": {"channels": [{}]}}), &cfg).is_err());
+        let no_start = json!({"results": {"channels": [{"alternatives": [{"words": [{"word": "x", "end": 1}]}]}]}});
+        assert!(parse(&no_start, &cfg).is_err());
+    }
+}
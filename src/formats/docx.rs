@@ -0,0 +1,423 @@
+use anyhow::Result;
+use std::io::Write;
+
+use crate::{
+    config::{Config, DocxLayout},
+    formats::{
+        registry::{CollectSink, CueSink, FormatEntry, FormatWriter},
+        time::utc_now,
+        xml::escape,
+        zip::ZipWriter,
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct Docx;
+
+impl FormatWriter for Docx {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_docx(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("docx", &["docx"])
+        .config_section("docx")
+        .writer(Docx)
+}
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// Table column widths in twentieths of a point, filling a Letter page with
+/// one-inch margins.
+const COLUMNS: [u32; 3] = [1500, 1800, 6060];
+
+/// A Word document built from the parts Word needs and nothing else. Every
+/// speaker gets a character style of its own (`Speaker: <name>`), so one
+/// speaker's formatting can be changed throughout from Word's style pane.
+fn write_docx(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.docx;
+    let title = c.title.as_deref().or(t.meta.title.as_deref());
+
+    let mut speakers: Vec<&str> = Vec::new();
+    for s in t.cues.iter().filter_map(|c| c.speaker.as_deref()) {
+        if !speakers.contains(&s) {
+            speakers.push(s);
+        }
+    }
+
+    let mut body = String::new();
+    if let Some(title) = title {
+        body.push_str(&format!(
+            "<w:p><w:pPr><w:pStyle w:val=\"Title\"/></w:pPr>{}</w:p>",
+            run(title, None)
+        ));
+    }
+    match c.layout {
+        DocxLayout::Table => table(&mut body, t, cfg, &speakers),
+        DocxLayout::Paragraph => paragraphs(&mut body, t, cfg, &speakers),
+    }
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:document xmlns:w=\"{W_NS}\"><w:body>{body}<w:sectPr><w:pgSz w:w=\"12240\" w:h=\"15840\"/><w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" w:header=\"720\" w:footer=\"720\" w:gutter=\"0\"/></w:sectPr></w:body></w:document>"
+    );
+
+    let mut zip = ZipWriter::new(out);
+    zip.add("[Content_Types].xml", CONTENT_TYPES.as_bytes())?;
+    zip.add("_rels/.rels", PACKAGE_RELS.as_bytes())?;
+    zip.add("docProps/core.xml", core_properties(t, title).as_bytes())?;
+    zip.add("word/_rels/document.xml.rels", DOCUMENT_RELS.as_bytes())?;
+    zip.add("word/styles.xml", styles(cfg, &speakers).as_bytes())?;
+    zip.add("word/document.xml", document.as_bytes())?;
+    zip.finish()
+}
+
+fn table(body: &mut String, t: &Transcript, cfg: &Config, speakers: &[&str]) {
+    let offset = cfg.policy.timestamp_offset_ms;
+    let border = "w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"BFBFBF\"";
+    body.push_str(&format!(
+        "<w:tbl><w:tblPr><w:tblW w:w=\"0\" w:type=\"auto\"/><w:tblBorders><w:top {border}/><w:left {border}/><w:bottom {border}/><w:right {border}/><w:insideH {border}/><w:insideV {border}/></w:tblBorders><w:tblLayout w:type=\"fixed\"/></w:tblPr><w:tblGrid>"
+    ));
+    for w in COLUMNS {
+        body.push_str(&format!("<w:gridCol w:w=\"{w}\"/>"));
+    }
+    body.push_str("</w:tblGrid>");
+
+    // The header row repeats on every page.
+    body.push_str("<w:tr><w:trPr><w:tblHeader/></w:trPr>");
+    for (label, w) in ["Timecode", "Speaker", "Text"].iter().zip(COLUMNS) {
+        body.push_str(&cell(
+            w,
+            &format!("<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>{label}</w:t></w:r></w:p>"),
+        ));
+    }
+    body.push_str("</w:tr>");
+
+    for cue in &t.cues {
+        body.push_str("<w:tr><w:trPr><w:cantSplit/></w:trPr>");
+        let timecode = run(
+            &timecode((cue.start_ms + offset).max(0), cfg),
+            Some("Timecode"),
+        );
+        body.push_str(&cell(COLUMNS[0], &format!("<w:p>{timecode}</w:p>")));
+        let speaker = cue
+            .speaker
+            .as_deref()
+            .map(|s| run(s, Some(&speaker_style_id(s, speakers))))
+            .unwrap_or_default();
+        body.push_str(&cell(COLUMNS[1], &format!("<w:p>{speaker}</w:p>")));
+        body.push_str(&cell(
+            COLUMNS[2],
+            &format!("<w:p>{}</w:p>", text_runs(&cue.text)),
+        ));
+        body.push_str("</w:tr>");
+    }
+    // Word expects a paragraph between a table and the section properties.
+    body.push_str("</w:tbl><w:p/>");
+}
+
+fn cell(width: u32, content: &str) -> String {
+    format!("<w:tc><w:tcPr><w:tcW w:w=\"{width}\" w:type=\"dxa\"/></w:tcPr>{content}</w:tc>")
+}
+
+/// Cues of one speaker run together until the speaker changes or a pause of
+/// `paragraph_gap_ms`; a new speaker's paragraph opens with their name.
+fn paragraphs(body: &mut String, t: &Transcript, cfg: &Config, speakers: &[&str]) {
+    let c = &cfg.formats.docx;
+    let offset = cfg.policy.timestamp_offset_ms;
+    let mut prev: Option<&Cue> = None;
+    for cue in &t.cues {
+        let start_ms = (cue.start_ms + offset).max(0);
+        let new_turn = prev.is_none_or(|p| p.speaker != cue.speaker);
+        let new_para =
+            new_turn || prev.is_some_and(|p| cue.start_ms - p.end_ms >= c.paragraph_gap_ms);
+        if new_para {
+            if prev.is_some() {
+                body.push_str("</w:p>");
+            }
+            body.push_str("<w:p>");
+            if c.timecodes {
+                body.push_str(&run(
+                    &format!("[{}] ", timecode(start_ms, cfg)),
+                    Some("Timecode"),
+                ));
+            }
+            if let Some(s) = cue.speaker.as_deref()
+                && new_turn
+            {
+                body.push_str(&run(
+                    &format!("{s}: "),
+                    Some(&speaker_style_id(s, speakers)),
+                ));
+            }
+        } else {
+            body.push_str(&run(" ", None));
+        }
+        body.push_str(&text_runs(&cue.text));
+        prev = Some(cue);
+    }
+    if prev.is_some() {
+        body.push_str("</w:p>");
+    }
+}
+
+fn run(text: &str, style: Option<&str>) -> String {
+    let props = style
+        .map(|s| format!("<w:rPr><w:rStyle w:val=\"{s}\"/></w:rPr>"))
+        .unwrap_or_default();
+    format!(
+        "<w:r>{props}<w:t xml:space=\"preserve\">{}</w:t></w:r>",
+        escape(text)
+    )
+}
+
+/// Cue lines joined by line breaks within one paragraph.
+fn text_runs(text: &str) -> String {
+    let lines: Vec<String> = text.lines().map(|l| escape(l.trim())).collect();
+    format!(
+        "<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>",
+        lines.join("</w:t><w:br/><w:t xml:space=\"preserve\">")
+    )
+}
+
+fn speaker_style_id(speaker: &str, speakers: &[&str]) -> String {
+    let n = speakers.iter().position(|s| *s == speaker).unwrap_or(0);
+    format!("Speaker{}", n + 1)
+}
+
+/// `hh:mm:ss`, or `hh:mm:ss.mmm` with `timecode_ms`.
+fn timecode(ms: i64, cfg: &Config) -> String {
+    let secs = ms / 1000;
+    let hms = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if cfg.formats.docx.timecode_ms {
+        format!("{hms}.{:03}", ms % 1000)
+    } else {
+        hms
+    }
+}
+
+fn styles(cfg: &Config, speakers: &[&str]) -> String {
+    let c = &cfg.formats.docx;
+    let font = escape(&c.font);
+    let half_points = (c.font_size_pt * 2.0).round() as i64;
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:styles xmlns:w=\"{W_NS}\"><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii=\"{font}\" w:hAnsi=\"{font}\" w:eastAsia=\"{font}\" w:cs=\"{font}\"/><w:sz w:val=\"{half_points}\"/><w:szCs w:val=\"{half_points}\"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after=\"120\" w:line=\"276\" w:lineRule=\"auto\"/></w:pPr></w:pPrDefault></w:docDefaults>"
+    );
+    out.push_str("<w:style w:type=\"paragraph\" w:default=\"1\" w:styleId=\"Normal\"><w:name w:val=\"Normal\"/><w:qFormat/></w:style>");
+    out.push_str(&format!(
+        "<w:style w:type=\"paragraph\" w:styleId=\"Title\"><w:name w:val=\"Title\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:qFormat/><w:pPr><w:spacing w:after=\"240\"/></w:pPr><w:rPr><w:b/><w:sz w:val=\"{}\"/><w:szCs w:val=\"{}\"/></w:rPr></w:style>",
+        half_points * 2,
+        half_points * 2
+    ));
+    out.push_str("<w:style w:type=\"character\" w:styleId=\"Timecode\"><w:name w:val=\"Timecode\"/><w:rPr><w:color w:val=\"7F7F7F\"/></w:rPr></w:style>");
+    for (i, s) in speakers.iter().enumerate() {
+        let color = if c.speaker_colors.is_empty() {
+            "000000".to_string()
+        } else {
+            escape(c.speaker_colors[i % c.speaker_colors.len()].trim_start_matches('#'))
+        };
+        out.push_str(&format!(
+            "<w:style w:type=\"character\" w:customStyle=\"1\" w:styleId=\"Speaker{}\"><w:name w:val=\"Speaker: {}\"/><w:rPr><w:b/><w:color w:val=\"{color}\"/></w:rPr></w:style>",
+            i + 1,
+            escape(s)
+        ));
+    }
+    out.push_str("</w:styles>");
+    out
+}
+
+fn core_properties(t: &Transcript, title: Option<&str>) -> String {
+    let (y, mo, d, secs) = utc_now();
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">",
+    );
+    if let Some(title) = title {
+        out.push_str(&format!("<dc:title>{}</dc:title>", escape(title)));
+    }
+    if let Some(lang) = &t.meta.language {
+        out.push_str(&format!("<dc:language>{}</dc:language>", escape(lang)));
+    }
+    out.push_str(&format!(
+        "<dcterms:created xsi:type=\"dcterms:W3CDTF\">{y:04}-{mo:02}-{d:02}T{:02}:{:02}:{:02}Z</dcterms:created></cp:coreProperties>",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{
+        testutil::{cue, render_bytes, spoken, unzip},
+        time::parse_time_to_ms,
+        xml::{Element, parse_document},
+    };
+
+    fn parts(t: &Transcript, cfg: &Config) -> Vec<(String, String)> {
+        unzip(&render_bytes(&Docx, t, cfg))
+            .into_iter()
+            .map(|(name, data)| (name, String::from_utf8(data).unwrap()))
+            .collect()
+    }
+
+    fn part<'p>(parts: &'p [(String, String)], name: &str) -> &'p str {
+        &parts.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    fn body(doc: &str) -> Element {
+        parse_document(doc).unwrap().child("body").unwrap().clone()
+    }
+
+    #[test]
+    fn package_has_every_part_as_well_formed_xml() {
+        let mut t = Transcript::new(vec![cue(0, 1000, "x")]);
+        t.meta.title = Some("Deposition & notes".to_string());
+        t.meta.language = Some("en-US".to_string());
+        let files = parts(&t, &Config::default());
+        let names: Vec<_> = files.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "[Content_Types].xml",
+                "_rels/.rels",
+                "docProps/core.xml",
+                "word/_rels/document.xml.rels",
+                "word/styles.xml",
+                "word/document.xml",
+            ]
+        );
+        for (name, xml) in &files {
+            assert!(parse_document(xml).is_ok(), "{name}");
+        }
+        let core = part(&files, "docProps/core.xml");
+        assert!(core.contains("<dc:title>Deposition &amp; notes</dc:title>"));
+        assert!(core.contains("<dc:language>en-US</dc:language>"));
+        let doc = body(part(&files, "word/document.xml"));
+        let title = doc.child("p").unwrap();
+        assert_eq!(title.text(), "Deposition & notes");
+    }
+
+    #[test]
+    fn table_rows_roundtrip() {
+        let mut cfg = Config::default();
+        cfg.formats.docx.timecode_ms = true;
+        let t = Transcript::new(vec![
+            spoken(1500, 2000, "Ann", "Hello <there>"),
+            cue(2000, 3000, "Narration"),
+            spoken(3_723_004, 3_724_000, "Bob", "Later"),
+        ]);
+        let files = parts(&t, &cfg);
+        let doc = body(part(&files, "word/document.xml"));
+        let rows: Vec<Vec<String>> = doc
+            .child("tbl")
+            .unwrap()
+            .children_named("tr")
+            .map(|tr| tr.children_named("tc").map(Element::text).collect())
+            .collect();
+        assert_eq!(rows[0], vec!["Timecode", "Speaker", "Text"]);
+        let back: Vec<_> = rows[1..]
+            .iter()
+            .map(|r| {
+                (
+                    parse_time_to_ms(&r[0]).unwrap(),
+                    r[1].as_str(),
+                    r[2].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            back,
+            vec![
+                (1500, "Ann", "Hello <there>"),
+                (2000, "", "Narration"),
+                (3_723_004, "Bob", "Later"),
+            ]
+        );
+    }
+
+    #[test]
+    fn speakers_get_styles_in_order_of_appearance() {
+        let mut cfg = Config::default();
+        cfg.formats.docx.speaker_colors = vec!["#111111".to_string(), "222222".to_string()];
+        let t = Transcript::new(vec![
+            spoken(0, 1, "Bob", "a"),
+            spoken(1, 2, "Ann", "b"),
+            spoken(2, 3, "Bob", "c"),
+            spoken(3, 4, "Cy & Co", "d"),
+        ]);
+        let files = parts(&t, &cfg);
+        let styles = part(&files, "word/styles.xml");
+        assert!(styles.contains("w:styleId=\"Speaker1\"><w:name w:val=\"Speaker: Bob\"/><w:rPr><w:b/><w:color w:val=\"111111\"/>"));
+        assert!(styles.contains("w:styleId=\"Speaker2\"><w:name w:val=\"Speaker: Ann\"/><w:rPr><w:b/><w:color w:val=\"222222\"/>"));
+        assert!(styles.contains("w:styleId=\"Speaker3\"><w:name w:val=\"Speaker: Cy &amp; Co\"/><w:rPr><w:b/><w:color w:val=\"111111\"/>"));
+        assert!(styles.contains("<w:sz w:val=\"22\"/>"));
+        let doc = part(&files, "word/document.xml");
+        assert_eq!(doc.matches("<w:rStyle w:val=\"Speaker1\"/>").count(), 2);
+
+        cfg.formats.docx.speaker_colors.clear();
+        let files = parts(&t, &cfg);
+        assert!(part(&files, "word/styles.xml").contains("<w:color w:val=\"000000\"/>"));
+    }
+
+    #[test]
+    fn paragraph_layout_breaks_on_speaker_and_pause() {
+        let mut cfg = Config::default();
+        cfg.formats.docx.layout = DocxLayout::Paragraph;
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "One"),
+            spoken(1000, 2000, "Ann", "two\nlines"),
+            spoken(5000, 6000, "Ann", "Three"),
+            spoken(6000, 7000, "Bob", "Four"),
+        ]);
+        let files = parts(&t, &cfg);
+        let xml = part(&files, "word/document.xml");
+        assert!(xml.contains("two</w:t><w:br/><w:t xml:space=\"preserve\">lines"));
+        let paragraphs: Vec<String> = body(xml)
+            .children_named("p")
+            .map(Element::text)
+            .filter(|p| !p.is_empty())
+            .collect();
+        assert_eq!(
+            paragraphs,
+            vec![
+                "[00:00:00] Ann: One twolines",
+                "[00:00:05] Three",
+                "[00:00:06] Bob: Four",
+            ]
+        );
+
+        cfg.formats.docx.timecodes = false;
+        cfg.policy.timestamp_offset_ms = 500;
+        let files = parts(&t, &cfg);
+        let first = body(part(&files, "word/document.xml"))
+            .child("p")
+            .unwrap()
+            .text();
+        assert_eq!(first, "Ann: One twolines");
+    }
+
+    #[test]
+    fn timecodes() {
+        let mut cfg = Config::default();
+        assert_eq!(timecode(3_723_456, &cfg), "01:02:03");
+        cfg.formats.docx.timecode_ms = true;
+        assert_eq!(timecode(3_723_456, &cfg), "01:02:03.456");
+    }
+}
@@ -0,0 +1,459 @@
+use anyhow::{Result, anyhow};
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    config::Config,
+    formats::{
+        registry::{CollectSink, CueSink, FormatEntry, FormatReader, FormatWriter},
+        time::utc_now,
+        tracks::SubtitleTrack,
+        xml::{Element, escape, parse_document},
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct Eaf;
+
+impl FormatReader for Eaf {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_eaf(input, cfg)
+    }
+}
+
+impl FormatWriter for Eaf {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_eaf(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("eaf", &["eaf"])
+        .config_section("eaf")
+        .sniff(|raw| raw.contains("<ANNOTATION_DOCUMENT"))
+        .reader(Eaf)
+        .writer(Eaf)
+}
+
+/// A time-aligned ELAN tier.
+#[derive(Debug, Clone)]
+pub struct EafTier {
+    pub id: String,
+    pub participant: Option<String>,
+    pub annotations: Vec<(i64, i64, String)>,
+}
+
+/// Reads the tier chosen by `[formats.eaf] tier` or `track`, or all
+/// time-aligned tiers merged with the participant (or tier ID) as speaker.
+pub fn parse_eaf(input: &str, cfg: &Config) -> Result<Transcript> {
+    let (tiers, meta) = parse_tiers(input)?;
+    let c = &cfg.formats.eaf;
+    let selected: Vec<&EafTier> = match (&c.tier, c.track) {
+        (Some(id), _) => vec![tiers.iter().find(|t| &t.id == id).ok_or_else(|| {
+            anyhow!(
+                "EAF has no time-aligned tier '{id}'; available: {}",
+                tiers
+                    .iter()
+                    .map(|t| t.id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?],
+        (None, Some(n)) => vec![
+            tiers
+                .get((n as usize).wrapping_sub(1))
+                .ok_or_else(|| anyhow!("EAF has no time-aligned tier {n}; see list-tracks"))?,
+        ],
+        (None, None) => tiers.iter().collect(),
+    };
+    let merge = selected.len() > 1;
+
+    let mut cues: Vec<Cue> = selected
+        .iter()
+        .flat_map(|tier| {
+            let speaker = tier.participant.clone().unwrap_or_else(|| tier.id.clone());
+            tier.annotations
+                .iter()
+                .map(move |(start_ms, end_ms, text)| Cue {
+                    start_ms: *start_ms,
+                    end_ms: *end_ms,
+                    text: text.clone(),
+                    speaker: merge.then(|| speaker.clone()),
+                    words: Vec::new(),
+                })
+        })
+        .collect();
+    cues.sort_by_key(|c| c.start_ms);
+    Ok(Transcript { cues, meta })
+}
+
+/// Time-aligned tiers as tracks for `list-tracks`, numbered for `--track`.
+pub fn list_tiers(input: &str) -> Result<Vec<SubtitleTrack>> {
+    Ok(parse_tiers(input)?
+        .0
+        .into_iter()
+        .enumerate()
+        .map(|(i, tier)| SubtitleTrack {
+            number: i as u64 + 1,
+            codec: "tier".to_string(),
+            name: Some(match tier.participant {
+                Some(p) if p != tier.id => format!("{} [{p}]", tier.id),
+                _ => tier.id,
+            }),
+            text: true,
+            ..SubtitleTrack::default()
+        })
+        .collect())
+}
+
+/// Tiers with `ALIGNABLE_ANNOTATION`s; symbolic (reference) tiers such as
+/// translations hang off another tier and are skipped.
+fn parse_tiers(input: &str) -> Result<(Vec<EafTier>, Meta)> {
+    let doc = parse_document(input)?;
+    if !doc.name.eq_ignore_ascii_case("ANNOTATION_DOCUMENT") {
+        return Err(anyhow!("not an ELAN annotation document"));
+    }
+
+    let mut meta = Meta::default();
+    if let Some(header) = doc.child("HEADER")
+        && let Some(url) = header
+            .child("MEDIA_DESCRIPTOR")
+            .and_then(|m| m.attr("MEDIA_URL"))
+    {
+        meta.tags.insert("media_url".to_string(), url.to_string());
+    }
+    if let Some(lang) = doc.child("LANGUAGE").and_then(|l| l.attr("LANG_ID")) {
+        meta.language = Some(lang.to_string());
+    }
+    let ms_per_unit = match doc
+        .child("HEADER")
+        .and_then(|h| h.attr("TIME_UNITS"))
+        .unwrap_or("milliseconds")
+    {
+        "milliseconds" => 1.0,
+        other => return Err(anyhow!("EAF: unsupported TIME_UNITS '{other}'")),
+    };
+
+    let mut slots: HashMap<&str, i64> = HashMap::new();
+    if let Some(order) = doc.child("TIME_ORDER") {
+        for slot in order.children_named("TIME_SLOT") {
+            if let (Some(id), Some(value)) = (slot.attr("TIME_SLOT_ID"), slot.attr("TIME_VALUE")) {
+                let value: f64 = value
+                    .parse()
+                    .map_err(|_| anyhow!("EAF: bad TIME_VALUE '{value}' on {id}"))?;
+                slots.insert(id, (value * ms_per_unit).round() as i64);
+            }
+        }
+    }
+
+    let mut tiers = Vec::new();
+    let mut unaligned = 0;
+    for tier in doc.children_named("TIER") {
+        let id = tier.attr("TIER_ID").unwrap_or("").to_string();
+        let mut annotations = Vec::new();
+        let mut alignable = false;
+        for a in tier
+            .children_named("ANNOTATION")
+            .filter_map(|a| a.child("ALIGNABLE_ANNOTATION"))
+        {
+            alignable = true;
+            let slot = |attr: &str| a.attr(attr).and_then(|s| slots.get(s)).copied();
+            let (Some(start_ms), Some(end_ms)) = (slot("TIME_SLOT_REF1"), slot("TIME_SLOT_REF2"))
+            else {
+                unaligned += 1;
+                continue;
+            };
+            let text = annotation_text(a);
+            if !text.is_empty() {
+                annotations.push((start_ms, end_ms, text));
+            }
+        }
+        if alignable {
+            tiers.push(EafTier {
+                id,
+                participant: tier
+                    .attr("PARTICIPANT")
+                    .filter(|p| !p.trim().is_empty())
+                    .map(str::to_string),
+                annotations,
+            });
+        }
+    }
+    if unaligned > 0 {
+        tracing::warn!(
+            annotations = unaligned,
+            "EAF: skipping annotations with unaligned time slots"
+        );
+    }
+    Ok((tiers, meta))
+}
+
+fn annotation_text(a: &Element) -> String {
+    a.child("ANNOTATION_VALUE")
+        .map(|v| v.text().trim().to_string())
+        .unwrap_or_default()
+}
+
+fn write_eaf(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.eaf;
+    let offset = cfg.policy.timestamp_offset_ms;
+
+    // Tiers in order of first appearance, one per speaker when configured.
+    let mut tiers: Vec<(String, Vec<&Cue>)> = Vec::new();
+    for cue in &t.cues {
+        let name = match &cue.speaker {
+            Some(s) if c.tier_per_speaker => s.clone(),
+            _ => c.default_tier.clone(),
+        };
+        match tiers.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, list)) => list.push(cue),
+            None => tiers.push((name, vec![cue])),
+        }
+    }
+    if tiers.is_empty() {
+        tiers.push((c.default_tier.clone(), Vec::new()));
+    }
+
+    let (y, mo, d, secs) = utc_now();
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<ANNOTATION_DOCUMENT AUTHOR=\"{}\" DATE=\"{y:04}-{mo:02}-{d:02}T{:02}:{:02}:{:02}+00:00\" FORMAT=\"3.0\" VERSION=\"3.0\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:noNamespaceSchemaLocation=\"http://www.mpi.nl/tools/elan/EAFv3.0.xsd\">",
+        escape(&c.author),
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )?;
+    writeln!(
+        out,
+        "    <HEADER MEDIA_FILE=\"\" TIME_UNITS=\"milliseconds\">"
+    )?;
+    let media_url = c
+        .media_url
+        .as_deref()
+        .or_else(|| t.meta.tags.get("media_url").map(String::as_str));
+    if let Some(url) = media_url {
+        writeln!(
+            out,
+            "        <MEDIA_DESCRIPTOR MEDIA_URL=\"{}\" MIME_TYPE=\"{}\"/>",
+            escape(url),
+            mime_type(url)
+        )?;
+    }
+    let count: usize = tiers.iter().map(|(_, cues)| cues.len()).sum();
+    writeln!(
+        out,
+        "        <PROPERTY NAME=\"lastUsedAnnotationId\">{count}</PROPERTY>"
+    )?;
+    writeln!(out, "    </HEADER>")?;
+
+    writeln!(out, "    <TIME_ORDER>")?;
+    let mut slot = 0;
+    for (_, cues) in &tiers {
+        for cue in cues {
+            for ms in [cue.start_ms, cue.end_ms] {
+                slot += 1;
+                writeln!(
+                    out,
+                    "        <TIME_SLOT TIME_SLOT_ID=\"ts{slot}\" TIME_VALUE=\"{}\"/>",
+                    (ms + offset).max(0)
+                )?;
+            }
+        }
+    }
+    writeln!(out, "    </TIME_ORDER>")?;
+
+    let mut slot = 0;
+    let mut id = 0;
+    for (name, cues) in &tiers {
+        let participant = if name == &c.default_tier { "" } else { name };
+        writeln!(
+            out,
+            "    <TIER LINGUISTIC_TYPE_REF=\"default-lt\" PARTICIPANT=\"{}\" TIER_ID=\"{}\">",
+            escape(participant),
+            escape(name)
+        )?;
+        for cue in cues {
+            id += 1;
+            slot += 2;
+            writeln!(out, "        <ANNOTATION>")?;
+            writeln!(
+                out,
+                "            <ALIGNABLE_ANNOTATION ANNOTATION_ID=\"a{id}\" TIME_SLOT_REF1=\"ts{}\" TIME_SLOT_REF2=\"ts{slot}\">",
+                slot - 1
+            )?;
+            writeln!(
+                out,
+                "                <ANNOTATION_VALUE>{}</ANNOTATION_VALUE>",
+                escape(&cue.text)
+            )?;
+            writeln!(out, "            </ALIGNABLE_ANNOTATION>")?;
+            writeln!(out, "        </ANNOTATION>")?;
+        }
+        writeln!(out, "    </TIER>")?;
+    }
+
+    writeln!(
+        out,
+        "    <LINGUISTIC_TYPE GRAPHIC_REFERENCES=\"false\" LINGUISTIC_TYPE_ID=\"default-lt\" TIME_ALIGNABLE=\"true\"/>"
+    )?;
+    if let Some(lang) = &t.meta.language {
+        writeln!(out, "    <LANGUAGE LANG_ID=\"{}\"/>", escape(lang))?;
+    }
+    for (stereotype, description) in [
+        (
+            "Time_Subdivision",
+            "Time subdivision of parent annotation's time interval, no time gaps allowed within this interval",
+        ),
+        (
+            "Symbolic_Subdivision",
+            "Symbolic subdivision of a parent annotation. Annotations refering to the same parent are ordered",
+        ),
+        (
+            "Symbolic_Association",
+            "1-1 association with a parent annotation",
+        ),
+        (
+            "Included_In",
+            "Time alignable annotations within the parent annotation's time interval, gaps are allowed",
+        ),
+    ] {
+        writeln!(
+            out,
+            "    <CONSTRAINT DESCRIPTION=\"{description}\" STEREOTYPE=\"{stereotype}\"/>"
+        )?;
+    }
+    writeln!(out, "</ANNOTATION_DOCUMENT>")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn mime_type(url: &str) -> &'static str {
+    let ext = url.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "wav" => "audio/x-wav",
+        "mp3" => "audio/mpeg",
+        "mp4" | "m4v" => "video/mp4",
+        "mpg" | "mpeg" => "video/mpeg",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, spoken, timings};
+
+    const SYMBOLIC: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ANNOTATION_DOCUMENT>
+  <HEADER TIME_UNITS="milliseconds"/>
+  <TIME_ORDER>
+    <TIME_SLOT TIME_SLOT_ID="ts1" TIME_VALUE="100"/>
+    <TIME_SLOT TIME_SLOT_ID="ts2" TIME_VALUE="900"/>
+    <TIME_SLOT TIME_SLOT_ID="ts3"/>
+  </TIME_ORDER>
+  <TIER TIER_ID="utt" PARTICIPANT=" ">
+    <ANNOTATION><ALIGNABLE_ANNOTATION ANNOTATION_ID="a1" TIME_SLOT_REF1="ts1" TIME_SLOT_REF2="ts2">
+      <ANNOTATION_VALUE> hola </ANNOTATION_VALUE></ALIGNABLE_ANNOTATION></ANNOTATION>
+    <ANNOTATION><ALIGNABLE_ANNOTATION ANNOTATION_ID="a2" TIME_SLOT_REF1="ts2" TIME_SLOT_REF2="ts3">
+      <ANNOTATION_VALUE>unaligned</ANNOTATION_VALUE></ALIGNABLE_ANNOTATION></ANNOTATION>
+  </TIER>
+  <TIER TIER_ID="gloss" PARENT_REF="utt">
+    <ANNOTATION><REF_ANNOTATION ANNOTATION_ID="a3" ANNOTATION_REF="a1">
+      <ANNOTATION_VALUE>hello</ANNOTATION_VALUE></REF_ANNOTATION></ANNOTATION>
+  </TIER>
+</ANNOTATION_DOCUMENT>"#;
+
+    #[test]
+    fn roundtrip_with_participants_media_and_language() {
+        let mut cfg = Config::default();
+        cfg.formats.eaf.author = "A & B".to_string();
+        let mut t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "Hi <there>"),
+            cue(1000, 2000, "narration"),
+            spoken(1500, 2500, "Ann", "Bye"),
+        ]);
+        t.meta.language = Some("en".to_string());
+        t.meta
+            .tags
+            .insert("media_url".to_string(), "file:///a.wav".to_string());
+
+        let out = render(&Eaf, &t, &cfg);
+        assert!(out.contains("AUTHOR=\"A &amp; B\""));
+        assert!(out.contains("MEDIA_URL=\"file:///a.wav\" MIME_TYPE=\"audio/x-wav\""));
+        assert!(out.contains("PARTICIPANT=\"Ann\" TIER_ID=\"Ann\""));
+        assert!(out.contains("PARTICIPANT=\"\" TIER_ID=\"transcript\""));
+        assert!(out.contains("<TIME_SLOT TIME_SLOT_ID=\"ts4\" TIME_VALUE=\"2500\"/>"));
+
+        let back = parse_eaf(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        let speakers: Vec<_> = back.cues.iter().map(|c| c.speaker.as_deref()).collect();
+        assert_eq!(speakers, vec![Some("Ann"), Some("transcript"), Some("Ann")]);
+        assert_eq!(back.meta.language.as_deref(), Some("en"));
+        assert_eq!(
+            back.meta.tags.get("media_url").map(String::as_str),
+            Some("file:///a.wav")
+        );
+    }
+
+    #[test]
+    fn configured_media_url_and_offset() {
+        let mut cfg = Config::default();
+        cfg.formats.eaf.media_url = Some("clip.mp4".to_string());
+        cfg.policy.timestamp_offset_ms = -500;
+        let out = render(&Eaf, &Transcript::new(vec![cue(200, 1000, "x")]), &cfg);
+        assert!(out.contains("MEDIA_URL=\"clip.mp4\" MIME_TYPE=\"video/mp4\""));
+        assert!(out.contains("TIME_VALUE=\"0\"/>"));
+        assert!(out.contains("TIME_VALUE=\"500\"/>"));
+    }
+
+    #[test]
+    fn skips_symbolic_tiers_and_unaligned_slots() {
+        let t = parse_eaf(SYMBOLIC, &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(100, 900, "hola")]);
+        assert_eq!(t.cues[0].speaker, None);
+
+        let tiers = list_tiers(SYMBOLIC).unwrap();
+        assert_eq!(tiers.len(), 1);
+        assert_eq!(tiers[0].name.as_deref(), Some("utt"));
+    }
+
+    #[test]
+    fn selects_tiers_and_lists_participants() {
+        let mut cfg = Config::default();
+        cfg.formats.eaf.tier_per_speaker = false;
+        cfg.formats.eaf.default_tier = "words".to_string();
+        let one = render(&Eaf, &Transcript::new(vec![spoken(0, 1, "Ann", "x")]), &cfg);
+        let out = one.replace("PARTICIPANT=\"\"", "PARTICIPANT=\"Ann\"");
+        assert_eq!(
+            list_tiers(&out).unwrap()[0].name.as_deref(),
+            Some("words [Ann]")
+        );
+
+        cfg.formats.eaf.tier = Some("words".to_string());
+        assert_eq!(parse_eaf(&out, &cfg).unwrap().cues.len(), 1);
+        cfg.formats.eaf.tier = Some("gloss".to_string());
+        let err = parse_eaf(&out, &cfg).unwrap_err().to_string();
+        assert!(err.contains("available: words"), "{err}");
+        cfg.formats.eaf.tier = None;
+        cfg.formats.eaf.track = Some(2);
+        assert!(parse_eaf(&out, &cfg).is_err());
+    }
+
+    #[test]
+    fn rejects_other_documents_and_units() {
+        let cfg = Config::default();
+        assert!(parse_eaf("<tt/>", &cfg).is_err());
+        let frames = SYMBOLIC.replace("milliseconds", "PAL-frames");
+        let err = parse_eaf(&frames, &cfg).unwrap_err().to_string();
+        assert!(err.contains("TIME_UNITS 'PAL-frames'"));
+        let bad = SYMBOLIC.replace("\"900\"", "\"soon\"");
+        assert!(parse_eaf(&bad, &cfg).is_err());
+    }
+}
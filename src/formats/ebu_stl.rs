@@ -0,0 +1,867 @@
+use anyhow::{Result, anyhow};
+use encoding_rs::Encoding;
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, Write},
+};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    config::{Config, StlCharacterTable, StlDisplayStandard, StlJustification},
+    formats::{
+        registry::{CollectSink, CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        time::utc_now,
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+const GSI_LEN: usize = 1024;
+const TTI_LEN: usize = 128;
+const TF_LEN: usize = 112;
+
+/// Text field control bytes.
+const TF_NEWLINE: u8 = 0x8A;
+const TF_UNUSED: u8 = 0x8F;
+/// Extension block number of the last (or only) block of a subtitle.
+const EBN_LAST: u8 = 0xFF;
+/// Extension block number of user data blocks, which carry no subtitle text.
+const EBN_USER_DATA: u8 = 0xFE;
+
+pub struct EbuStl;
+
+impl FormatReader for EbuStl {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_stl(input.as_bytes(), cfg)
+    }
+
+    /// STL is binary, so the bytes are read as-is instead of as UTF-8 text.
+    fn read_stream<'a>(
+        &self,
+        mut input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        let mut raw = Vec::new();
+        input.read_to_end(&mut raw)?;
+        Ok(CueStream::from_transcript(parse_stl(&raw, cfg)?))
+    }
+}
+
+impl FormatWriter for EbuStl {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_stl(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("stl", &["stl"])
+        .config_section("stl")
+        .sniff(|raw| {
+            raw.get(3..11)
+                .is_some_and(|dfc| dfc.starts_with("STL") && dfc.ends_with(".01"))
+        })
+        .reader(EbuStl)
+        .writer(EbuStl)
+}
+
+/// Character code tables named by the GSI `CCT` field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharTable {
+    /// ISO 6937 with non-spacing diacritics.
+    Latin,
+    /// ISO 8859-5/6/7/8.
+    Iso8859(&'static Encoding),
+}
+
+impl CharTable {
+    fn from_code(code: &str) -> Result<Self> {
+        Ok(match code {
+            "00" => StlCharacterTable::Latin.into(),
+            "01" => StlCharacterTable::Cyrillic.into(),
+            "02" => StlCharacterTable::Arabic.into(),
+            "03" => StlCharacterTable::Greek.into(),
+            "04" => StlCharacterTable::Hebrew.into(),
+            other => {
+                return Err(anyhow!(
+                    "unsupported EBU STL character code table '{other}'"
+                ));
+            }
+        })
+    }
+
+    fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Latin => decode_iso6937(bytes),
+            Self::Iso8859(enc) => enc.decode_without_bom_handling(bytes).0.into_owned(),
+        }
+    }
+
+    /// Encodes one character, or `None` when the table has no code for it.
+    fn encode(self, ch: char) -> Option<Vec<u8>> {
+        match self {
+            Self::Latin => encode_iso6937(ch),
+            Self::Iso8859(enc) => {
+                let s = ch.to_string();
+                let (bytes, _, unmappable) = enc.encode(&s);
+                (!unmappable).then(|| bytes.into_owned())
+            }
+        }
+    }
+}
+
+impl From<StlCharacterTable> for CharTable {
+    fn from(table: StlCharacterTable) -> Self {
+        match table {
+            StlCharacterTable::Latin => Self::Latin,
+            StlCharacterTable::Cyrillic => Self::Iso8859(encoding_rs::ISO_8859_5),
+            StlCharacterTable::Arabic => Self::Iso8859(encoding_rs::ISO_8859_6),
+            StlCharacterTable::Greek => Self::Iso8859(encoding_rs::ISO_8859_7),
+            StlCharacterTable::Hebrew => Self::Iso8859(encoding_rs::ISO_8859_8),
+        }
+    }
+}
+
+pub fn parse_stl(raw: &[u8], cfg: &Config) -> Result<Transcript> {
+    if raw.len() < GSI_LEN {
+        return Err(anyhow!(
+            "EBU STL: file is {} bytes, shorter than the GSI block",
+            raw.len()
+        ));
+    }
+    let gsi = &raw[..GSI_LEN];
+    let field = |from: usize, to: usize| gsi_text(&gsi[from..to]);
+
+    let dfc = field(3, 11);
+    let fps = match dfc.as_str() {
+        "STL25.01" => 25.0,
+        "STL30.01" => 30.0,
+        other => return Err(anyhow!("EBU STL: unknown disk format code '{other}'")),
+    };
+    let cct = field(12, 14);
+    let table = CharTable::from_code(if cct.is_empty() { "00" } else { &cct })?;
+    let lc = field(14, 16);
+
+    let mut meta = Meta::default();
+    let opt = field(16, 48);
+    let tpt = field(80, 112);
+    meta.title = [opt, tpt.clone()].into_iter().find(|t| !t.is_empty());
+    meta.language = language_from_code(&lc).map(str::to_string);
+    meta.tags.insert("fps".to_string(), fps.to_string());
+    meta.tags.insert("cct".to_string(), cct);
+    let optional = [
+        ("language_code", lc),
+        ("episode", field(48, 80)),
+        ("translated_title", tpt),
+        ("translated_episode", field(112, 144)),
+        ("translator", field(144, 176)),
+        ("translator_contact", field(176, 208)),
+        ("reference", field(208, 224)),
+        ("creation_date", field(224, 230)),
+        ("revision_date", field(230, 236)),
+        ("country", field(274, 277)),
+        ("publisher", field(277, 309)),
+        ("editor", field(309, 341)),
+        ("editor_contact", field(341, 373)),
+    ];
+    for (key, value) in optional {
+        if !value.is_empty() {
+            meta.tags.insert(key.to_string(), value);
+        }
+    }
+
+    let tcp = field(256, 264);
+    let tcp_ms = if cfg.formats.stl.offset_start_of_programme {
+        parse_gsi_timecode(&tcp, fps).unwrap_or(0)
+    } else {
+        0
+    };
+    if !tcp.is_empty() {
+        meta.tags.insert("start_of_programme".to_string(), tcp);
+    }
+
+    let body = &raw[GSI_LEN..];
+    if !body.len().is_multiple_of(TTI_LEN) {
+        tracing::warn!(
+            trailing = body.len() % TTI_LEN,
+            "EBU STL: ignoring incomplete trailing TTI block"
+        );
+    }
+
+    let mut cues = Vec::new();
+    let mut text_bytes: Vec<u8> = Vec::new();
+    for block in body.chunks_exact(TTI_LEN) {
+        let ebn = block[3];
+        let comment = block[15] == 1;
+        if ebn == EBN_USER_DATA || comment {
+            continue;
+        }
+        text_bytes.extend_from_slice(&block[16..16 + TF_LEN]);
+        // Text continues in extension blocks until the last one.
+        if ebn != EBN_LAST {
+            continue;
+        }
+
+        let start_ms = tti_timecode_ms(&block[5..9], fps) - tcp_ms;
+        let end_ms = tti_timecode_ms(&block[9..13], fps) - tcp_ms;
+        let text = decode_text_field(&std::mem::take(&mut text_bytes), table);
+        if text.is_empty() {
+            continue;
+        }
+        cues.push(Cue {
+            start_ms,
+            end_ms,
+            text,
+            speaker: None,
+            words: Vec::new(),
+        });
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+/// GSI text fields are space-padded; anything outside ASCII is replaced.
+fn gsi_text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| {
+            if b.is_ascii() && !b.is_ascii_control() {
+                char::from(b)
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// `HHMMSSFF` from the GSI block.
+fn parse_gsi_timecode(tc: &str, fps: f64) -> Option<i64> {
+    if tc.len() != 8 || !tc.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let n = |i: usize| tc[i..i + 2].parse::<i64>().ok();
+    Some(timecode_ms(n(0)?, n(2)?, n(4)?, n(6)?, fps))
+}
+
+/// Four binary bytes: hours, minutes, seconds, frames.
+fn tti_timecode_ms(b: &[u8], fps: f64) -> i64 {
+    timecode_ms(
+        i64::from(b[0]),
+        i64::from(b[1]),
+        i64::from(b[2]),
+        i64::from(b[3]),
+        fps,
+    )
+}
+
+fn timecode_ms(h: i64, m: i64, s: i64, f: i64, fps: f64) -> i64 {
+    (h * 3_600 + m * 60 + s) * 1000 + (f as f64 * 1000.0 / fps).round() as i64
+}
+
+/// Splits the text field into rows and drops teletext colour, box and
+/// height codes along with italic/underline toggles.
+fn decode_text_field(tf: &[u8], table: CharTable) -> String {
+    let mut rows: Vec<String> = Vec::new();
+    let mut current: Vec<u8> = Vec::new();
+    let mut flush = |current: &mut Vec<u8>| {
+        let row = table.decode(current);
+        let row = row.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !row.is_empty() {
+            rows.push(row);
+        }
+        current.clear();
+    };
+
+    for &b in tf {
+        match b {
+            TF_NEWLINE => flush(&mut current),
+            TF_UNUSED => {}
+            // Teletext spacing attributes (colours, boxing, double height)
+            // occupy a cell on screen.
+            0x00..=0x1F => current.push(b' '),
+            0x80..=0x9F => {}
+            _ => current.push(b),
+        }
+    }
+    flush(&mut current);
+    rows.join("\n")
+}
+
+/// Non-spacing diacritics (0xC1-0xCF) precede the letter they modify.
+const ISO6937_DIACRITICS: [(u8, char); 13] = [
+    (0xC1, '\u{300}'),
+    (0xC2, '\u{301}'),
+    (0xC3, '\u{302}'),
+    (0xC4, '\u{303}'),
+    (0xC5, '\u{304}'),
+    (0xC6, '\u{306}'),
+    (0xC7, '\u{307}'),
+    (0xC8, '\u{308}'),
+    (0xCA, '\u{30A}'),
+    (0xCB, '\u{327}'),
+    (0xCD, '\u{30B}'),
+    (0xCE, '\u{328}'),
+    (0xCF, '\u{30C}'),
+];
+
+const ISO6937_UPPER: [(u8, char); 75] = [
+    (0xA0, '\u{A0}'),
+    (0xA1, '¡'),
+    (0xA2, '¢'),
+    (0xA3, '£'),
+    (0xA4, '$'),
+    (0xA5, '¥'),
+    (0xA6, '#'),
+    (0xA7, '§'),
+    (0xA8, '¤'),
+    (0xA9, '‘'),
+    (0xAA, '“'),
+    (0xAB, '«'),
+    (0xAC, '←'),
+    (0xAD, '↑'),
+    (0xAE, '→'),
+    (0xAF, '↓'),
+    (0xB0, '°'),
+    (0xB1, '±'),
+    (0xB2, '²'),
+    (0xB3, '³'),
+    (0xB4, '×'),
+    (0xB5, 'µ'),
+    (0xB6, '¶'),
+    (0xB7, '·'),
+    (0xB8, '÷'),
+    (0xB9, '’'),
+    (0xBA, '”'),
+    (0xBB, '»'),
+    (0xBC, '¼'),
+    (0xBD, '½'),
+    (0xBE, '¾'),
+    (0xBF, '¿'),
+    (0xD0, '―'),
+    (0xD1, '¹'),
+    (0xD2, '®'),
+    (0xD3, '©'),
+    (0xD4, '™'),
+    (0xD5, '♪'),
+    (0xD6, '¬'),
+    (0xD7, '¦'),
+    (0xDC, '⅛'),
+    (0xDD, '⅜'),
+    (0xDE, '⅝'),
+    (0xDF, '⅞'),
+    (0xE0, 'Ω'),
+    (0xE1, 'Æ'),
+    (0xE2, 'Đ'),
+    (0xE3, 'ª'),
+    (0xE4, 'Ħ'),
+    (0xE6, 'Ĳ'),
+    (0xE7, 'Ŀ'),
+    (0xE8, 'Ł'),
+    (0xE9, 'Ø'),
+    (0xEA, 'Œ'),
+    (0xEB, 'º'),
+    (0xEC, 'Þ'),
+    (0xED, 'Ŧ'),
+    (0xEE, 'Ŋ'),
+    (0xEF, 'ŉ'),
+    (0xF0, 'ĸ'),
+    (0xF1, 'æ'),
+    (0xF2, 'đ'),
+    (0xF3, 'ð'),
+    (0xF4, 'ħ'),
+    (0xF5, 'ı'),
+    (0xF6, 'ĳ'),
+    (0xF7, 'ŀ'),
+    (0xF8, 'ł'),
+    (0xF9, 'ø'),
+    (0xFA, 'œ'),
+    (0xFB, 'ß'),
+    (0xFC, 'þ'),
+    (0xFD, 'ŧ'),
+    (0xFE, 'ŋ'),
+    (0xFF, '\u{AD}'),
+];
+
+fn decode_iso6937(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    let mut pending_mark: Option<char> = None;
+    for &b in bytes {
+        if let Some((_, mark)) = ISO6937_DIACRITICS.iter().find(|(code, _)| *code == b) {
+            pending_mark = Some(*mark);
+            continue;
+        }
+        let ch = if (0x20..0x7F).contains(&b) {
+            char::from(b)
+        } else {
+            ISO6937_UPPER
+                .iter()
+                .find(|(code, _)| *code == b)
+                .map(|(_, c)| *c)
+                .unwrap_or(' ')
+        };
+        out.push(ch);
+        if let Some(mark) = pending_mark.take() {
+            out.push(mark);
+        }
+    }
+    out.nfc().collect()
+}
+
+fn encode_iso6937(ch: char) -> Option<Vec<u8>> {
+    if (' '..='~').contains(&ch) {
+        return Some(vec![ch as u8]);
+    }
+    if let Some((code, _)) = ISO6937_UPPER.iter().find(|(_, c)| *c == ch) {
+        return Some(vec![*code]);
+    }
+    // Accented letters go out as diacritic + base letter.
+    let mut parts = ch.to_string().nfd().collect::<Vec<char>>().into_iter();
+    let (Some(base), Some(mark), None) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    let (code, _) = ISO6937_DIACRITICS.iter().find(|(_, m)| *m == mark)?;
+    base.is_ascii_alphabetic().then(|| vec![*code, base as u8])
+}
+
+/// EBU language codes for the languages seen in practice.
+const LANGUAGE_CODES: [(&str, &str); 44] = [
+    ("01", "sq"),
+    ("02", "br"),
+    ("03", "ca"),
+    ("04", "hr"),
+    ("05", "cy"),
+    ("06", "cs"),
+    ("07", "da"),
+    ("08", "de"),
+    ("09", "en"),
+    ("0A", "es"),
+    ("0B", "eo"),
+    ("0C", "et"),
+    ("0D", "eu"),
+    ("0E", "fo"),
+    ("0F", "fr"),
+    ("10", "fy"),
+    ("11", "ga"),
+    ("12", "gd"),
+    ("13", "gl"),
+    ("14", "is"),
+    ("15", "it"),
+    ("16", "se"),
+    ("17", "la"),
+    ("18", "lv"),
+    ("19", "lb"),
+    ("1A", "lt"),
+    ("1B", "hu"),
+    ("1C", "mt"),
+    ("1D", "nl"),
+    ("1E", "no"),
+    ("1F", "oc"),
+    ("20", "pl"),
+    ("21", "pt"),
+    ("22", "ro"),
+    ("23", "rm"),
+    ("24", "sr"),
+    ("25", "sk"),
+    ("26", "sl"),
+    ("27", "fi"),
+    ("28", "sv"),
+    ("29", "tr"),
+    ("2A", "nl-BE"),
+    ("2B", "wa"),
+    ("56", "ru"),
+];
+
+fn language_from_code(code: &str) -> Option<&'static str> {
+    LANGUAGE_CODES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, l)| *l)
+}
+
+fn language_to_code(lang: &str) -> &'static str {
+    let lang = lang.to_lowercase();
+    LANGUAGE_CODES
+        .iter()
+        .find(|(_, l)| l.to_lowercase() == lang)
+        .or_else(|| {
+            let primary = lang.split(['-', '_']).next().unwrap_or("");
+            LANGUAGE_CODES.iter().find(|(_, l)| *l == primary)
+        })
+        .map(|(c, _)| *c)
+        .unwrap_or("00")
+}
+
+fn write_stl(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let stl = &cfg.formats.stl;
+    let fps: u32 = match stl.frame_rate {
+        25 => 25,
+        30 => 30,
+        other => return Err(anyhow!("EBU STL frame rate must be 25 or 30, not {other}")),
+    };
+    let table = CharTable::from(stl.character_table);
+    let justification = match stl.justification {
+        StlJustification::None => 0,
+        StlJustification::Left => 1,
+        StlJustification::Centered => 2,
+        StlJustification::Right => 3,
+    };
+    let teletext = matches!(
+        stl.display_standard,
+        StlDisplayStandard::Level1 | StlDisplayStandard::Level2
+    );
+    let offset = cfg.policy.timestamp_offset_ms;
+
+    let mut unsupported = BTreeSet::new();
+    let mut blocks: Vec<[u8; TTI_LEN]> = Vec::new();
+    let mut subtitles = 0usize;
+    let mut max_row_chars = 0usize;
+    let mut max_rows = 0usize;
+    let mut first_tc: Option<[u8; 4]> = None;
+
+    for cue in &t.cues {
+        let rows: Vec<String> = cue
+            .text
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|l| !l.is_empty())
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+
+        let mut tf: Vec<u8> = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                tf.push(TF_NEWLINE);
+            }
+            let mut n = 0;
+            for ch in row.chars() {
+                match table.encode(ch) {
+                    Some(bytes) => {
+                        tf.extend(bytes);
+                        n += 1;
+                    }
+                    None => {
+                        unsupported.insert(ch);
+                    }
+                }
+            }
+            max_row_chars = max_row_chars.max(n);
+        }
+        max_rows = max_rows.max(rows.len());
+        if rows
+            .iter()
+            .any(|r| r.chars().count() > stl.max_chars_per_row)
+        {
+            tracing::warn!(
+                start_ms = cue.start_ms,
+                max = stl.max_chars_per_row,
+                "EBU STL row longer than the configured maximum"
+            );
+        }
+
+        subtitles += 1;
+        let tci = frames_to_tc(ms_to_frames(cue.start_ms + offset, fps), fps);
+        let tco = frames_to_tc(ms_to_frames(cue.end_ms + offset, fps), fps);
+        first_tc.get_or_insert(tci);
+
+        // Teletext rows are double height, so each row takes two lines.
+        let step = if teletext { 2 } else { 1 };
+        let vp = stl
+            .vertical_position
+            .saturating_sub((rows.len() as u8 - 1).saturating_mul(step))
+            .max(1);
+
+        let chunks: Vec<&[u8]> = tf.chunks(TF_LEN).collect();
+        for (ebn, chunk) in chunks.iter().enumerate() {
+            let mut block = [0u8; TTI_LEN];
+            block[1..3].copy_from_slice(&(subtitles as u16).to_le_bytes());
+            block[3] = if ebn + 1 == chunks.len() {
+                EBN_LAST
+            } else {
+                ebn as u8
+            };
+            block[5..9].copy_from_slice(&tci);
+            block[9..13].copy_from_slice(&tco);
+            block[13] = vp;
+            block[14] = justification;
+            block[16..].fill(TF_UNUSED);
+            block[16..16 + chunk.len()].copy_from_slice(chunk);
+            blocks.push(block);
+        }
+    }
+
+    if !unsupported.is_empty() {
+        let chars: String = unsupported.into_iter().collect();
+        tracing::warn!(
+            chars = chars.as_str(),
+            table = stl.character_table.code(),
+            "dropping characters the EBU STL character table cannot represent"
+        );
+    }
+
+    let meta = &t.meta;
+    let tag = |k: &str| meta.tags.get(k).map(String::as_str).unwrap_or("");
+    // A code carried over from an STL input survives languages we cannot map.
+    let lc = match meta.language.as_deref().map(language_to_code) {
+        Some(code) if code != "00" => code,
+        _ => match tag("language_code") {
+            "" => "00",
+            code => code,
+        },
+    };
+    let creation_date = match tag("creation_date") {
+        "" => today_yymmdd(),
+        d => d.to_string(),
+    };
+    let first_tc = first_tc.unwrap_or([0; 4]);
+
+    let mut gsi = Vec::with_capacity(GSI_LEN);
+    let mut put = |value: &str, len: usize| {
+        let mut bytes: Vec<u8> = value
+            .chars()
+            .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+            .take(len)
+            .collect();
+        bytes.resize(len, b' ');
+        gsi.extend(bytes);
+    };
+    put("850", 3);
+    put(&format!("STL{fps}.01"), 8);
+    put(stl.display_standard.code(), 1);
+    put(stl.character_table.code(), 2);
+    put(lc, 2);
+    put(meta.title.as_deref().unwrap_or(""), 32);
+    put(tag("episode"), 32);
+    put(tag("translated_title"), 32);
+    put(tag("translated_episode"), 32);
+    put(tag("translator"), 32);
+    put(tag("translator_contact"), 32);
+    put(tag("reference"), 16);
+    put(&creation_date, 6);
+    put(&today_yymmdd(), 6);
+    put("00", 2);
+    put(&format!("{:05}", blocks.len()), 5);
+    put(&format!("{subtitles:05}"), 5);
+    put("001", 3);
+    put(&format!("{:02}", max_row_chars.clamp(1, 99)), 2);
+    put(
+        &format!("{:02}", if teletext { 23 } else { max_rows.clamp(1, 99) }),
+        2,
+    );
+    put("1", 1);
+    put("00000000", 8);
+    put(
+        &format!(
+            "{:02}{:02}{:02}{:02}",
+            first_tc[0], first_tc[1], first_tc[2], first_tc[3]
+        ),
+        8,
+    );
+    put("1", 1);
+    put("1", 1);
+    put(tag("country"), 3);
+    put(tag("publisher"), 32);
+    put(tag("editor"), 32);
+    put(tag("editor_contact"), 32);
+    put("", 75);
+    put("", 576);
+
+    out.write_all(&gsi)?;
+    for block in &blocks {
+        out.write_all(block)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn ms_to_frames(ms: i64, fps: u32) -> i64 {
+    (ms.max(0) as f64 * f64::from(fps) / 1000.0).round() as i64
+}
+
+fn frames_to_tc(frames: i64, fps: u32) -> [u8; 4] {
+    let fps = i64::from(fps);
+    let secs = frames / fps;
+    [
+        (secs / 3_600).min(23) as u8,
+        ((secs / 60) % 60) as u8,
+        (secs % 60) as u8,
+        (frames % fps) as u8,
+    ]
+}
+
+/// Today's date as `YYMMDD` for the GSI creation and revision fields.
+fn today_yymmdd() -> String {
+    let (year, month, day, _) = utc_now();
+    format!("{:02}{month:02}{day:02}", year % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render_bytes, timings};
+
+    fn gsi_field(stl: &[u8], from: usize, to: usize) -> String {
+        gsi_text(&stl[from..to])
+    }
+
+    fn tti(stl: &[u8], n: usize) -> &[u8] {
+        &stl[GSI_LEN + n * TTI_LEN..GSI_LEN + (n + 1) * TTI_LEN]
+    }
+
+    #[test]
+    fn gsi_and_tti_roundtrip() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![
+            cue(1000, 2000, "Grüße, Ærø"),
+            cue(3_600_040, 3_602_000, "Two\nrows"),
+        ]);
+        t.meta.title = Some("Programme".to_string());
+        t.meta.language = Some("de-AT".to_string());
+        t.meta
+            .tags
+            .insert("publisher".to_string(), "ACME".to_string());
+        t.meta
+            .tags
+            .insert("creation_date".to_string(), "240131".to_string());
+
+        let stl = render_bytes(&EbuStl, &t, &cfg);
+        assert_eq!(stl.len(), GSI_LEN + 2 * TTI_LEN);
+        assert_eq!(gsi_field(&stl, 3, 11), "STL25.01");
+        assert_eq!(gsi_field(&stl, 14, 16), "08");
+        assert_eq!(gsi_field(&stl, 224, 230), "240131");
+        assert_eq!(gsi_field(&stl, 238, 243), "00002");
+        assert_eq!(gsi_field(&stl, 243, 248), "00002");
+        assert_eq!(gsi_field(&stl, 264, 272), "00000100");
+
+        let second = tti(&stl, 1);
+        assert_eq!(second[1..4], [2, 0, EBN_LAST]);
+        assert_eq!(second[5..9], [1, 0, 0, 1]);
+        assert_eq!(second[13], 20, "two teletext rows end on row 22");
+        assert_eq!(second[14], 2);
+
+        let back = parse_stl(&stl, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.meta.title.as_deref(), Some("Programme"));
+        assert_eq!(back.meta.language.as_deref(), Some("de"));
+        assert_eq!(
+            back.meta.tags.get("publisher").map(String::as_str),
+            Some("ACME")
+        );
+        assert_eq!(back.meta.tags.get("fps").map(String::as_str), Some("25"));
+    }
+
+    #[test]
+    fn long_text_uses_extension_blocks() {
+        let cfg = Config::default();
+        let text = "x".repeat(150);
+        let stl = render_bytes(&EbuStl, &Transcript::new(vec![cue(0, 1000, &text)]), &cfg);
+        assert_eq!(stl.len(), GSI_LEN + 2 * TTI_LEN);
+        assert_eq!(tti(&stl, 0)[3], 0);
+        assert_eq!(tti(&stl, 1)[3], EBN_LAST);
+        assert_eq!(parse_stl(&stl, &cfg).unwrap().cues[0].text, text);
+    }
+
+    #[test]
+    fn thirty_fps_cyrillic() {
+        let mut cfg = Config::default();
+        cfg.formats.stl.frame_rate = 30;
+        cfg.formats.stl.character_table = StlCharacterTable::Cyrillic;
+        cfg.formats.stl.justification = StlJustification::Left;
+        let t = Transcript::new(vec![cue(1000, 2000, "Привет")]);
+
+        let stl = render_bytes(&EbuStl, &t, &cfg);
+        assert_eq!(gsi_field(&stl, 3, 11), "STL30.01");
+        assert_eq!(tti(&stl, 0)[14], 1);
+        assert_eq!(timings(&parse_stl(&stl, &cfg).unwrap()), timings(&t));
+    }
+
+    #[test]
+    fn start_of_programme_offset() {
+        let t = Transcript::new(vec![cue(12_000, 13_000, "Hi")]);
+        let mut stl = render_bytes(&EbuStl, &t, &Config::default());
+        stl[256..264].copy_from_slice(b"00001000");
+
+        let mut cfg = Config::default();
+        assert_eq!(
+            timings(&parse_stl(&stl, &cfg).unwrap()),
+            vec![(2000, 3000, "Hi")]
+        );
+        cfg.formats.stl.offset_start_of_programme = false;
+        assert_eq!(timings(&parse_stl(&stl, &cfg).unwrap()), timings(&t));
+    }
+
+    #[test]
+    fn skips_comment_and_user_data_blocks() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            cue(0, 1000, "a"),
+            cue(1000, 2000, "b"),
+            cue(2000, 3000, "c"),
+        ]);
+        let mut stl = render_bytes(&EbuStl, &t, &cfg);
+        stl[GSI_LEN + 15] = 1;
+        stl[GSI_LEN + TTI_LEN + 3] = EBN_USER_DATA;
+        // Trailing garbage shorter than a block is ignored.
+        stl.extend_from_slice(&[0; 5]);
+        assert_eq!(
+            timings(&parse_stl(&stl, &cfg).unwrap()),
+            vec![(2000, 3000, "c")]
+        );
+    }
+
+    #[test]
+    fn text_field_codes() {
+        let tf = [
+            0x07, b'A', 0x85, b'B', TF_NEWLINE, 0x1C, b'C', TF_UNUSED, TF_UNUSED,
+        ];
+        assert_eq!(decode_text_field(&tf, CharTable::Latin), "AB\nC");
+        assert_eq!(decode_iso6937(&[0xC8, b'u', 0xE1]), "üÆ");
+        assert_eq!(encode_iso6937('é'), Some(vec![0xC2, b'e']));
+        assert_eq!(encode_iso6937('😀'), None);
+    }
+
+    #[test]
+    fn bad_input_and_settings_are_errors() {
+        let cfg = Config::default();
+        assert!(parse_stl(&[b' '; 100], &cfg).is_err());
+        let mut stl = render_bytes(&EbuStl, &Transcript::new(Vec::new()), &cfg);
+        stl[3..11].copy_from_slice(b"STL24.01");
+        assert!(parse_stl(&stl, &cfg).is_err());
+
+        let mut cfg = Config::default();
+        cfg.formats.stl.frame_rate = 24;
+        assert!(
+            EbuStl
+                .write(&Transcript::new(Vec::new()), &cfg, &mut Vec::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn unknown_character_table_fails_at_config_load() {
+        let toml = Config::default().to_toml_pretty().unwrap();
+        assert!(toml.contains("character_table = \"00\""));
+        let parsed: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.formats.stl.character_table, StlCharacterTable::Latin);
+        assert_eq!(
+            parsed.formats.stl.display_standard,
+            StlDisplayStandard::Level1
+        );
+
+        let bad = toml.replace("character_table = \"00\"", "character_table = \"09\"");
+        assert!(toml::from_str::<Config>(&bad).is_err());
+        let bad = toml.replace(
+            "display_standard = \"1\"",
+            "display_standard = \"teletext\"",
+        );
+        assert!(toml::from_str::<Config>(&bad).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::{
+        registry::{CollectSink, CueSink, FormatEntry, FormatWriter},
+        xml::escape,
+    },
+    model::{Meta, Transcript},
+};
+
+/// EPUB3 media overlay: a SMIL document pairing each cue's element in the
+/// XHTML content document with its clip of the audio.
+pub struct Smil;
+
+/// The XHTML content document the SMIL overlay points into, one `<p>` per
+/// cue carrying the fragment ID.
+pub struct Xhtml;
+
+impl FormatWriter for Smil {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_smil(t, cfg, out)
+        })))
+    }
+}
+
+impl FormatWriter for Xhtml {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_xhtml(t, cfg, out)
+        })))
+    }
+}
+
+pub fn smil_entry() -> FormatEntry {
+    FormatEntry::new("smil", &["smil"])
+        .config_section("epub")
+        .writer(Smil)
+}
+
+pub fn xhtml_entry() -> FormatEntry {
+    FormatEntry::new("xhtml", &["xhtml"])
+        .config_section("epub")
+        .writer(Xhtml)
+}
+
+fn write_smil(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.epub;
+    let offset = cfg.policy.timestamp_offset_ms;
+    let text_href = escape(&c.text_href);
+    let audio_src = escape(&c.audio_src);
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<smil xmlns=\"http://www.w3.org/ns/SMIL\" xmlns:epub=\"http://www.idpf.org/2007/ops\" version=\"3.0\">"
+    )?;
+    writeln!(out, "  <body>")?;
+    writeln!(
+        out,
+        "    <seq id=\"seq1\" epub:textref=\"{text_href}\" epub:type=\"bodymatter chapter\">"
+    )?;
+    for (i, cue) in t.cues.iter().enumerate() {
+        writeln!(out, "      <par id=\"par{}\">", i + 1)?;
+        writeln!(
+            out,
+            "        <text src=\"{text_href}#{}{}\"/>",
+            escape(&c.id_prefix),
+            i + 1
+        )?;
+        writeln!(
+            out,
+            "        <audio src=\"{audio_src}\" clipBegin=\"{}\" clipEnd=\"{}\"/>",
+            clock((cue.start_ms + offset).max(0)),
+            clock((cue.end_ms + offset).max(0))
+        )?;
+        writeln!(out, "      </par>")?;
+    }
+    writeln!(out, "    </seq>")?;
+    writeln!(out, "  </body>")?;
+    writeln!(out, "</smil>")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn write_xhtml(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.epub;
+    let lang = escape(t.meta.language.as_deref().unwrap_or(&c.lang));
+    let title = c
+        .title
+        .as_deref()
+        .or(t.meta.title.as_deref())
+        .unwrap_or("Transcript");
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(
+        out,
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{lang}\" lang=\"{lang}\">"
+    )?;
+    writeln!(out, "  <head>")?;
+    writeln!(out, "    <meta charset=\"UTF-8\"/>")?;
+    writeln!(out, "    <title>{}</title>", escape(title))?;
+    if let Some(href) = &c.stylesheet {
+        writeln!(
+            out,
+            "    <link rel=\"stylesheet\" type=\"text/css\" href=\"{}\"/>",
+            escape(href)
+        )?;
+    }
+    writeln!(out, "  </head>")?;
+    writeln!(out, "  <body>")?;
+    writeln!(out, "    <section epub:type=\"chapter\">")?;
+    for (i, cue) in t.cues.iter().enumerate() {
+        let text = cue
+            .text
+            .lines()
+            .map(|l| escape(l.trim()))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        let speaker = cue
+            .speaker
+            .as_ref()
+            .map(|s| format!("<span class=\"speaker\">{}:</span> ", escape(s)))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "      <p id=\"{}{}\">{speaker}{text}</p>",
+            escape(&c.id_prefix),
+            i + 1
+        )?;
+    }
+    writeln!(out, "    </section>")?;
+    writeln!(out, "  </body>")?;
+    writeln!(out, "</html>")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// SMIL full clock value, `h:mm:ss.fff`.
+fn clock(ms: i64) -> String {
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{
+        testutil::{cue, render, spoken, timings},
+        time::parse_time_to_ms,
+        xml::{Element, parse_document},
+    };
+
+    fn all<'a>(root: &'a Element, name: &str) -> Vec<&'a Element> {
+        let mut out = Vec::new();
+        root.descendants_named(name, &mut out);
+        out
+    }
+
+    /// Reads the overlay back into cues by following each `<par>` into the
+    /// content document.
+    fn read_back(smil: &str, xhtml: &str) -> Transcript {
+        let smil = parse_document(smil).unwrap();
+        let xhtml = parse_document(xhtml).unwrap();
+        let paragraphs = all(&xhtml, "p");
+        let cues = all(&smil, "par")
+            .into_iter()
+            .map(|par| {
+                let src = par.child("text").unwrap().attr("src").unwrap();
+                let (href, id) = src.split_once('#').unwrap();
+                assert_eq!(href, "chapter.xhtml");
+                let p = paragraphs
+                    .iter()
+                    .find(|p| p.attr("id") == Some(id))
+                    .unwrap();
+                let audio = par.child("audio").unwrap();
+                assert_eq!(audio.attr("src"), Some("chapter.mp3"));
+                cue(
+                    parse_time_to_ms(audio.attr("clipBegin").unwrap()).unwrap(),
+                    parse_time_to_ms(audio.attr("clipEnd").unwrap()).unwrap(),
+                    &p.text(),
+                )
+            })
+            .collect();
+        Transcript::new(cues)
+    }
+
+    #[test]
+    fn overlay_and_content_document_roundtrip() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            cue(0, 1500, "Tom & Jerry"),
+            cue(1500, 3_725_250, "Past an hour"),
+        ]);
+        let smil = render(&Smil, &t, &cfg);
+        assert!(smil.contains("<text src=\"chapter.xhtml#cue-2\"/>"));
+        assert!(smil.contains("clipBegin=\"0:00:01.500\" clipEnd=\"1:02:05.250\""));
+        let xhtml = render(&Xhtml, &t, &cfg);
+        assert!(xhtml.contains("<p id=\"cue-1\">Tom &amp; Jerry</p>"));
+        assert_eq!(timings(&read_back(&smil, &xhtml)), timings(&t));
+    }
+
+    #[test]
+    fn content_document_speakers_lines_and_header() {
+        let mut cfg = Config::default();
+        cfg.formats.epub.id_prefix = "s".to_string();
+        cfg.formats.epub.stylesheet = Some("css/a.css".to_string());
+        let mut t = Transcript::new(vec![spoken(0, 1000, "<Ann>", "One\n two")]);
+        t.meta.title = Some("Chapter 1".to_string());
+        t.meta.language = Some("fr".to_string());
+
+        let out = render(&Xhtml, &t, &cfg);
+        assert!(out.contains("xml:lang=\"fr\" lang=\"fr\""));
+        assert!(out.contains("<title>Chapter 1</title>"));
+        assert!(out.contains("href=\"css/a.css\""));
+        assert!(
+            out.contains(
+                "<p id=\"s1\"><span class=\"speaker\">&lt;Ann&gt;:</span> One<br/>two</p>"
+            )
+        );
+
+        cfg.formats.epub.title = Some("Override".to_string());
+        t.meta.language = None;
+        let out = render(&Xhtml, &t, &cfg);
+        assert!(out.contains("<title>Override</title>"));
+        assert!(out.contains("lang=\"en\""));
+    }
+
+    #[test]
+    fn overlay_applies_offset_and_clamps() {
+        let mut cfg = Config::default();
+        cfg.policy.timestamp_offset_ms = -500;
+        cfg.formats.epub.audio_src = "a&b.mp3".to_string();
+        let out = render(&Smil, &Transcript::new(vec![cue(200, 1000, "x")]), &cfg);
+        assert!(
+            out.contains("src=\"a&amp;b.mp3\" clipBegin=\"0:00:00.000\" clipEnd=\"0:00:00.500\"")
+        );
+        assert_eq!(clock(59_999), "0:00:59.999");
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::{Context, Result};
+use std::{fs, io::Write};
+
+use crate::{
+    config::{Config, HtmlMediaElement},
+    formats::{
+        registry::{CollectSink, CueSink, FormatEntry, FormatWriter},
+        xml::escape,
+    },
+    model::{Meta, Transcript},
+};
+
+pub struct Html;
+
+impl FormatWriter for Html {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_html(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("html", &["html", "htm"])
+        .config_section("html")
+        .writer(Html)
+}
+
+const DEFAULT_CSS: &str = "\
+body { font-family: system-ui, sans-serif; line-height: 1.6; max-width: 46rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+.media { width: 100%; position: sticky; top: 0; background: #fff; }
+.speaker { display: block; font-weight: 600; margin-top: 1rem; }
+.ts { font-size: 0.8em; color: #888; text-decoration: none; margin-right: 0.25em; font-variant-numeric: tabular-nums; }
+.ts:hover { text-decoration: underline; }
+.cue.active { background: #fff3b0; }";
+
+/// Seeks the media on timestamp clicks and highlights the cue being played.
+const SCRIPT: &str = "\
+(function () {
+  var media = document.getElementById('media');
+  var cues = Array.prototype.slice.call(document.querySelectorAll('.cue'));
+  document.addEventListener('click', function (e) {
+    var ts = e.target.closest('.ts');
+    if (!ts || !media) return;
+    e.preventDefault();
+    media.currentTime = parseFloat(ts.parentNode.dataset.start);
+    media.play();
+  });
+  if (!media) return;
+  var active = null;
+  media.addEventListener('timeupdate', function () {
+    var t = media.currentTime, next = null;
+    for (var i = 0; i < cues.length; i++) {
+      if (t >= parseFloat(cues[i].dataset.start) && t < parseFloat(cues[i].dataset.end)) { next = cues[i]; break; }
+    }
+    if (next === active) return;
+    if (active) active.classList.remove('active');
+    if (next) {
+      next.classList.add('active');
+      if (AUTOSCROLL) next.scrollIntoView({ block: 'center', behavior: 'smooth' });
+    }
+    active = next;
+  });
+})();";
+
+/// A self-contained page: cues grouped into paragraphs by speaker and pause,
+/// each with a timestamp link, plus an optional player the script follows.
+/// A `template` file replaces the built-in page layout; it is filled in at
+/// `{{title}}`, `{{lang}}`, `{{css}}`, `{{media}}`, `{{transcript}}` and
+/// `{{script}}`.
+fn write_html(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.html;
+    let offset = cfg.policy.timestamp_offset_ms;
+    let title = c
+        .title
+        .as_deref()
+        .or(t.meta.title.as_deref())
+        .unwrap_or("Transcript");
+    let lang = t.meta.language.as_deref().unwrap_or(&c.lang);
+
+    let media = match &c.media_src {
+        Some(src) => {
+            let el = match c.media_element {
+                HtmlMediaElement::Audio => "audio",
+                HtmlMediaElement::Video => "video",
+            };
+            format!(
+                "<{el} id=\"media\" class=\"media\" controls preload=\"metadata\" src=\"{}\"></{el}>",
+                escape(src)
+            )
+        }
+        None => String::new(),
+    };
+
+    let mut body = String::new();
+    let mut prev: Option<(&Option<String>, i64)> = None;
+    for (i, cue) in t.cues.iter().enumerate() {
+        let start_ms = (cue.start_ms + offset).max(0);
+        let end_ms = (cue.end_ms + offset).max(0);
+        let new_para = match prev {
+            None => true,
+            Some((speaker, end)) => speaker != &cue.speaker || start_ms - end >= c.paragraph_gap_ms,
+        };
+        if new_para {
+            if prev.is_some() {
+                body.push_str("</p>\n");
+            }
+            body.push_str("<p>");
+            // Pauses start paragraphs too, but only a new speaker is named.
+            if let Some(s) = &cue.speaker
+                && prev.is_none_or(|(speaker, _)| speaker != &cue.speaker)
+            {
+                body.push_str(&format!("<span class=\"speaker\">{}</span>", escape(s)));
+            }
+        } else {
+            body.push(' ');
+        }
+        let id = format!("{}{}", c.id_prefix, i + 1);
+        let text = cue
+            .text
+            .lines()
+            .map(|l| escape(l.trim()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        body.push_str(&format!(
+            "<span class=\"cue\" id=\"{id}\" data-start=\"{:.3}\" data-end=\"{:.3}\"><a class=\"ts\" href=\"#{id}\">{}</a>{text}</span>",
+            start_ms as f64 / 1000.0,
+            end_ms as f64 / 1000.0,
+            display_time(start_ms),
+            id = escape(&id),
+        ));
+        prev = Some((&cue.speaker, end_ms));
+    }
+    if prev.is_some() {
+        body.push_str("</p>\n");
+    }
+
+    let css = c.css.as_deref().unwrap_or(DEFAULT_CSS);
+    let script = SCRIPT.replace("AUTOSCROLL", if c.autoscroll { "true" } else { "false" });
+
+    let page = match &c.template {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("failed reading HTML template {path}"))?
+            .replace("{{title}}", &escape(title))
+            .replace("{{lang}}", &escape(lang))
+            .replace("{{css}}", css)
+            .replace("{{media}}", &media)
+            .replace("{{script}}", &script)
+            // Last, so placeholder-like text in cues is left alone.
+            .replace("{{transcript}}", &body),
+        None => format!(
+            "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n<style>\n{css}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n{media}\n<main class=\"transcript\">\n{body}</main>\n<script>\n{script}\n</script>\n</body>\n</html>\n",
+            lang = escape(lang),
+            title = escape(title),
+        ),
+    };
+    out.write_all(page.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// `m:ss`, or `h:mm:ss` past the first hour.
+fn display_time(ms: i64) -> String {
+    let secs = ms / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::HtmlCfg,
+        formats::{
+            testutil::{cue, render, spoken, timings},
+            xml::parse_document,
+        },
+    };
+
+    fn with_template(name: &str, template: &str, f: impl FnOnce(&mut HtmlCfg)) -> Config {
+        let path = std::env::temp_dir().join(format!("html-{name}-{}.html", std::process::id()));
+        fs::write(&path, template).unwrap();
+        let mut cfg = Config::default();
+        cfg.formats.html.template = Some(path.to_string_lossy().into_owned());
+        f(&mut cfg.formats.html);
+        cfg
+    }
+
+    fn render_and_clean(t: &Transcript, cfg: &Config) -> String {
+        let out = render(&Html, t, cfg);
+        fs::remove_file(cfg.formats.html.template.as_deref().unwrap()).unwrap();
+        out
+    }
+
+    #[test]
+    fn cue_spans_roundtrip_through_their_data_attributes() {
+        let cfg = with_template("spans", "<main>{{transcript}}</main>", |_| {});
+        let t = Transcript::new(vec![
+            spoken(0, 1500, "Ann", "Fish & chips"),
+            spoken(1500, 2500, "Ann", "{{title}}"),
+            cue(9000, 3_700_000, "Later"),
+        ]);
+        let out = render_and_clean(&t, &cfg);
+        assert!(out.contains("<span class=\"speaker\">Ann</span>"));
+        assert!(out.contains("{{title}}</span>"));
+
+        let doc = parse_document(&out).unwrap();
+        let mut spans = Vec::new();
+        doc.descendants_named("span", &mut spans);
+        let back: Vec<_> = spans
+            .iter()
+            .filter(|s| s.attr("class") == Some("cue"))
+            .map(|s| {
+                let secs = |a: &str| (s.attr(a).unwrap().parse::<f64>().unwrap() * 1000.0) as i64;
+                let ts = s.child("a").unwrap().text();
+                let text = s.text()[ts.len()..].to_string();
+                cue(secs("data-start"), secs("data-end"), &text)
+            })
+            .collect();
+        assert_eq!(timings(&Transcript::new(back)), timings(&t));
+    }
+
+    #[test]
+    fn paragraphs_break_on_speaker_and_pause() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "One"),
+            spoken(1000, 2000, "Ann", "Two"),
+            spoken(4000, 5000, "Ann", "Three"),
+            spoken(5000, 6000, "Bob", "Four"),
+        ]);
+        let out = render(&Html, &t, &cfg);
+        let paragraphs: Vec<&str> = out.lines().filter(|l| l.starts_with("<p>")).collect();
+        assert_eq!(paragraphs.len(), 3);
+        assert!(paragraphs[0].contains("One</span> <span class=\"cue\" id=\"cue-2\""));
+        assert!(!paragraphs[1].contains("class=\"speaker\""));
+        assert!(paragraphs[2].starts_with("<p><span class=\"speaker\">Bob</span>"));
+        assert!(out.contains("<a class=\"ts\" href=\"#cue-3\">0:04</a>Three"));
+    }
+
+    #[test]
+    fn page_header_media_and_script() {
+        let mut cfg = Config::default();
+        cfg.formats.html.media_src = Some("talk.mp4?a=1&b=2".to_string());
+        cfg.formats.html.media_element = HtmlMediaElement::Video;
+        cfg.formats.html.autoscroll = false;
+        let mut t = Transcript::new(vec![cue(0, 1000, "x")]);
+        t.meta.title = Some("Q&A".to_string());
+        t.meta.language = Some("de".to_string());
+
+        let out = render(&Html, &t, &cfg);
+        assert!(out.starts_with("<!DOCTYPE html>\n<html lang=\"de\">\n"));
+        assert!(out.contains("<title>Q&amp;A</title>"));
+        assert!(out.contains("<video id=\"media\" class=\"media\" controls preload=\"metadata\" src=\"talk.mp4?a=1&amp;b=2\"></video>"));
+        assert!(out.contains("if (false) next.scrollIntoView"));
+        assert!(out.contains(".cue.active"));
+    }
+
+    #[test]
+    fn template_placeholders_and_errors() {
+        let cfg = with_template(
+            "placeholders",
+            "{{lang}}|{{title}}|{{css}}|{{media}}|",
+            |c| {
+                c.title = Some("T".to_string());
+                c.css = Some("p{}".to_string());
+            },
+        );
+        assert_eq!(
+            render_and_clean(&Transcript::new(Vec::new()), &cfg),
+            "en|T|p{}||"
+        );
+
+        let mut cfg = Config::default();
+        cfg.formats.html.template = Some("/nonexistent/page.html".to_string());
+        let mut out = Vec::new();
+        let err = Html
+            .write(&Transcript::new(Vec::new()), &cfg, &mut out)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("failed reading HTML template"));
+    }
+
+    #[test]
+    fn display_times() {
+        assert_eq!(display_time(59_999), "0:59");
+        assert_eq!(display_time(600_000), "10:00");
+        assert_eq!(display_time(3_723_000), "1:02:03");
+    }
+}
@@ -1,8 +1,69 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::io::Write;
 
-use crate::model::{Cue, Transcript};
+use crate::{
+    config::Config,
+    formats::{
+        asr::seconds_to_ms,
+        assemblyai, aws_transcribe, deepgram, json_mapping,
+        registry::{CollectSink, CueSink, FormatEntry, FormatReader, FormatWriter},
+        speech_marks, whisper_cpp,
+    },
+    model::{Cue, Meta, Transcript, Word},
+};
+
+pub struct Json;
+
+impl FormatReader for Json {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_json(input, cfg)
+    }
+}
+
+impl FormatWriter for Json {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        if let Some(m) = &cfg.formats.json.mapping {
+            return Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+                json_mapping::render_mapped(t, m, out)
+            })));
+        }
+        let wrapped = cfg.formats.json.wrapped;
+        if wrapped {
+            write!(
+                out,
+                "{{\n  \"schema\": {},\n  \"version\": {},\n  \"cues\": [",
+                serde_json::to_string(SCHEMA)?,
+                SCHEMA_VERSION
+            )?;
+        } else {
+            write!(out, "[")?;
+        }
+        Ok(Box::new(JsonSink {
+            out,
+            time_units: cfg.formats.json.time_units.as_str(),
+            wrapped,
+            count: 0,
+        }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("json", &["json"])
+        .config_section("json")
+        .sniff(|raw| {
+            let trimmed = raw.trim_start();
+            trimmed.starts_with('{') || trimmed.starts_with('[')
+        })
+        .reader(Json)
+        .writer(Json)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WrappedJson {
@@ -18,37 +79,74 @@ pub struct JsonCue {
     pub text: String,
     #[serde(default)]
     pub speaker: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<JsonWord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonWord {
+    pub start: Value,
+    pub end: Value,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
 }
 
-pub fn write_json(t: &Transcript, cfg_time_units: &str, wrapped: bool) -> Result<String> {
-    if wrapped {
-        let w = WrappedJson {
-            schema: "subxform.transcript".to_string(),
-            version: 1,
-            cues: t
-                .cues
+const SCHEMA: &str = "subxform.transcript";
+const SCHEMA_VERSION: u32 = 1;
+
+/// Emits the same layout as `serde_json::to_string_pretty` on `WrappedJson`
+/// (or a bare cue array), one cue at a time.
+struct JsonSink<'a> {
+    out: &'a mut dyn Write,
+    time_units: &'a str,
+    wrapped: bool,
+    count: usize,
+}
+
+impl CueSink for JsonSink<'_> {
+    fn push(&mut self, c: &Cue) -> Result<()> {
+        let cue = JsonCue {
+            start: encode_time(c.start_ms, self.time_units),
+            end: encode_time(c.end_ms, self.time_units),
+            text: c.text.clone(),
+            speaker: c.speaker.clone(),
+            words: c
+                .words
                 .iter()
-                .map(|c| JsonCue {
-                    start: encode_time(c.start_ms, cfg_time_units),
-                    end: encode_time(c.end_ms, cfg_time_units),
-                    text: c.text.clone(),
-                    speaker: c.speaker.clone(),
+                .map(|w| JsonWord {
+                    start: encode_time(w.start_ms, self.time_units),
+                    end: encode_time(w.end_ms, self.time_units),
+                    text: w.text.clone(),
+                    confidence: w.confidence,
                 })
                 .collect(),
         };
-        Ok(serde_json::to_string_pretty(&w)?)
-    } else {
-        let cues: Vec<JsonCue> = t
-            .cues
-            .iter()
-            .map(|c| JsonCue {
-                start: encode_time(c.start_ms, cfg_time_units),
-                end: encode_time(c.end_ms, cfg_time_units),
-                text: c.text.clone(),
-                speaker: c.speaker.clone(),
-            })
-            .collect();
-        Ok(serde_json::to_string_pretty(&cues)?)
+        let indent = if self.wrapped { "    " } else { "  " };
+        let sep = if self.count == 0 { "\n" } else { ",\n" };
+        self.out.write_all(sep.as_bytes())?;
+        let pretty = serde_json::to_string_pretty(&cue)?;
+        for (i, line) in pretty.lines().enumerate() {
+            if i > 0 {
+                self.out.write_all(b"\n")?;
+            }
+            write!(self.out, "{indent}{line}")?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        if self.count > 0 {
+            let close_indent = if self.wrapped { "  " } else { "" };
+            write!(self.out, "\n{close_indent}")?;
+        }
+        self.out.write_all(b"]")?;
+        if self.wrapped {
+            self.out.write_all(b"\n}")?;
+        }
+        self.out.flush()?;
+        Ok(())
     }
 }
 
@@ -59,15 +157,51 @@ fn encode_time(ms: i64, units: &str) -> Value {
     }
 }
 
-pub fn parse_json(input: &str) -> Result<Transcript> {
+pub fn parse_json(input: &str, cfg: &Config) -> Result<Transcript> {
+    // Polly speech marks are saved as `.json` too, but hold one object per line.
+    if speech_marks::looks_like_polly(input) {
+        return speech_marks::parse_polly(input, cfg);
+    }
+
     let v: Value = serde_json::from_str(input)?;
 
+    if let Some(m) = &cfg.formats.json.mapping
+        && let Some(t) = json_mapping::parse_mapped(&v, m)?
+    {
+        return Ok(t);
+    }
+
     if let Some(cues) = v.get("cues") {
         return parse_json_cues_array(cues);
     }
 
     if let Some(segs) = v.get("segments") {
-        return parse_json_segments_array(segs);
+        let mut t = parse_json_segments_array(segs)?;
+        t.meta.language = v
+            .get("language")
+            .and_then(|x| x.as_str())
+            .map(|s| s.to_string());
+        return Ok(t);
+    }
+
+    if whisper_cpp::looks_like(&v) {
+        return whisper_cpp::parse(&v);
+    }
+
+    if aws_transcribe::looks_like(&v) {
+        return aws_transcribe::parse(&v, cfg);
+    }
+
+    if deepgram::looks_like(&v) {
+        return deepgram::parse(&v, cfg);
+    }
+
+    if assemblyai::looks_like(&v) {
+        return assemblyai::parse(&v, cfg);
+    }
+
+    if speech_marks::looks_like_timepoints(&v) {
+        return speech_marks::parse_timepoints(&v, cfg);
     }
 
     if v.is_array() {
@@ -102,11 +236,17 @@ fn parse_json_cues_array(v: &Value) -> Result<Transcript> {
             .and_then(|x| x.as_str())
             .map(|s| s.to_string());
 
+        let words = match obj.get("words") {
+            Some(w) => parse_json_words(w)?,
+            None => Vec::new(),
+        };
+
         cues.push(Cue {
             start_ms,
             end_ms,
             text,
             speaker,
+            words,
         });
     }
 
@@ -124,25 +264,121 @@ fn parse_json_segments_array(v: &Value) -> Result<Transcript> {
             .as_object()
             .ok_or_else(|| anyhow!("segment must be an object"))?;
         let start_ms =
-            decode_time_to_ms(obj.get("start").ok_or_else(|| anyhow!("missing start"))?)?;
-        let end_ms = decode_time_to_ms(obj.get("end").ok_or_else(|| anyhow!("missing end"))?)?;
+            decode_seconds_to_ms(obj.get("start").ok_or_else(|| anyhow!("missing start"))?)?;
+        let end_ms = decode_seconds_to_ms(obj.get("end").ok_or_else(|| anyhow!("missing end"))?)?;
         let text = obj
             .get("text")
             .and_then(|x| x.as_str())
             .unwrap_or("")
             .to_string();
 
+        // WhisperX adds diarization and aligned words to Whisper's segments.
+        let speaker = obj
+            .get("speaker")
+            .and_then(|x| x.as_str())
+            .map(|s| s.to_string());
+        let words = match obj.get("words") {
+            Some(w) => parse_segment_words(w, start_ms, end_ms)?,
+            None => Vec::new(),
+        };
+
         cues.push(Cue {
             start_ms,
             end_ms,
             text,
-            speaker: None,
+            speaker,
+            words,
         });
     }
 
     Ok(Transcript::new(cues))
 }
 
+fn parse_json_words(v: &Value) -> Result<Vec<Word>> {
+    let arr = v
+        .as_array()
+        .ok_or_else(|| anyhow!("words must be an array"))?;
+    let mut words = Vec::with_capacity(arr.len());
+
+    for item in arr {
+        let obj = item
+            .as_object()
+            .ok_or_else(|| anyhow!("word must be an object"))?;
+        let start_ms = decode_time_to_ms(
+            obj.get("start")
+                .ok_or_else(|| anyhow!("word missing start"))?,
+        )?;
+        let end_ms = decode_time_to_ms(obj.get("end").ok_or_else(|| anyhow!("word missing end"))?)?;
+        let text = obj
+            .get("text")
+            .and_then(|x| x.as_str())
+            .ok_or_else(|| anyhow!("word missing text"))?
+            .to_string();
+        let confidence = obj.get("confidence").and_then(|x| x.as_f64());
+        words.push(Word {
+            start_ms,
+            end_ms,
+            text,
+            confidence,
+        });
+    }
+
+    Ok(words)
+}
+
+/// Whisper and WhisperX word entries (`word`, `probability`/`score`). WhisperX
+/// leaves words it could not align (numbers, symbols) without timings; those
+/// are pinned to the end of the previous word.
+fn parse_segment_words(v: &Value, seg_start_ms: i64, seg_end_ms: i64) -> Result<Vec<Word>> {
+    let arr = v
+        .as_array()
+        .ok_or_else(|| anyhow!("words must be an array"))?;
+    let mut words: Vec<Word> = Vec::with_capacity(arr.len());
+
+    for item in arr {
+        let obj = item
+            .as_object()
+            .ok_or_else(|| anyhow!("word must be an object"))?;
+        let text = obj
+            .get("word")
+            .or_else(|| obj.get("text"))
+            .and_then(|x| x.as_str())
+            .ok_or_else(|| anyhow!("word missing text"))?
+            .trim()
+            .to_string();
+        let prev_end = words.last().map_or(seg_start_ms, |w| w.end_ms);
+        let start_ms = match obj.get("start") {
+            Some(t) => decode_seconds_to_ms(t)?,
+            None => prev_end,
+        };
+        let end_ms = match obj.get("end") {
+            Some(t) => decode_seconds_to_ms(t)?,
+            None => start_ms.min(seg_end_ms),
+        };
+        let confidence = ["probability", "score", "confidence"]
+            .iter()
+            .find_map(|k| obj.get(*k).and_then(|x| x.as_f64()));
+        words.push(Word {
+            start_ms,
+            end_ms,
+            text,
+            confidence,
+        });
+    }
+
+    Ok(words)
+}
+
+/// Whisper and WhisperX segment times are always seconds, including whole
+/// seconds written as integers.
+fn decode_seconds_to_ms(v: &Value) -> Result<i64> {
+    match (seconds_to_ms(v), v) {
+        (Some(ms), _) => Ok(ms),
+        (None, Value::String(s)) => crate::formats::time::parse_time_to_ms(s),
+        _ => Err(anyhow!("unsupported time type")),
+    }
+}
+
 fn decode_time_to_ms(v: &Value) -> Result<i64> {
     match v {
         Value::Number(n) => {
@@ -166,3 +402,77 @@ fn decode_time_to_ms(v: &Value) -> Result<i64> {
         _ => Err(anyhow!("unsupported time type")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, spoken, timings, word};
+
+    #[test]
+    fn wrapped_roundtrip_keeps_speakers_and_words() {
+        let cfg = Config::default();
+        let mut first = spoken(1000, 2000, "Ann", "Hello you");
+        first.words = vec![word(1000, 1500, "Hello"), word(1500, 2000, "you")];
+        let t = Transcript::new(vec![first, cue(2500, 3000, "Bye")]);
+
+        let out = render(&Json, &t, &cfg);
+        let v: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(v["schema"], SCHEMA);
+        assert_eq!(v["cues"][0]["start"], 1.0);
+
+        let back = parse_json(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.cues[0].speaker.as_deref(), Some("Ann"));
+        assert_eq!(back.cues[0].words.len(), 2);
+        assert_eq!(back.cues[0].words[1].start_ms, 1500);
+    }
+
+    #[test]
+    fn bare_array_in_ms() {
+        let mut cfg = Config::default();
+        cfg.formats.json.wrapped = false;
+        cfg.formats.json.time_units = "ms".to_string();
+        let out = render(&Json, &Transcript::new(vec![cue(1000, 2000, "Hi")]), &cfg);
+        assert!(out.starts_with('['));
+        assert_eq!(
+            timings(&parse_json(&out, &cfg).unwrap()),
+            vec![(1000, 2000, "Hi")]
+        );
+    }
+
+    #[test]
+    fn empty_transcript_is_valid_json() {
+        let cfg = Config::default();
+        let out = render(&Json, &Transcript::new(Vec::new()), &cfg);
+        let v: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(v["cues"], Value::Array(Vec::new()));
+    }
+
+    #[test]
+    fn whisper_segments_carry_language() {
+        let cfg = Config::default();
+        let raw = r#"{"language":"en","segments":[{"start":0.5,"end":1.25,"text":" Hi"}]}"#;
+        let t = parse_json(raw, &cfg).unwrap();
+        assert_eq!(t.meta.language.as_deref(), Some("en"));
+        assert_eq!(timings(&t), vec![(500, 1250, " Hi")]);
+    }
+
+    #[test]
+    fn whole_second_segment_times_are_seconds() {
+        let cfg = Config::default();
+        let raw = r#"{"segments":[{"start":3,"end":"4","text":"Hi",
+            "words":[{"word":"Hi","start":3,"end":4}]}]}"#;
+        let t = parse_json(raw, &cfg).unwrap();
+        assert_eq!(timings(&t), vec![(3000, 4000, "Hi")]);
+        assert_eq!(
+            (t.cues[0].words[0].start_ms, t.cues[0].words[0].end_ms),
+            (3000, 4000)
+        );
+    }
+
+    #[test]
+    fn unknown_shape_is_an_error() {
+        let cfg = Config::default();
+        assert!(parse_json(r#"{"hello":"world"}"#, &cfg).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use anyhow::{Result, anyhow};
+use serde_json::{Map, Value};
+use std::io::Write;
+
+use crate::{
+    config::JsonMappingCfg,
+    formats::{
+        asr::number,
+        time::{format_vtt_timestamp, parse_time_to_ms},
+    },
+    model::{Cue, Transcript, Word},
+};
+
+/// Reads `v` through the configured mapping, or `None` when the mapped cue
+/// array is not there, so the built-in shapes can be tried instead.
+pub fn parse_mapped(v: &Value, m: &JsonMappingCfg) -> Result<Option<Transcript>> {
+    let Some(items) = lookup(v, &m.cues).and_then(Value::as_array) else {
+        return Ok(None);
+    };
+    let mut cues = Vec::with_capacity(items.len());
+
+    for (i, item) in items.iter().enumerate() {
+        let field = |path: &str| {
+            lookup(item, path).ok_or_else(|| anyhow!("mapped cue {i}: missing '{path}'"))
+        };
+        let words = match m.words.as_deref().and_then(|p| lookup(item, p)) {
+            Some(Value::Array(ws)) => ws
+                .iter()
+                .enumerate()
+                .map(|(j, w)| parse_word(w, m).map_err(|e| anyhow!("mapped cue {i} word {j}: {e}")))
+                .collect::<Result<Vec<_>>>()?,
+            Some(Value::Null) | None => Vec::new(),
+            Some(_) => return Err(anyhow!("mapped cue {i}: words must be an array")),
+        };
+        cues.push(Cue {
+            start_ms: decode_time(field(&m.start)?, &m.time_units)?,
+            end_ms: decode_time(field(&m.end)?, &m.time_units)?,
+            text: string(field(&m.text)?),
+            speaker: m
+                .speaker
+                .as_deref()
+                .and_then(|p| lookup(item, p))
+                .filter(|s| !s.is_null())
+                .map(string),
+            words,
+        });
+    }
+
+    Ok(Some(Transcript::new(cues)))
+}
+
+fn parse_word(w: &Value, m: &JsonMappingCfg) -> Result<Word> {
+    let field = |path: &str| lookup(w, path).ok_or_else(|| anyhow!("missing '{path}'"));
+    Ok(Word {
+        start_ms: decode_time(field(&m.word_start)?, &m.time_units)?,
+        end_ms: decode_time(field(&m.word_end)?, &m.time_units)?,
+        text: string(field(&m.word_text)?),
+        confidence: m
+            .word_confidence
+            .as_deref()
+            .and_then(|p| lookup(w, p))
+            .and_then(number),
+    })
+}
+
+/// Writes cues as objects with the mapped field names, nested under the
+/// mapped cue path.
+pub fn render_mapped(t: &Transcript, m: &JsonMappingCfg, out: &mut dyn Write) -> Result<()> {
+    let mut cues = Vec::with_capacity(t.cues.len());
+    for c in &t.cues {
+        let mut obj = Value::Object(Map::new());
+        insert(&mut obj, &m.start, encode_time(c.start_ms, &m.time_units))?;
+        insert(&mut obj, &m.end, encode_time(c.end_ms, &m.time_units))?;
+        insert(&mut obj, &m.text, Value::from(c.text.as_str()))?;
+        if let (Some(path), Some(speaker)) = (&m.speaker, &c.speaker) {
+            insert(&mut obj, path, Value::from(speaker.as_str()))?;
+        }
+        if let Some(path) = &m.words
+            && !c.words.is_empty()
+        {
+            let mut words = Vec::with_capacity(c.words.len());
+            for w in &c.words {
+                let mut wo = Value::Object(Map::new());
+                insert(
+                    &mut wo,
+                    &m.word_start,
+                    encode_time(w.start_ms, &m.time_units),
+                )?;
+                insert(&mut wo, &m.word_end, encode_time(w.end_ms, &m.time_units))?;
+                insert(&mut wo, &m.word_text, Value::from(w.text.as_str()))?;
+                if let (Some(path), Some(conf)) = (&m.word_confidence, w.confidence) {
+                    insert(&mut wo, path, Value::from(conf))?;
+                }
+                words.push(wo);
+            }
+            insert(&mut obj, path, Value::Array(words))?;
+        }
+        cues.push(obj);
+    }
+
+    let mut doc = Value::Null;
+    insert(&mut doc, &m.cues, Value::Array(cues))?;
+    serde_json::to_writer_pretty(&mut *out, &doc)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Follows a dotted path (`data.results`, `alternatives.0.text`); an empty
+/// path is the value itself.
+fn lookup<'v>(v: &'v Value, path: &str) -> Option<&'v Value> {
+    if path.is_empty() {
+        return Some(v);
+    }
+    path.split('.').try_fold(v, |v, key| match v {
+        Value::Array(a) => a.get(key.parse::<usize>().ok()?),
+        _ => v.get(key),
+    })
+}
+
+/// Sets a dotted path, creating objects along the way.
+fn insert(target: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    let mut cur = target;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        if cur.is_null() {
+            *cur = Value::Object(Map::new());
+        }
+        let obj = cur
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("JSON mapping: '{path}' collides with another field"))?;
+        if keys.peek().is_none() {
+            obj.insert(key.to_string(), value);
+            return Ok(());
+        }
+        cur = obj.entry(key).or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+fn string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn decode_time(v: &Value, units: &str) -> Result<i64> {
+    let bad = || anyhow!("unrecognized time value: {v}");
+    match units {
+        "ms" => number(v).map(|ms| ms.round() as i64).ok_or_else(bad),
+        "timestamp" => parse_time_to_ms(v.as_str().ok_or_else(bad)?),
+        _ => number(v)
+            .map(|secs| (secs * 1000.0).round() as i64)
+            .ok_or_else(bad),
+    }
+}
+
+fn encode_time(ms: i64, units: &str) -> Value {
+    match units {
+        "ms" => Value::from(ms),
+        "timestamp" => Value::from(format_vtt_timestamp(ms)),
+        _ => Value::from((ms as f64) / 1000.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        formats::{
+            json::{Json, parse_json},
+            testutil::{cue, render, timings, word},
+        },
+    };
+    use serde_json::json;
+
+    fn nested() -> JsonMappingCfg {
+        JsonMappingCfg {
+            cues: "data.items".to_string(),
+            start: "timing.begin".to_string(),
+            end: "timing.finish".to_string(),
+            text: "transcript".to_string(),
+            speaker: Some("who".to_string()),
+            words: Some("tokens".to_string()),
+            word_start: "t0".to_string(),
+            word_end: "t1".to_string(),
+            word_text: "w".to_string(),
+            word_confidence: Some("p".to_string()),
+            time_units: "ms".to_string(),
+        }
+    }
+
+    #[test]
+    fn roundtrip_through_json_format() {
+        let mut cfg = Config::default();
+        cfg.formats.json.mapping = Some(nested());
+        let mut c = cue(1000, 2000, "Hi there");
+        c.speaker = Some("Ann".to_string());
+        c.words = vec![word(1000, 1400, "Hi"), word(1500, 2000, "there")];
+        c.words[0].confidence = Some(0.5);
+        let t = Transcript::new(vec![c, cue(2500, 3000, "Bye")]);
+
+        let out = render(&Json, &t, &cfg);
+        let v: Value = serde_json::from_str(&out).unwrap();
+        let first = &v["data"]["items"][0];
+        assert_eq!(first["timing"], json!({"begin": 1000, "finish": 2000}));
+        assert_eq!(first["who"], "Ann");
+        assert_eq!(
+            first["tokens"][0],
+            json!({"t0": 1000, "t1": 1400, "w": "Hi", "p": 0.5})
+        );
+        assert!(v["data"]["items"][1].get("who").is_none());
+        assert!(v["data"]["items"][1].get("tokens").is_none());
+
+        let back = parse_json(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.cues[0].speaker.as_deref(), Some("Ann"));
+        assert_eq!(back.cues[0].words.len(), 2);
+        assert_eq!(back.cues[0].words[0].confidence, Some(0.5));
+        assert_eq!(back.cues[1].speaker, None);
+    }
+
+    #[test]
+    fn bare_array_with_timestamps_and_index_paths() {
+        let m = JsonMappingCfg {
+            start: "span.0".to_string(),
+            end: "span.1".to_string(),
+            time_units: "timestamp".to_string(),
+            ..JsonMappingCfg::default()
+        };
+        let v = json!([{"span": ["00:00:01.000", "00:00:02.500"], "text": 7}]);
+        let t = parse_mapped(&v, &m).unwrap().unwrap();
+        assert_eq!(timings(&t), vec![(1000, 2500, "7")]);
+
+        let mut out = Vec::new();
+        render_mapped(&t, &m, &mut out).unwrap();
+        let v: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            v[0]["span"],
+            json!({"0": "00:00:01.000", "1": "00:00:02.500"})
+        );
+    }
+
+    #[test]
+    fn seconds_are_the_default_unit() {
+        let v = json!([{"start": 1.25, "end": "2", "text": "Hi"}]);
+        let t = parse_mapped(&v, &JsonMappingCfg::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(timings(&t), vec![(1250, 2000, "Hi")]);
+    }
+
+    #[test]
+    fn missing_cue_array_falls_back_to_builtin_shapes() {
+        let mut cfg = Config::default();
+        cfg.formats.json.mapping = Some(nested());
+        let v = json!({"cues": [{"start": 1.0, "end": 2.0, "text": "Hi"}]});
+        assert!(parse_mapped(&v, &nested()).unwrap().is_none());
+        assert_eq!(
+            timings(&parse_json(&v.to_string(), &cfg).unwrap()),
+            vec![(1000, 2000, "Hi")]
+        );
+    }
+
+    #[test]
+    fn malformed_cues_are_errors() {
+        let m = nested();
+        let missing = json!({"data": {"items": [{"timing": {"begin": 0}, "transcript": "x"}]}});
+        let err = parse_mapped(&missing, &m).unwrap_err().to_string();
+        assert!(
+            err.contains("mapped cue 0: missing 'timing.finish'"),
+            "{err}"
+        );
+
+        let words = json!({"data": {"items": [
+            {"timing": {"begin": 0, "finish": 1}, "transcript": "x", "tokens": "x"}
+        ]}});
+        assert!(parse_mapped(&words, &m).is_err());
+
+        let bad_time = json!([{"start": "soon", "end": 1, "text": "x"}]);
+        assert!(parse_mapped(&bad_time, &JsonMappingCfg::default()).is_err());
+    }
+
+    #[test]
+    fn colliding_paths_are_rejected() {
+        let m = JsonMappingCfg {
+            end: "start.end".to_string(),
+            ..JsonMappingCfg::default()
+        };
+        let t = Transcript::new(vec![cue(0, 1000, "x")]);
+        let err = render_mapped(&t, &m, &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("collides"));
+    }
+}
@@ -0,0 +1,485 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::{
+        plain::synth_duration_ms,
+        registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+    },
+    model::{Cue, Meta, Transcript, Word},
+};
+
+pub struct Lrc;
+
+impl FormatReader for Lrc {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_lrc(input, cfg)
+    }
+}
+
+impl FormatWriter for Lrc {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        write_lrc_header(out, meta)?;
+        Ok(Box::new(LrcSink {
+            cfg,
+            out,
+            pending: None,
+        }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("lrc", &["lrc"])
+        .config_section("lrc")
+        .sniff(looks_like_lrc)
+        .reader(Lrc)
+        .writer(Lrc)
+}
+
+/// Lines sniffed before deciding; enough to get past a header of ID tags.
+const SNIFF_LINES: usize = 16;
+
+/// `[ti:...]`-style ID tag lines are skipped; every other line has to start
+/// with a time tag, and there has to be at least one.
+fn looks_like_lrc(raw: &str) -> bool {
+    let mut timed = 0;
+    for line in raw
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .take(SNIFF_LINES)
+    {
+        let Some((tag, rest)) = line.strip_prefix('[').and_then(|l| l.split_once(']')) else {
+            return false;
+        };
+        if parse_lrc_time(tag).is_ok() {
+            timed += 1;
+        } else if !rest.trim().is_empty() || !is_id_tag(tag) {
+            return false;
+        }
+    }
+    timed > 0
+}
+
+/// `key:value` with an alphabetic key, as in `ti:Title` or `length:03:20`.
+fn is_id_tag(tag: &str) -> bool {
+    tag.split_once(':')
+        .is_some_and(|(key, _)| !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// A time-tagged lyric line before end times are known.
+struct LrcLine {
+    start_ms: i64,
+    text: String,
+    words: Vec<Word>,
+}
+
+pub fn parse_lrc(input: &str, cfg: &Config) -> Result<Transcript> {
+    let mut meta = Meta::default();
+    let mut offset_ms: i64 = 0;
+    let mut lines: Vec<LrcLine> = Vec::new();
+
+    for (line_no, raw_line) in input.lines().enumerate() {
+        let mut rest = raw_line.trim().trim_start_matches('\u{feff}');
+        if rest.is_empty() {
+            continue;
+        }
+
+        let mut starts = Vec::new();
+        while let Some(tag_body) = rest.strip_prefix('[') {
+            let (tag, after) = tag_body
+                .split_once(']')
+                .ok_or_else(|| anyhow!("LRC line {}: unterminated tag", line_no + 1))?;
+            match parse_lrc_time(tag) {
+                Ok(ms) => starts.push(ms),
+                Err(_) => {
+                    if let Some((key, value)) = tag.split_once(':') {
+                        apply_meta_tag(&mut meta, &mut offset_ms, key.trim(), value.trim())?;
+                    }
+                }
+            }
+            rest = after.trim_start();
+        }
+
+        if starts.is_empty() {
+            if !rest.is_empty() {
+                tracing::debug!(line = line_no + 1, "skipping LRC line without time tag");
+            }
+            continue;
+        }
+
+        let (text, words) = parse_word_tags(rest)?;
+        for start_ms in starts {
+            lines.push(LrcLine {
+                start_ms,
+                text: text.clone(),
+                words: words.clone(),
+            });
+        }
+    }
+
+    // Repeated choruses share one line with several tags; play order is by time.
+    lines.sort_by_key(|l| l.start_ms);
+
+    let mut cues = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        // Empty lines only mark where the previous lyric stops.
+        if line.text.is_empty() {
+            continue;
+        }
+        let end_ms = match lines.get(i + 1) {
+            Some(next) => next.start_ms,
+            None => line
+                .words
+                .last()
+                .map(|w| w.end_ms)
+                .filter(|&e| e > line.start_ms)
+                .unwrap_or_else(|| line.start_ms + synth_duration_ms(&line.text, cfg)),
+        };
+
+        let shift = |ms: i64| ms - offset_ms;
+        cues.push(Cue {
+            start_ms: shift(line.start_ms),
+            end_ms: shift(end_ms),
+            text: line.text.clone(),
+            speaker: None,
+            words: line
+                .words
+                .iter()
+                .map(|w| Word {
+                    start_ms: shift(w.start_ms),
+                    end_ms: shift(if w.end_ms < 0 { end_ms } else { w.end_ms }),
+                    text: w.text.clone(),
+                    confidence: w.confidence,
+                })
+                .collect(),
+        });
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+fn apply_meta_tag(meta: &mut Meta, offset_ms: &mut i64, key: &str, value: &str) -> Result<()> {
+    let value_opt = Some(value.to_string()).filter(|v| !v.is_empty());
+    match key.to_lowercase().as_str() {
+        "ti" => meta.title = value_opt,
+        "ar" => meta.artist = value_opt,
+        "al" => meta.album = value_opt,
+        "la" | "lang" => meta.language = value_opt,
+        // Positive offsets make lyrics appear sooner.
+        "offset" => {
+            *offset_ms = value
+                .trim_start_matches('+')
+                .parse()
+                .map_err(|_| anyhow!("bad LRC offset: '{value}'"))?;
+        }
+        other => {
+            meta.tags.insert(other.to_string(), value.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Splits enhanced `<mm:ss.xx>word` tags out of a lyric line. Each word runs
+/// until the next tag; a trailing tag with no word after it closes the last
+/// word, otherwise its end stays unknown (negative) until the line end is known.
+fn parse_word_tags(line: &str) -> Result<(String, Vec<Word>)> {
+    if !line.contains('<') {
+        return Ok((line.trim().to_string(), Vec::new()));
+    }
+
+    let mut text = String::new();
+    let mut timed: Vec<(i64, String)> = Vec::new();
+    let mut rest = line;
+
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|c| open + c) else {
+            break;
+        };
+        let before = &rest[..open];
+        text.push_str(before);
+        if let Some((_, t)) = timed.last_mut() {
+            t.push_str(before);
+        }
+
+        match parse_lrc_time(&rest[open + 1..close]) {
+            Ok(ms) => timed.push((ms, String::new())),
+            // Not a time tag; keep it as literal text.
+            Err(_) => {
+                let literal = &rest[open..=close];
+                text.push_str(literal);
+                if let Some((_, t)) = timed.last_mut() {
+                    t.push_str(literal);
+                }
+            }
+        }
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+    if let Some((_, t)) = timed.last_mut() {
+        t.push_str(rest);
+    }
+
+    let mut words = Vec::new();
+    for (i, (start_ms, t)) in timed.iter().enumerate() {
+        let t = t.trim();
+        if t.is_empty() {
+            continue;
+        }
+        words.push(Word {
+            start_ms: *start_ms,
+            end_ms: timed.get(i + 1).map(|(ms, _)| *ms).unwrap_or(-1),
+            text: t.to_string(),
+            confidence: None,
+        });
+    }
+
+    Ok((text.split_whitespace().collect::<Vec<_>>().join(" "), words))
+}
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss.xxx`; minutes may exceed 59. Only `.`
+/// separates the fraction, so `[hh:mm:ss]` markers are not read as LRC times.
+fn parse_lrc_time(s: &str) -> Result<i64> {
+    let s = s.trim();
+    let (min_s, sec_s) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow!("not an LRC time: '{s}'"))?;
+    if min_s.is_empty() || !min_s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("not an LRC time: '{s}'"));
+    }
+    let (whole, frac) = match sec_s.split_once('.') {
+        Some((w, f)) => (w, f),
+        None => (sec_s, ""),
+    };
+    if whole.is_empty()
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !frac.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(anyhow!("not an LRC time: '{s}'"));
+    }
+
+    let min: i64 = min_s.parse()?;
+    let sec: i64 = whole.parse()?;
+    let mut frac_s = frac.to_string();
+    frac_s.truncate(3);
+    while frac_s.len() < 3 {
+        frac_s.push('0');
+    }
+    let milli: i64 = frac_s.parse()?;
+
+    Ok((min * 60 + sec) * 1000 + milli)
+}
+
+fn format_lrc_time(ms_in: i64) -> String {
+    let ms = ms_in.max(0);
+    let centis = (ms % 1000) / 10;
+    let total_seconds = ms / 1000;
+    let sec = total_seconds % 60;
+    let min = total_seconds / 60;
+    format!("{min:02}:{sec:02}.{centis:02}")
+}
+
+/// ID tags carried over from `Meta::tags`. Other formats' tags (`fps`,
+/// `job_name`, ...) are not LRC, and `offset` is already applied to the times.
+const ID_TAGS: [&str; 5] = ["au", "by", "re", "ve", "length"];
+
+fn write_lrc_header(out: &mut dyn Write, meta: &Meta) -> Result<()> {
+    let known = [
+        ("ti", &meta.title),
+        ("ar", &meta.artist),
+        ("al", &meta.album),
+    ];
+    for (key, value) in known {
+        if let Some(v) = value {
+            writeln!(out, "[{key}:{v}]")?;
+        }
+    }
+    for key in ID_TAGS {
+        if let Some(value) = meta.tags.get(key) {
+            writeln!(out, "[{key}:{value}]")?;
+        }
+    }
+    Ok(())
+}
+
+/// Holds one cue back so its end can be compared with the next start.
+struct LrcSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    pending: Option<Cue>,
+}
+
+impl LrcSink<'_> {
+    fn write_cue(&mut self, cue: &Cue, next_start: Option<i64>) -> Result<()> {
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        write!(self.out, "[{}]", format_lrc_time(cue.start_ms + offset))?;
+        if self.cfg.formats.lrc.enhanced && !cue.words.is_empty() {
+            for (i, w) in cue.words.iter().enumerate() {
+                if i > 0 {
+                    self.out.write_all(b" ")?;
+                }
+                write!(
+                    self.out,
+                    "<{}>{}",
+                    format_lrc_time(w.start_ms + offset),
+                    w.text
+                )?;
+            }
+            let last_end = cue.words.last().map(|w| w.end_ms).unwrap_or(cue.end_ms);
+            writeln!(self.out, " <{}>", format_lrc_time(last_end + offset))?;
+        } else {
+            writeln!(self.out, "{text}")?;
+        }
+
+        let gap = next_start.is_none_or(|n| n > cue.end_ms);
+        if self.cfg.formats.lrc.end_markers && gap {
+            writeln!(self.out, "[{}]", format_lrc_time(cue.end_ms + offset))?;
+        }
+        Ok(())
+    }
+}
+
+impl CueSink for LrcSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        if let Some(prev) = self.pending.take() {
+            self.write_cue(&prev, Some(cue.start_ms))?;
+        }
+        self.pending = Some(cue.clone());
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if let Some(prev) = self.pending.take() {
+            self.write_cue(&prev, None)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings, word};
+
+    #[test]
+    fn roundtrip_with_header_and_end_markers() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![
+            cue(1000, 3000, "First line"),
+            cue(3000, 4500, "Second"),
+            cue(6000, 7000, "After a break"),
+        ]);
+        t.meta.title = Some("Song".to_string());
+        t.meta.tags.insert("by".to_string(), "me".to_string());
+
+        let out = render(&Lrc, &t, &cfg);
+        assert_eq!(
+            out,
+            "[ti:Song]\n[by:me]\n[00:01.00]First line\n[00:03.00]Second\n[00:04.50]\n\
+             [00:06.00]After a break\n[00:07.00]\n"
+        );
+        let back = parse_lrc(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.meta.title.as_deref(), Some("Song"));
+        assert_eq!(back.meta.tags.get("by").map(String::as_str), Some("me"));
+    }
+
+    #[test]
+    fn header_only_carries_lrc_tags() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![cue(0, 1000, "Hi")]);
+        for key in ["fps", "job_name", "offset", "length"] {
+            t.meta.tags.insert(key.to_string(), "1".to_string());
+        }
+        assert_eq!(
+            render(&Lrc, &t, &cfg),
+            "[length:1]\n[00:00.00]Hi\n[00:01.00]\n"
+        );
+    }
+
+    #[test]
+    fn enhanced_word_timing_roundtrip() {
+        let cfg = Config::default();
+        let mut c = cue(1000, 2000, "Hello world");
+        c.words = vec![word(1000, 1400, "Hello"), word(1400, 2000, "world")];
+        let t = Transcript::new(vec![c]);
+
+        let out = render(&Lrc, &t, &cfg);
+        assert_eq!(
+            out,
+            "[00:01.00]<00:01.00>Hello <00:01.40>world <00:02.00>\n[00:02.00]\n"
+        );
+        let back = parse_lrc(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), vec![(1000, 2000, "Hello world")]);
+        let words: Vec<_> = back.cues[0]
+            .words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str()))
+            .collect();
+        assert_eq!(words, vec![(1000, 1400, "Hello"), (1400, 2000, "world")]);
+    }
+
+    #[test]
+    fn repeated_tags_offset_and_open_end() {
+        let cfg = Config::default();
+        let raw = "[offset:+500]\n[00:10.00][00:01.5]Chorus\n[00:05.123]Verse\n";
+        let t = parse_lrc(raw, &cfg).unwrap();
+        let last_end = 9500 + synth_duration_ms("Chorus", &cfg);
+        assert_eq!(
+            timings(&t),
+            vec![
+                (1000, 4623, "Chorus"),
+                (4623, 9500, "Verse"),
+                (9500, last_end, "Chorus")
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_offset_is_an_error() {
+        let cfg = Config::default();
+        assert!(parse_lrc("[offset:soon]\n[00:01.00]Hi\n", &cfg).is_err());
+    }
+
+    #[test]
+    fn time_tags() {
+        assert_eq!(parse_lrc_time("01:02.5").unwrap(), 62_500);
+        assert_eq!(parse_lrc_time("75:00").unwrap(), 4_500_000);
+        assert_eq!(parse_lrc_time("00:01.2345").unwrap(), 1234);
+        assert!(parse_lrc_time("00:01:00").is_err());
+        assert!(parse_lrc_time("ti:Song").is_err());
+        assert_eq!(format_lrc_time(62_519), "01:02.51");
+    }
+
+    #[test]
+    fn sniff_skips_id_tags() {
+        assert!(looks_like_lrc(
+            "\u{feff}[ti:Song]\n[ar:Band]\n[length:03:20]\n\n[00:01.00]Hi\n"
+        ));
+        assert!(looks_like_lrc("[00:01.00]<00:01.00>Hi <00:01.50>there\n"));
+    }
+
+    #[test]
+    fn sniff_false_positives() {
+        // Transcript markers and sound cues are not lyrics.
+        assert!(!looks_like_lrc(
+            "[00:00:01] Alice: hi\n[00:00:05] Bob: hello\n"
+        ));
+        assert!(!looks_like_lrc("[Music]\n[Applause]\n"));
+        assert!(!looks_like_lrc("[ti:Only tags]\n[ar:Nobody]\n"));
+        assert!(!looks_like_lrc("[00:01.00]Hi\nplain text line\n"));
+        assert!(!looks_like_lrc(
+            "[note: see below] some prose\n[00:01.00]Hi\n"
+        ));
+    }
+}
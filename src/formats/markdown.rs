@@ -0,0 +1,255 @@
+use anyhow::Result;
+use std::io::Write;
+
+use crate::{
+    config::{Config, MarkdownToc},
+    formats::registry::{CollectSink, CueSink, FormatEntry, FormatWriter},
+    model::{Meta, Transcript},
+};
+
+pub struct Markdown;
+
+impl FormatWriter for Markdown {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_markdown(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("markdown", &["md", "markdown"])
+        .config_section("markdown")
+        .writer(Markdown)
+}
+
+/// Consecutive cues of one speaker without a long pause between them.
+struct Paragraph {
+    start_ms: i64,
+    speaker: Option<String>,
+    /// First paragraph of a new speaker turn.
+    turn: Option<usize>,
+    /// First paragraph after a pause of `section_gap_ms` or more.
+    section: Option<usize>,
+    text: String,
+}
+
+fn paragraphs(t: &Transcript, cfg: &Config) -> Vec<Paragraph> {
+    let c = &cfg.formats.markdown;
+    let offset = cfg.policy.timestamp_offset_ms;
+    let mut out: Vec<Paragraph> = Vec::new();
+    let (mut turns, mut sections) = (0, 0);
+    let mut last_end = 0;
+
+    for cue in &t.cues {
+        let start_ms = (cue.start_ms + offset).max(0);
+        let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let gap = start_ms - last_end;
+        last_end = last_end.max((cue.end_ms + offset).max(0));
+
+        let new_turn = out.last().is_none_or(|p| p.speaker != cue.speaker);
+        let new_section = out.is_empty() || gap >= c.section_gap_ms;
+        if let Some(p) = out.last_mut()
+            && !new_turn
+            && !new_section
+            && gap < c.paragraph_gap_ms
+        {
+            p.text.push(' ');
+            p.text.push_str(&text);
+            continue;
+        }
+        out.push(Paragraph {
+            start_ms,
+            speaker: cue.speaker.clone(),
+            turn: new_turn.then(|| {
+                turns += 1;
+                turns
+            }),
+            section: new_section.then(|| {
+                sections += 1;
+                sections
+            }),
+            text,
+        });
+    }
+    out
+}
+
+/// Paragraphs break on speaker changes and pauses, a new speaker gets a bold
+/// heading, and `[hh:mm:ss]` markers are placed at most every
+/// `marker_interval_ms` (0 marks every paragraph). The optional table of
+/// contents links speaker turns or the sections between long pauses.
+fn write_markdown(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.markdown;
+    let paras = paragraphs(t, cfg);
+
+    if let Some(title) = c.title.as_deref().or(t.meta.title.as_deref()) {
+        writeln!(out, "# {}\n", escape(title))?;
+    }
+
+    if c.toc != MarkdownToc::None {
+        writeln!(out, "## Contents\n")?;
+        for p in &paras {
+            let entry = if c.toc == MarkdownToc::Speakers {
+                p.turn.map(|n| {
+                    let who = p.speaker.as_deref().unwrap_or("Unknown");
+                    (format!("turn-{n}"), escape(who))
+                })
+            } else {
+                p.section
+                    .map(|n| (format!("section-{n}"), escape(&preview(&p.text))))
+            };
+            if let Some((anchor, label)) = entry {
+                writeln!(
+                    out,
+                    "- [{} {label}](#{anchor})",
+                    hms(p.start_ms).replace('[', "\\[").replace(']', "\\]")
+                )?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    let mut last_marker: Option<i64> = None;
+    for p in &paras {
+        if c.toc == MarkdownToc::Sections
+            && let Some(n) = p.section
+        {
+            writeln!(out, "<a id=\"section-{n}\"></a>\n")?;
+        }
+        if let Some(n) = p.turn {
+            let anchor = if c.toc == MarkdownToc::Speakers {
+                format!(" <a id=\"turn-{n}\"></a>")
+            } else {
+                String::new()
+            };
+            if let Some(s) = &p.speaker {
+                writeln!(out, "**{}**{anchor}\n", escape(s))?;
+            } else if !anchor.is_empty() {
+                writeln!(out, "{}\n", anchor.trim_start())?;
+            }
+        }
+        let marker =
+            c.timestamps && last_marker.is_none_or(|m| p.start_ms - m >= c.marker_interval_ms);
+        if marker {
+            last_marker = Some(p.start_ms);
+            write!(out, "{} ", hms(p.start_ms))?;
+        }
+        writeln!(out, "{}\n", escape(&p.text))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// `[hh:mm:ss]`
+fn hms(ms: i64) -> String {
+    let secs = ms / 1000;
+    format!(
+        "[{:02}:{:02}:{:02}]",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// The first few words, for table of contents entries.
+fn preview(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() > 8 {
+        format!("{}…", words[..8].join(" "))
+    } else {
+        words.join(" ")
+    }
+}
+
+/// Keeps transcript text from being read as Markdown syntax.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        if matches!(ch, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, spoken};
+
+    #[test]
+    fn paragraphs_turns_and_marker_interval() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "One"),
+            spoken(1000, 2000, "Ann", "two"),
+            spoken(5000, 6000, "Ann", "*Three*"),
+            spoken(70_000, 71_000, "Bob", "Four"),
+        ]);
+        assert_eq!(
+            render(&Markdown, &t, &cfg),
+            "**Ann**\n\n[00:00:00] One two\n\n\\*Three\\*\n\n**Bob**\n\n[00:01:10] Four\n\n"
+        );
+    }
+
+    #[test]
+    fn speaker_contents_link_every_turn() {
+        let mut cfg = Config::default();
+        cfg.formats.markdown.toc = MarkdownToc::Speakers;
+        cfg.formats.markdown.marker_interval_ms = 0;
+        let mut t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "a"),
+            cue(1500, 2000, "b"),
+            spoken(3000, 4000, "Ann", "c"),
+        ]);
+        t.meta.title = Some("Q_A".to_string());
+        assert_eq!(
+            render(&Markdown, &t, &cfg),
+            "# Q\\_A\n\n## Contents\n\n\
+             - [\\[00:00:00\\] Ann](#turn-1)\n\
+             - [\\[00:00:01\\] Unknown](#turn-2)\n\
+             - [\\[00:00:03\\] Ann](#turn-3)\n\n\
+             **Ann** <a id=\"turn-1\"></a>\n\n[00:00:00] a\n\n\
+             <a id=\"turn-2\"></a>\n\n[00:00:01] b\n\n\
+             **Ann** <a id=\"turn-3\"></a>\n\n[00:00:03] c\n\n"
+        );
+    }
+
+    #[test]
+    fn section_contents_preview_text_after_long_pauses() {
+        let mut cfg = Config::default();
+        cfg.formats.markdown.toc = MarkdownToc::Sections;
+        cfg.formats.markdown.timestamps = false;
+        cfg.formats.markdown.title = Some("Talk".to_string());
+        cfg.policy.timestamp_offset_ms = 1000;
+        let t = Transcript::new(vec![
+            cue(0, 1000, "one two three four five six seven eight nine ten"),
+            cue(19_000, 20_000, "Later"),
+        ]);
+        assert_eq!(
+            render(&Markdown, &t, &cfg),
+            "# Talk\n\n## Contents\n\n\
+             - [\\[00:00:01\\] one two three four five six seven eight…](#section-1)\n\
+             - [\\[00:00:20\\] Later](#section-2)\n\n\
+             <a id=\"section-1\"></a>\n\none two three four five six seven eight nine ten\n\n\
+             <a id=\"section-2\"></a>\n\nLater\n\n"
+        );
+    }
+
+    #[test]
+    fn escapes_markdown_syntax() {
+        assert_eq!(
+            escape(r"# [a](b) <i> `c` \ _d_"),
+            r"\# \[a\](b) \<i\> \`c\` \\ \_d\_"
+        );
+        assert_eq!(hms(3_723_999), "[01:02:03]");
+        assert_eq!(preview("  a  b "), "a b");
+    }
+}
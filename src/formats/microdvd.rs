@@ -0,0 +1,280 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct MicroDvd;
+
+impl FormatReader for MicroDvd {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_microdvd(input, cfg)
+    }
+}
+
+impl FormatWriter for MicroDvd {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        let fps = cfg
+            .formats
+            .microdvd
+            .fps
+            .or_else(|| meta.tags.get("fps").and_then(|f| f.parse().ok()))
+            .ok_or_else(|| {
+                anyhow!("MicroDVD output needs a frame rate (--fps or [formats.microdvd] fps)")
+            })?;
+        check_fps(fps)?;
+
+        if cfg.formats.microdvd.write_fps_header {
+            writeln!(out, "{{1}}{{1}}{fps}")?;
+        }
+        Ok(Box::new(MicroDvdSink { cfg, out, fps }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("microdvd", &["sub"])
+        .config_section("microdvd")
+        .sniff(|raw| {
+            raw.trim_start_matches('\u{feff}')
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty())
+                .is_some_and(|l| split_frames(l).is_some())
+        })
+        .reader(MicroDvd)
+        .writer(MicroDvd)
+}
+
+pub fn parse_microdvd(input: &str, cfg: &Config) -> Result<Transcript> {
+    let mdvd_cfg = &cfg.formats.microdvd;
+    let mut fps = mdvd_cfg.fps;
+    let mut meta = Meta::default();
+    let mut frames: Vec<(i64, Option<i64>, String)> = Vec::new();
+
+    for (line_no, raw_line) in input.lines().enumerate() {
+        let line = raw_line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+        let (start, end, text) = split_frames(line).ok_or_else(|| {
+            anyhow!(
+                "MicroDVD line {}: expected '{{start}}{{end}}text'",
+                line_no + 1
+            )
+        })?;
+
+        // `{1}{1}23.976` declares the frame rate instead of showing text.
+        if frames.is_empty()
+            && start <= 1
+            && end == Some(start)
+            && let Ok(header_fps) = text.trim().parse::<f64>()
+        {
+            if mdvd_cfg.trust_header || fps.is_none() {
+                fps = Some(header_fps);
+            } else if fps != Some(header_fps) {
+                tracing::warn!(
+                    header_fps,
+                    fps = fps.unwrap_or_default(),
+                    "ignoring MicroDVD frame rate header"
+                );
+            }
+            continue;
+        }
+
+        frames.push((start, end, clean_text(text)));
+    }
+
+    let fps = fps.ok_or_else(|| {
+        anyhow!("MicroDVD input has no frame rate header; pass --fps or set [formats.microdvd] fps")
+    })?;
+    check_fps(fps)?;
+    meta.tags.insert("fps".to_string(), fps.to_string());
+
+    let to_ms = |frame: i64| (frame as f64 * 1000.0 / fps).round() as i64;
+    let mut cues = Vec::with_capacity(frames.len());
+    for (i, (start, end, text)) in frames.iter().enumerate() {
+        // An empty end frame means "until the next subtitle".
+        let end_frame = end.or_else(|| frames.get(i + 1).map(|n| n.0));
+        let start_ms = to_ms(*start);
+        cues.push(Cue {
+            start_ms,
+            end_ms: end_frame.map(to_ms).unwrap_or(start_ms),
+            text: text.clone(),
+            speaker: None,
+            words: Vec::new(),
+        });
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+fn check_fps(fps: f64) -> Result<()> {
+    if !(fps.is_finite() && fps > 0.0) {
+        return Err(anyhow!("invalid MicroDVD frame rate: {fps}"));
+    }
+    Ok(())
+}
+
+/// `{start}{end}rest`, where `end` may be empty.
+fn split_frames(line: &str) -> Option<(i64, Option<i64>, &str)> {
+    let rest = line.strip_prefix('{')?;
+    let (start_s, rest) = rest.split_once('}')?;
+    let rest = rest.strip_prefix('{')?;
+    let (end_s, text) = rest.split_once('}')?;
+
+    let start = start_s.trim().parse().ok()?;
+    let end = if end_s.trim().is_empty() {
+        None
+    } else {
+        Some(end_s.trim().parse().ok()?)
+    };
+    Some((start, end, text))
+}
+
+/// Drops `{y:i}`-style control codes and leading `/` italic markers; `|`
+/// separates lines.
+fn clean_text(text: &str) -> String {
+    text.split('|')
+        .map(|line| {
+            let mut out = String::with_capacity(line.len());
+            let mut rest = line;
+            while let Some(open) = rest.find('{') {
+                let Some(close) = rest[open..].find('}').map(|c| open + c) else {
+                    break;
+                };
+                let code = &rest[open + 1..close];
+                out.push_str(&rest[..open]);
+                if !is_control_code(code) {
+                    out.push_str(&rest[open..=close]);
+                }
+                rest = &rest[close + 1..];
+            }
+            out.push_str(rest);
+            out.trim().trim_start_matches('/').trim().to_string()
+        })
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_control_code(code: &str) -> bool {
+    code.split_once(':')
+        .is_some_and(|(k, _)| k.len() == 1 && k.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+struct MicroDvdSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    fps: f64,
+}
+
+impl CueSink for MicroDvdSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        let to_frame = |ms: i64| ((ms.max(0) as f64) * self.fps / 1000.0).round() as i64;
+        let text = cue
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("|");
+        writeln!(
+            self.out,
+            "{{{}}}{{{}}}{}",
+            to_frame(cue.start_ms + offset),
+            to_frame(cue.end_ms + offset),
+            text
+        )?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    fn at_fps(fps: f64) -> Config {
+        let mut cfg = Config::default();
+        cfg.formats.microdvd.fps = Some(fps);
+        cfg
+    }
+
+    #[test]
+    fn roundtrip_with_fps_header() {
+        let cfg = at_fps(25.0);
+        let t = Transcript::new(vec![cue(1000, 2000, "One\nTwo"), cue(4000, 5000, "Three")]);
+        let out = render(&MicroDvd, &t, &cfg);
+        assert_eq!(out, "{1}{1}25\n{25}{50}One|Two\n{100}{125}Three\n");
+
+        let back = parse_microdvd(&out, &Config::default()).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.meta.tags.get("fps").map(String::as_str), Some("25"));
+    }
+
+    #[test]
+    fn writer_falls_back_to_source_fps() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![cue(1000, 2000, "Hi")]);
+        assert!(render_err(&t, &cfg).contains("needs a frame rate"));
+        t.meta.tags.insert("fps".to_string(), "10".to_string());
+        assert!(render(&MicroDvd, &t, &cfg).ends_with("{10}{20}Hi\n"));
+    }
+
+    fn render_err(t: &Transcript, cfg: &Config) -> String {
+        MicroDvd
+            .write(t, cfg, &mut Vec::new())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn header_versus_configured_fps() {
+        let raw = "{1}{1}10\n{10}{20}Hi\n";
+        let mut cfg = at_fps(20.0);
+        assert_eq!(
+            timings(&parse_microdvd(raw, &cfg).unwrap()),
+            vec![(1000, 2000, "Hi")]
+        );
+        cfg.formats.microdvd.trust_header = false;
+        assert_eq!(
+            timings(&parse_microdvd(raw, &cfg).unwrap()),
+            vec![(500, 1000, "Hi")]
+        );
+    }
+
+    #[test]
+    fn missing_fps_and_bad_lines_are_errors() {
+        let cfg = Config::default();
+        assert!(parse_microdvd("{10}{20}Hi\n", &cfg).is_err());
+        assert!(parse_microdvd("{1}{1}0\n{10}{20}Hi\n", &cfg).is_err());
+        assert!(parse_microdvd("{1}{1}25\nHi\n", &cfg).is_err());
+    }
+
+    #[test]
+    fn control_codes_and_open_ends() {
+        let cfg = at_fps(10.0);
+        let raw = "{10}{}{y:i}/Slanted|{c:$0000ff}second {not a code}\n{30}{40}Next\n";
+        assert_eq!(
+            timings(&parse_microdvd(raw, &cfg).unwrap()),
+            vec![
+                (1000, 3000, "Slanted\nsecond {not a code}"),
+                (3000, 4000, "Next")
+            ]
+        );
+    }
+}
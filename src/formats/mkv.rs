@@ -0,0 +1,749 @@
+use anyhow::{Context, Result, anyhow};
+use flate2::read::ZlibDecoder;
+use std::io::{self, BufRead, Read};
+
+use crate::{
+    config::Config,
+    formats::{
+        plain::synth_duration_ms,
+        registry::{CueStream, FormatEntry, FormatReader},
+        tracks::{SubtitleTrack, clean_lines, is_text_codec, pick_track},
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+const ID_EBML: u32 = 0x1A45_DFA3;
+const ID_SEGMENT: u32 = 0x1853_8067;
+const ID_INFO: u32 = 0x1549_A966;
+const ID_TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const ID_TITLE: u32 = 0x7BA9;
+const ID_TRACKS: u32 = 0x1654_AE6B;
+const ID_TRACK_ENTRY: u32 = 0xAE;
+const ID_TRACK_NUMBER: u32 = 0xD7;
+const ID_TRACK_TYPE: u32 = 0x83;
+const ID_CODEC_ID: u32 = 0x86;
+const ID_NAME: u32 = 0x536E;
+const ID_LANGUAGE: u32 = 0x22_B59C;
+const ID_LANGUAGE_BCP47: u32 = 0x22_B59D;
+const ID_FLAG_DEFAULT: u32 = 0x88;
+const ID_FLAG_FORCED: u32 = 0x55AA;
+const ID_CONTENT_ENCODINGS: u32 = 0x6D80;
+const ID_CONTENT_ENCODING: u32 = 0x6240;
+const ID_CONTENT_COMPRESSION: u32 = 0x5034;
+const ID_CONTENT_COMP_ALGO: u32 = 0x4254;
+const ID_CONTENT_COMP_SETTINGS: u32 = 0x4255;
+const ID_CONTENT_ENCRYPTION: u32 = 0x5035;
+const ID_CLUSTER: u32 = 0x1F43_B675;
+const ID_CLUSTER_TIMESTAMP: u32 = 0xE7;
+const ID_SIMPLE_BLOCK: u32 = 0xA3;
+const ID_BLOCK_GROUP: u32 = 0xA0;
+const ID_BLOCK: u32 = 0xA1;
+const ID_BLOCK_DURATION: u32 = 0x9B;
+
+const TRACK_TYPE_SUBTITLE: u64 = 0x11;
+/// Upper bound for elements that are read into memory whole.
+const MAX_ELEMENT_BYTES: u64 = 64 * 1024 * 1024;
+
+pub struct Mkv;
+
+impl FormatReader for Mkv {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        extract_subtitles(&mut input.as_bytes(), cfg)
+    }
+
+    /// Matroska is binary; clusters are walked in file order and every block
+    /// outside the chosen track is skipped without being buffered.
+    fn read_stream<'a>(
+        &self,
+        mut input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        Ok(CueStream::from_transcript(extract_subtitles(
+            &mut input, cfg,
+        )?))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("mkv", &["mkv", "mks", "webm"])
+        .config_section("mkv")
+        .sniff(|raw| raw.starts_with("\u{1a}E\u{7e3}"))
+        .reader(Mkv)
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrackEntry {
+    info: SubtitleTrack,
+    /// 0 = zlib, 3 = header stripping.
+    compression: Option<(u64, Vec<u8>)>,
+    encrypted: bool,
+}
+
+/// Segment-level state gathered before the first cluster.
+struct Header {
+    timestamp_scale: u64,
+    title: Option<String>,
+    tracks: Vec<TrackEntry>,
+}
+
+/// Lists the subtitle tracks of a Matroska file, reading only up to the
+/// track table.
+pub fn list_tracks(input: &mut dyn BufRead) -> Result<Vec<SubtitleTrack>> {
+    let mut r = EbmlReader { r: input };
+    let header = read_header(&mut r)?;
+    Ok(header.tracks.into_iter().map(|t| t.info).collect())
+}
+
+pub fn extract_subtitles(input: &mut dyn BufRead, cfg: &Config) -> Result<Transcript> {
+    let mut r = EbmlReader { r: input };
+    let header = read_header(&mut r)?;
+    let infos: Vec<SubtitleTrack> = header.tracks.iter().map(|t| t.info.clone()).collect();
+    let chosen = pick_track(&infos, cfg.formats.mkv.track)?.number;
+    let track = header
+        .tracks
+        .iter()
+        .find(|t| t.info.number == chosen)
+        .ok_or_else(|| anyhow!("Matroska: track {chosen} vanished"))?;
+    if track.encrypted {
+        return Err(anyhow!("Matroska: subtitle track {chosen} is encrypted"));
+    }
+    tracing::info!(
+        track = chosen,
+        codec = track.info.codec.as_str(),
+        language = track.info.language.as_deref().unwrap_or("-"),
+        "extracting Matroska subtitle track"
+    );
+
+    // (start in timestamp ticks, duration in ticks, payload)
+    let mut blocks: Vec<(i64, Option<i64>, Vec<u8>)> = Vec::new();
+    let mut cluster_ts: i64 = 0;
+    while let Some((id, size)) = r.header()? {
+        match id {
+            ID_CLUSTER => {}
+            ID_CLUSTER_TIMESTAMP => cluster_ts = read_uint(&r.data(size)?) as i64,
+            ID_SIMPLE_BLOCK => {
+                let size = known(size, id)?;
+                if let Some((rel, payload)) = r.block(size, chosen)? {
+                    blocks.push((cluster_ts + rel, None, payload));
+                }
+            }
+            ID_BLOCK_GROUP => {
+                let mut remaining = known(size, id)?;
+                let mut found = None;
+                let mut duration = None;
+                while remaining > 0 {
+                    let (child, child_size, header_len) = r
+                        .header_with_len()?
+                        .ok_or_else(|| anyhow!("Matroska: truncated block group"))?;
+                    let child_size = known(child_size, child)?;
+                    remaining = remaining.saturating_sub(header_len + child_size);
+                    match child {
+                        ID_BLOCK => found = r.block(child_size, chosen)?,
+                        ID_BLOCK_DURATION => {
+                            duration = Some(read_uint(&r.data(Some(child_size))?) as i64)
+                        }
+                        _ => r.skip(child_size)?,
+                    }
+                }
+                if let Some((rel, payload)) = found {
+                    blocks.push((cluster_ts + rel, duration, payload));
+                }
+            }
+            _ => r.skip(known(size, id)?)?,
+        }
+    }
+
+    blocks.sort_by_key(|b| b.0);
+    let to_ms = |ticks: i64| (ticks as i128 * header.timestamp_scale as i128 / 1_000_000) as i64;
+
+    let mut cues = Vec::with_capacity(blocks.len());
+    for (i, (start, duration, payload)) in blocks.iter().enumerate() {
+        let payload = decompress(payload, track.compression.as_ref())?;
+        let raw = String::from_utf8_lossy(&payload);
+        let (text, speaker) = payload_text(&track.info.codec, &raw);
+        if text.is_empty() {
+            continue;
+        }
+        let start_ms = to_ms(*start);
+        let end_ms = match duration {
+            Some(d) => to_ms(start + d),
+            // SimpleBlocks carry no duration; hold until the next block.
+            None => blocks
+                .get(i + 1)
+                .map(|n| to_ms(n.0))
+                .unwrap_or_else(|| start_ms + synth_duration_ms(&text, cfg)),
+        };
+        cues.push(Cue {
+            start_ms,
+            end_ms,
+            text,
+            speaker,
+            words: Vec::new(),
+        });
+    }
+
+    let mut meta = Meta {
+        title: header.title.clone(),
+        language: track.info.language.clone(),
+        ..Meta::default()
+    };
+    meta.tags.insert("track".to_string(), chosen.to_string());
+    meta.tags
+        .insert("codec".to_string(), track.info.codec.clone());
+    if let Some(name) = &track.info.name {
+        meta.tags.insert("track_name".to_string(), name.clone());
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+/// Walks the EBML header and segment up to the first cluster.
+fn read_header(r: &mut EbmlReader<'_>) -> Result<Header> {
+    let (id, size) = r
+        .header()?
+        .ok_or_else(|| anyhow!("Matroska: empty input"))?;
+    if id != ID_EBML {
+        return Err(anyhow!("not a Matroska/WebM file (no EBML header)"));
+    }
+    r.skip(known(size, id)?)?;
+
+    let mut header = Header {
+        timestamp_scale: 1_000_000,
+        title: None,
+        tracks: Vec::new(),
+    };
+    let mut seen_tracks = false;
+    loop {
+        // A file without clusters still has a track table worth listing.
+        let Some((id, size)) = r.header()? else {
+            break;
+        };
+        match id {
+            ID_SEGMENT => {}
+            // Cluster children follow directly; the block pass picks them up.
+            ID_CLUSTER => break,
+            ID_INFO => {
+                for (child, data) in children(&r.data(size)?)? {
+                    match child {
+                        ID_TIMESTAMP_SCALE => header.timestamp_scale = read_uint(data).max(1),
+                        ID_TITLE => header.title = Some(read_string(data)),
+                        _ => {}
+                    }
+                }
+            }
+            ID_TRACKS => {
+                seen_tracks = true;
+                for (child, data) in children(&r.data(size)?)? {
+                    if child == ID_TRACK_ENTRY
+                        && let Some(t) = parse_track_entry(data)?
+                    {
+                        header.tracks.push(t);
+                    }
+                }
+            }
+            _ => r.skip(known(size, id)?)?,
+        }
+    }
+    if !seen_tracks {
+        return Err(anyhow!(
+            "Matroska: track table must come before the first cluster"
+        ));
+    }
+    Ok(header)
+}
+
+fn parse_track_entry(data: &[u8]) -> Result<Option<TrackEntry>> {
+    let mut t = TrackEntry::default();
+    let mut track_type = 0;
+    let mut lang_legacy: Option<String> = None;
+    let mut lang_bcp47: Option<String> = None;
+    // FlagDefault defaults to 1 in the spec.
+    t.info.default = true;
+
+    for (id, value) in children(data)? {
+        match id {
+            ID_TRACK_NUMBER => t.info.number = read_uint(value),
+            ID_TRACK_TYPE => track_type = read_uint(value),
+            ID_CODEC_ID => t.info.codec = read_string(value),
+            ID_NAME => t.info.name = Some(read_string(value)).filter(|s| !s.is_empty()),
+            ID_LANGUAGE => lang_legacy = Some(read_string(value)),
+            ID_LANGUAGE_BCP47 => lang_bcp47 = Some(read_string(value)),
+            ID_FLAG_DEFAULT => t.info.default = read_uint(value) != 0,
+            ID_FLAG_FORCED => t.info.forced = read_uint(value) != 0,
+            ID_CONTENT_ENCODINGS => {
+                for (_, encoding) in children(value)?
+                    .into_iter()
+                    .filter(|(id, _)| *id == ID_CONTENT_ENCODING)
+                {
+                    for (id, part) in children(encoding)? {
+                        match id {
+                            ID_CONTENT_COMPRESSION => {
+                                let mut algo = 0;
+                                let mut settings = Vec::new();
+                                for (id, v) in children(part)? {
+                                    match id {
+                                        ID_CONTENT_COMP_ALGO => algo = read_uint(v),
+                                        ID_CONTENT_COMP_SETTINGS => settings = v.to_vec(),
+                                        _ => {}
+                                    }
+                                }
+                                t.compression = Some((algo, settings));
+                            }
+                            ID_CONTENT_ENCRYPTION => t.encrypted = true,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if track_type != TRACK_TYPE_SUBTITLE {
+        return Ok(None);
+    }
+    // The legacy element defaults to "eng" when absent; "und" means unknown.
+    t.info.language = lang_bcp47
+        .or(lang_legacy)
+        .or_else(|| Some("eng".to_string()))
+        .filter(|l| l != "und");
+    t.info.text = is_text_codec(&t.info.codec);
+    Ok(Some(t))
+}
+
+fn decompress(payload: &[u8], compression: Option<&(u64, Vec<u8>)>) -> Result<Vec<u8>> {
+    match compression {
+        None => Ok(payload.to_vec()),
+        Some((0, _)) => {
+            let mut out = Vec::new();
+            ZlibDecoder::new(payload)
+                .read_to_end(&mut out)
+                .context("Matroska: failed inflating zlib-compressed subtitle block")?;
+            Ok(out)
+        }
+        Some((3, stripped)) => {
+            let mut out = stripped.clone();
+            out.extend_from_slice(payload);
+            Ok(out)
+        }
+        Some((algo, _)) => Err(anyhow!(
+            "Matroska: unsupported subtitle compression algorithm {algo}"
+        )),
+    }
+}
+
+/// Cue text (and speaker for ASS/SSA) from one block payload.
+fn payload_text(codec: &str, raw: &str) -> (String, Option<String>) {
+    match codec {
+        // ReadOrder, Layer, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+        "S_TEXT/ASS" | "S_TEXT/SSA" | "S_ASS" | "S_SSA" => {
+            let fields: Vec<&str> = raw.splitn(9, ',').collect();
+            let text = fields.get(8).copied().unwrap_or(raw);
+            let speaker = fields
+                .get(3)
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty());
+            (clean_lines(&strip_ass_overrides(text)), speaker)
+        }
+        _ => (clean_lines(&strip_tags(raw)), None),
+    }
+}
+
+/// Drops `{\...}` override blocks and turns `\N`, `\n` and `\h` into text.
+fn strip_ass_overrides(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    for ch in text.chars() {
+        match ch {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            _ if depth == 0 => out.push(ch),
+            _ => {}
+        }
+    }
+    out.replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+}
+
+/// Drops `<i>`-style markup from SRT and WebVTT payloads.
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|c| open + c) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    out.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+}
+
+fn known(size: Option<u64>, id: u32) -> Result<u64> {
+    size.ok_or_else(|| anyhow!("Matroska: element {id:#x} has unknown size"))
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+    data.iter()
+        .take(8)
+        .fold(0, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+fn read_string(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// Length of a variable-size integer from its first byte.
+fn vint_len(first: u8) -> Result<usize> {
+    match first.leading_zeros() {
+        n @ 0..=7 => Ok(n as usize + 1),
+        _ => Err(anyhow!("Matroska: invalid variable-size integer")),
+    }
+}
+
+/// Decodes a vint; element IDs keep their length marker, sizes drop it.
+/// `None` is the reserved all-ones "unknown size".
+fn vint_value(bytes: &[u8], keep_marker: bool) -> Option<u64> {
+    let len = bytes.len();
+    // An 8-byte vint's marker is the whole first byte.
+    let mask = if keep_marker {
+        0xFF
+    } else {
+        0xFFu8.checked_shr(len as u32).unwrap_or(0)
+    };
+    let mut value = u64::from(bytes[0] & mask);
+    for &b in &bytes[1..] {
+        value = (value << 8) | u64::from(b);
+    }
+    let all_ones = (1u64 << (7 * len)) - 1;
+    (keep_marker || value != all_ones).then_some(value)
+}
+
+/// Child elements of an in-memory master element.
+fn children(mut data: &[u8]) -> Result<Vec<(u32, &[u8])>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let id_len = vint_len(data[0])?;
+        let size_first = *data
+            .get(id_len)
+            .ok_or_else(|| anyhow!("Matroska: truncated element"))?;
+        let size_len = vint_len(size_first)?;
+        let header_len = id_len + size_len;
+        if data.len() < header_len {
+            return Err(anyhow!("Matroska: truncated element"));
+        }
+        let id = vint_value(&data[..id_len], true).unwrap_or(0) as u32;
+        let size = vint_value(&data[id_len..header_len], false)
+            .map(|s| s as usize)
+            .unwrap_or(data.len() - header_len)
+            .min(data.len() - header_len);
+        out.push((id, &data[header_len..header_len + size]));
+        data = &data[header_len + size..];
+    }
+    Ok(out)
+}
+
+/// Sequential EBML reader; nothing is buffered beyond the element at hand.
+struct EbmlReader<'r> {
+    r: &'r mut dyn BufRead,
+}
+
+impl EbmlReader<'_> {
+    fn vint(&mut self, keep_marker: bool) -> Result<Option<(Option<u64>, u64)>> {
+        let mut first = [0u8; 1];
+        if self.r.read(&mut first)? == 0 {
+            return Ok(None);
+        }
+        let len = vint_len(first[0])?;
+        let mut bytes = vec![first[0]; len];
+        self.r
+            .read_exact(&mut bytes[1..])
+            .context("Matroska: truncated element header")?;
+        Ok(Some((vint_value(&bytes, keep_marker), len as u64)))
+    }
+
+    /// `(id, size, header length)`, or `None` at end of input.
+    fn header_with_len(&mut self) -> Result<Option<(u32, Option<u64>, u64)>> {
+        let Some((id, id_len)) = self.vint(true)? else {
+            return Ok(None);
+        };
+        let (size, size_len) = self
+            .vint(false)?
+            .ok_or_else(|| anyhow!("Matroska: truncated element header"))?;
+        Ok(Some((id.unwrap_or(0) as u32, size, id_len + size_len)))
+    }
+
+    fn header(&mut self) -> Result<Option<(u32, Option<u64>)>> {
+        Ok(self.header_with_len()?.map(|(id, size, _)| (id, size)))
+    }
+
+    fn data(&mut self, size: Option<u64>) -> Result<Vec<u8>> {
+        let size = size.ok_or_else(|| anyhow!("Matroska: element has unknown size"))?;
+        if size > MAX_ELEMENT_BYTES {
+            return Err(anyhow!("Matroska: element of {size} bytes is too large"));
+        }
+        let mut buf = vec![0u8; size as usize];
+        self.r
+            .read_exact(&mut buf)
+            .context("Matroska: truncated element")?;
+        Ok(buf)
+    }
+
+    fn skip(&mut self, size: u64) -> Result<()> {
+        let skipped = io::copy(&mut self.r.take(size), &mut io::sink())?;
+        if skipped < size {
+            return Err(anyhow!("Matroska: truncated element"));
+        }
+        Ok(())
+    }
+
+    /// Reads a Block/SimpleBlock body if it belongs to `track`, returning its
+    /// relative timestamp and frame data; other tracks are skipped.
+    fn block(&mut self, size: u64, track: u64) -> Result<Option<(i64, Vec<u8>)>> {
+        let (number, len) = self
+            .vint(false)?
+            .ok_or_else(|| anyhow!("Matroska: truncated block"))?;
+        let rest = size
+            .checked_sub(len)
+            .ok_or_else(|| anyhow!("Matroska: malformed block"))?;
+        if number != Some(track) {
+            self.skip(rest)?;
+            return Ok(None);
+        }
+        let data = self.data(Some(rest))?;
+        if data.len() < 3 {
+            return Err(anyhow!("Matroska: malformed block"));
+        }
+        let rel = i64::from(i16::from_be_bytes([data[0], data[1]]));
+        let flags = data[2];
+        if flags & 0x06 != 0 {
+            tracing::warn!("Matroska: skipping laced subtitle block");
+            return Ok(None);
+        }
+        Ok(Some((rel, data[3..].to_vec())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::timings;
+    use flate2::{Compression, write::ZlibEncoder};
+    use std::io::Write;
+
+    /// One element with an 8-byte size, which every reader must accept.
+    fn el(id: u32, body: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&b| b == 0)
+            .collect();
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn uint(id: u32, v: u64) -> Vec<u8> {
+        el(id, &v.to_be_bytes())
+    }
+
+    fn track(number: u64, codec: &str, extra: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = [
+            uint(ID_TRACK_NUMBER, number),
+            uint(
+                ID_TRACK_TYPE,
+                if codec.starts_with("V_") {
+                    1
+                } else {
+                    TRACK_TYPE_SUBTITLE
+                },
+            ),
+            el(ID_CODEC_ID, codec.as_bytes()),
+        ]
+        .concat();
+        body.extend(extra.concat());
+        el(ID_TRACK_ENTRY, &body)
+    }
+
+    fn block(track: u8, rel: i16, payload: &[u8]) -> Vec<u8> {
+        let mut body = vec![0x80 | track];
+        body.extend_from_slice(&rel.to_be_bytes());
+        body.push(0x80);
+        body.extend_from_slice(payload);
+        body
+    }
+
+    /// EBML header, then an unknown-size segment as live WebM writers emit.
+    fn file(tracks: &[Vec<u8>], clusters: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = el(ID_EBML, &el(0x4282, b"matroska"));
+        out.extend([
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        out.extend(el(
+            ID_INFO,
+            &[uint(ID_TIMESTAMP_SCALE, 1_000_000), el(ID_TITLE, b"Film")].concat(),
+        ));
+        out.extend(el(ID_TRACKS, &tracks.concat()));
+        out.extend(clusters.concat());
+        out
+    }
+
+    fn sample() -> Vec<u8> {
+        let tracks = [
+            track(1, "V_VP9", &[]),
+            track(
+                2,
+                "S_TEXT/UTF8",
+                &[el(ID_LANGUAGE, b"ger"), uint(ID_FLAG_DEFAULT, 0)],
+            ),
+            track(
+                3,
+                "S_TEXT/ASS",
+                &[el(ID_LANGUAGE_BCP47, b"en-US"), el(ID_NAME, b"Full")],
+            ),
+            track(4, "S_HDMV/PGS", &[uint(ID_FLAG_FORCED, 1)]),
+        ];
+        let ass = b"1,0,Default,Ann,0,0,0,,{\\i1}Hi\\Nthere";
+        let cluster = [
+            uint(ID_CLUSTER_TIMESTAMP, 1000),
+            el(ID_SIMPLE_BLOCK, &block(1, 0, b"video")),
+            el(ID_SIMPLE_BLOCK, &block(2, 0, b"<i>Hallo</i> &amp; so")),
+            el(
+                ID_BLOCK_GROUP,
+                &[
+                    el(ID_BLOCK, &block(3, 500, ass)),
+                    uint(ID_BLOCK_DURATION, 1500),
+                ]
+                .concat(),
+            ),
+            el(ID_SIMPLE_BLOCK, &block(2, 2000, b"Welt")),
+        ]
+        .concat();
+        file(&tracks, &[el(ID_CLUSTER, &cluster)])
+    }
+
+    fn extract(raw: &[u8], track: Option<u64>) -> Result<Transcript> {
+        let mut cfg = Config::default();
+        cfg.formats.mkv.track = track;
+        extract_subtitles(&mut &raw[..], &cfg)
+    }
+
+    #[test]
+    fn lists_subtitle_tracks_only() {
+        let tracks = list_tracks(&mut &sample()[..]).unwrap();
+        let listed: Vec<_> = tracks
+            .iter()
+            .map(|t| (t.number, t.language.as_deref(), t.default, t.forced, t.text))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (2, Some("ger"), false, false, true),
+                (3, Some("en-US"), true, false, true),
+                (4, Some("eng"), true, true, false)
+            ]
+        );
+        assert_eq!(tracks[1].name.as_deref(), Some("Full"));
+    }
+
+    #[test]
+    fn default_track_is_ass_with_speakers() {
+        let t = extract(&sample(), None).unwrap();
+        assert_eq!(timings(&t), vec![(1500, 3000, "Hi\nthere")]);
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("Ann"));
+        assert_eq!(t.meta.title.as_deref(), Some("Film"));
+        assert_eq!(t.meta.tags.get("track").map(String::as_str), Some("3"));
+    }
+
+    #[test]
+    fn simple_blocks_hold_until_the_next() {
+        let cfg = Config::default();
+        let t = extract(&sample(), Some(2)).unwrap();
+        let last_end = 3000 + synth_duration_ms("Welt", &cfg);
+        assert_eq!(
+            timings(&t),
+            vec![(1000, 3000, "Hallo & so"), (3000, last_end, "Welt")]
+        );
+        assert_eq!(t.meta.language.as_deref(), Some("ger"));
+    }
+
+    #[test]
+    fn bitmap_and_missing_tracks_are_errors() {
+        let err = extract(&sample(), Some(4)).unwrap_err().to_string();
+        assert!(err.contains("does not hold text"));
+        let err = extract(&sample(), Some(9)).unwrap_err().to_string();
+        assert!(err.contains("no subtitle track 9"));
+    }
+
+    #[test]
+    fn compressed_tracks() {
+        let encoding = |algo: u64, settings: &[u8]| {
+            let compression = [
+                uint(ID_CONTENT_COMP_ALGO, algo),
+                el(ID_CONTENT_COMP_SETTINGS, settings),
+            ];
+            el(
+                ID_CONTENT_ENCODINGS,
+                &el(
+                    ID_CONTENT_ENCODING,
+                    &el(ID_CONTENT_COMPRESSION, &compression.concat()),
+                ),
+            )
+        };
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(b"Packed").unwrap();
+        let zlib = zlib.finish().unwrap();
+
+        let raw = file(
+            &[
+                track(1, "S_TEXT/UTF8", &[encoding(3, b"Str")]),
+                track(2, "S_TEXT/UTF8", &[encoding(0, b"")]),
+            ],
+            &[el(
+                ID_CLUSTER,
+                &[
+                    uint(ID_CLUSTER_TIMESTAMP, 0),
+                    el(ID_SIMPLE_BLOCK, &block(1, 0, b"ipped")),
+                    el(ID_SIMPLE_BLOCK, &block(2, 0, &zlib)),
+                ]
+                .concat(),
+            )],
+        );
+        assert_eq!(extract(&raw, Some(1)).unwrap().cues[0].text, "Stripped");
+        assert_eq!(extract(&raw, Some(2)).unwrap().cues[0].text, "Packed");
+    }
+
+    #[test]
+    fn encrypted_track_is_an_error() {
+        let encrypted = el(
+            ID_CONTENT_ENCODINGS,
+            &el(ID_CONTENT_ENCODING, &el(ID_CONTENT_ENCRYPTION, &[])),
+        );
+        let raw = file(&[track(1, "S_TEXT/UTF8", &[encrypted])], &[]);
+        assert!(
+            extract(&raw, None)
+                .unwrap_err()
+                .to_string()
+                .contains("encrypted")
+        );
+    }
+
+    #[test]
+    fn rejects_other_input() {
+        assert!(extract(b"1\n00:00:01,000 --> 00:00:02,000\nHi\n", None).is_err());
+        assert!(extract(&[], None).is_err());
+        let no_tracks = el(ID_EBML, &[]);
+        assert!(list_tracks(&mut &no_tracks[..]).is_err());
+        assert!((entry().sniff.unwrap())(
+            &String::from_utf8_lossy(&sample())
+        ));
+    }
+}
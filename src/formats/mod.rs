@@ -1,7 +1,40 @@
+pub mod asr;
 pub mod ass;
+pub mod assemblyai;
+pub mod audacity;
+pub mod aws_transcribe;
+pub mod deepgram;
+pub mod docx;
+pub mod eaf;
+pub mod ebu_stl;
+pub mod epub;
+pub mod html;
 pub mod json;
+pub mod json_mapping;
+pub mod lrc;
+pub mod markdown;
+pub mod microdvd;
+pub mod mkv;
+pub mod mp4;
+pub mod nist;
+pub mod plain;
+pub mod registry;
+pub mod sami;
+pub mod sbv;
+pub mod scc;
+pub mod speech_marks;
 pub mod srt;
+pub mod ssml;
+pub mod subviewer;
+#[cfg(test)]
+pub(crate) mod testutil;
+pub mod textgrid;
 pub mod time;
+pub mod tracks;
 pub mod tsv;
 pub mod txt;
 pub mod vtt;
+pub mod whisper_cpp;
+pub mod xml;
+pub mod youtube;
+pub mod zip;
@@ -0,0 +1,1188 @@
+use anyhow::{Context, Result, anyhow};
+use std::io::{self, BufRead, Read, SeekFrom};
+
+use crate::{
+    config::Config,
+    formats::{
+        plain::synth_duration_ms,
+        registry::{CueStream, FormatEntry, FormatReader, SeekableRead},
+        tracks::{SubtitleTrack, clean_lines, is_text_codec, pick_track},
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+/// Upper bound for boxes that are read into memory whole.
+const MAX_BOX_BYTES: u64 = 256 * 1024 * 1024;
+
+pub struct Mp4;
+
+impl FormatReader for Mp4 {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        extract_subtitles(&mut input.as_bytes(), cfg)
+    }
+
+    /// MP4 is binary; top-level boxes are walked in file order and only the
+    /// chosen track's samples are kept from `mdat`, unless `moov` comes last.
+    fn read_stream<'a>(
+        &self,
+        mut input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        Ok(CueStream::from_transcript(extract_subtitles(
+            &mut input, cfg,
+        )?))
+    }
+
+    /// Files are walked box header by box header; with `moov` parsed, only
+    /// the chosen track's samples are read, wherever `mdat` sits.
+    fn read_seekable<'a>(
+        &self,
+        mut input: Box<dyn SeekableRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        Ok(CueStream::from_transcript(extract_seekable(
+            &mut input, cfg,
+        )?))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("mp4", &["mp4", "m4v", "mov"])
+        .config_section("mp4")
+        .sniff(|raw| raw.get(4..8).is_some_and(|t| matches!(t, "ftyp" | "styp")))
+        .reader(Mp4)
+}
+
+/// Handler types of timed-text and caption tracks.
+fn is_subtitle_handler(handler: &[u8]) -> bool {
+    matches!(handler, b"text" | b"sbtl" | b"subt" | b"clcp")
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    /// Absolute file offset of the sample data.
+    offset: u64,
+    size: u64,
+    /// Decode time in media timescale units.
+    time: u64,
+    duration: u64,
+    data: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Track {
+    info: SubtitleTrack,
+    timescale: u64,
+    /// Presentation shift from the edit list, in milliseconds.
+    shift_ms: i64,
+    samples: Vec<Sample>,
+    default_duration: u64,
+    default_size: u64,
+    /// Decode time where the next fragment continues without a `tfdt`.
+    next_time: u64,
+}
+
+/// Lists the subtitle tracks of an MP4 file, reading up to the `moov` box.
+pub fn list_tracks(input: &mut dyn BufRead) -> Result<Vec<SubtitleTrack>> {
+    let mut r = BoxReader { r: input, pos: 0 };
+    while let Some(b) = r.header()? {
+        if &b.kind == b"moov" {
+            let moov = r.data(b.body_len()?)?;
+            return Ok(parse_moov(&moov)?.into_iter().map(|t| t.info).collect());
+        }
+        r.skip_box(&b)?;
+    }
+    Err(anyhow!("MP4: no moov box found"))
+}
+
+pub fn extract_subtitles(input: &mut dyn BufRead, cfg: &Config) -> Result<Transcript> {
+    let mut r = BoxReader { r: input, pos: 0 };
+    let mut track: Option<Track> = None;
+    // mdat bodies seen before `moov`, as (absolute start, bytes).
+    let mut buffered: Vec<(u64, Vec<u8>)> = Vec::new();
+
+    while let Some(b) = r.header()? {
+        match &b.kind {
+            b"moov" => {
+                let moov = r.data(b.body_len()?)?;
+                track = Some(choose_track(&moov, cfg)?);
+            }
+            b"moof" => {
+                let moof = r.data(b.body_len()?)?;
+                if let Some(t) = track.as_mut() {
+                    parse_moof(&moof, b.start, t)?;
+                }
+            }
+            b"mdat" => match track.as_mut() {
+                Some(t) => r.read_samples(&b, &mut t.samples)?,
+                None => {
+                    tracing::warn!("MP4: media data precedes moov; buffering it");
+                    let start = b.start + b.header_len;
+                    let body = match b.size {
+                        Some(_) => r.data(b.body_len()?)?,
+                        None => {
+                            let mut body = Vec::new();
+                            r.r.read_to_end(&mut body)?;
+                            body
+                        }
+                    };
+                    r.pos = start + body.len() as u64;
+                    buffered.push((start, body));
+                }
+            },
+            _ => r.skip_box(&b)?,
+        }
+    }
+
+    let mut track = track.ok_or_else(|| anyhow!("MP4: no moov box found"))?;
+    for s in track.samples.iter_mut().filter(|s| s.data.is_none()) {
+        s.data = buffered.iter().find_map(|(start, body)| {
+            let from = s.offset.checked_sub(*start)? as usize;
+            body.get(from..from + s.size as usize).map(<[u8]>::to_vec)
+        });
+    }
+    track_transcript(&track, cfg)
+}
+
+/// Reads the box headers of a seekable file, keeping `moov` and `moof`
+/// bodies, then seeks to each sample of the chosen track.
+fn extract_seekable(input: &mut dyn SeekableRead, cfg: &Config) -> Result<Transcript> {
+    let mut track: Option<Track> = None;
+    // Fragments are parsed once `moov` has picked the track.
+    let mut moofs: Vec<(u64, Vec<u8>)> = Vec::new();
+    let mut pos = 0;
+    loop {
+        input.seek(SeekFrom::Start(pos))?;
+        let mut r = BoxReader { r: input, pos };
+        let Some(b) = r.header()? else { break };
+        match &b.kind {
+            b"moov" => {
+                let moov = r.data(b.body_len()?)?;
+                track = Some(choose_track(&moov, cfg)?);
+            }
+            b"moof" => moofs.push((b.start, r.data(b.body_len()?)?)),
+            _ => {}
+        }
+        match b.size {
+            Some(size) => pos = b.start + size,
+            None => break,
+        }
+    }
+
+    let mut track = track.ok_or_else(|| anyhow!("MP4: no moov box found"))?;
+    for (start, moof) in &moofs {
+        parse_moof(moof, *start, &mut track)?;
+    }
+    let mut order: Vec<usize> = (0..track.samples.len()).collect();
+    order.sort_by_key(|&i| track.samples[i].offset);
+    for i in order {
+        let s = &mut track.samples[i];
+        if s.size > MAX_BOX_BYTES {
+            return Err(anyhow!("MP4: sample of {} bytes is too large", s.size));
+        }
+        input.seek(SeekFrom::Start(s.offset))?;
+        let mut buf = vec![0u8; s.size as usize];
+        // Samples pointing past the end of the file are reported as missing.
+        s.data = match input.read_exact(&mut buf) {
+            Ok(()) => Some(buf),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+            Err(e) => return Err(e.into()),
+        };
+    }
+    track_transcript(&track, cfg)
+}
+
+/// Parses `moov` and picks the track named by `[formats.mp4] track`.
+fn choose_track(moov: &[u8], cfg: &Config) -> Result<Track> {
+    let tracks = parse_moov(moov)?;
+    let infos: Vec<SubtitleTrack> = tracks.iter().map(|t| t.info.clone()).collect();
+    let chosen = pick_track(&infos, cfg.formats.mp4.track)?.number;
+    let t = tracks
+        .into_iter()
+        .find(|t| t.info.number == chosen)
+        .ok_or_else(|| anyhow!("MP4: track {chosen} vanished"))?;
+    tracing::info!(
+        track = chosen,
+        codec = t.info.codec.as_str(),
+        language = t.info.language.as_deref().unwrap_or("-"),
+        "extracting MP4 subtitle track"
+    );
+    Ok(t)
+}
+
+/// Turns the samples read for a track into cues.
+fn track_transcript(track: &Track, cfg: &Config) -> Result<Transcript> {
+    let to_ms = |time: u64| (time as i128 * 1000 / track.timescale as i128) as i64;
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut missing = 0;
+    for s in &track.samples {
+        let Some(data) = &s.data else {
+            missing += 1;
+            continue;
+        };
+        let start_ms = to_ms(s.time) + track.shift_ms;
+        let end_ms = to_ms(s.time + s.duration) + track.shift_ms;
+        for (text, speaker) in sample_texts(&track.info.codec, data)? {
+            let end_ms = if end_ms > start_ms {
+                end_ms
+            } else {
+                start_ms + synth_duration_ms(&text, cfg)
+            };
+            // WebVTT cues that span several samples are repeated in each.
+            if let Some(prev) = cues
+                .iter_mut()
+                .rev()
+                .take_while(|c| c.end_ms >= start_ms)
+                .find(|c| c.end_ms == start_ms && c.text == text)
+            {
+                prev.end_ms = end_ms;
+                continue;
+            }
+            cues.push(Cue {
+                start_ms,
+                end_ms,
+                text,
+                speaker,
+                words: Vec::new(),
+            });
+        }
+    }
+    if missing > 0 {
+        tracing::warn!(samples = missing, "MP4: sample data not found in any mdat");
+    }
+
+    let mut meta = Meta {
+        language: track.info.language.clone(),
+        ..Meta::default()
+    };
+    meta.tags
+        .insert("track".to_string(), track.info.number.to_string());
+    meta.tags
+        .insert("codec".to_string(), track.info.codec.clone());
+    if let Some(name) = &track.info.name {
+        meta.tags.insert("track_name".to_string(), name.clone());
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+fn parse_moov(moov: &[u8]) -> Result<Vec<Track>> {
+    let mut movie_timescale = 1000;
+    let mut tracks = Vec::new();
+    let mut trex = Vec::new();
+    for (kind, body) in boxes(moov)? {
+        match &kind {
+            b"mvhd" => {
+                let (version, _, mut c) = full_box(body)?;
+                c.skip(if version == 1 { 16 } else { 8 })?;
+                movie_timescale = u64::from(c.u32()?).max(1);
+            }
+            b"trak" => tracks.push(body),
+            b"mvex" => {
+                for (kind, body) in boxes(body)? {
+                    if &kind == b"trex" {
+                        let (_, _, mut c) = full_box(body)?;
+                        let id = u64::from(c.u32()?);
+                        c.skip(4)?;
+                        trex.push((id, u64::from(c.u32()?), u64::from(c.u32()?)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    for trak in tracks {
+        if let Some(mut t) = parse_trak(trak, movie_timescale)? {
+            if let Some(&(_, duration, size)) = trex.iter().find(|x| x.0 == t.info.number) {
+                t.default_duration = duration;
+                t.default_size = size;
+            }
+            out.push(t);
+        }
+    }
+    Ok(out)
+}
+
+fn parse_trak(trak: &[u8], movie_timescale: u64) -> Result<Option<Track>> {
+    let mut t = Track {
+        timescale: 1000,
+        ..Track::default()
+    };
+    let mut handler = [0u8; 4];
+    let mut lang_packed: Option<String> = None;
+    let mut lang_bcp47: Option<String> = None;
+    let mut stbl: Option<&[u8]> = None;
+    let mut edits: Vec<(u64, i64)> = Vec::new();
+
+    for (kind, body) in boxes(trak)? {
+        match &kind {
+            b"tkhd" => {
+                let (version, flags, mut c) = full_box(body)?;
+                c.skip(if version == 1 { 16 } else { 8 })?;
+                t.info.number = u64::from(c.u32()?);
+                t.info.default = flags & 0x1 != 0;
+            }
+            b"edts" => {
+                for (kind, body) in boxes(body)? {
+                    if &kind != b"elst" {
+                        continue;
+                    }
+                    let (version, _, mut c) = full_box(body)?;
+                    for _ in 0..c.u32()? {
+                        let edit = if version == 1 {
+                            (c.u64()?, c.u64()? as i64)
+                        } else {
+                            (u64::from(c.u32()?), i64::from(c.u32()? as i32))
+                        };
+                        c.skip(4)?;
+                        edits.push(edit);
+                    }
+                }
+            }
+            b"mdia" => {
+                for (kind, body) in boxes(body)? {
+                    match &kind {
+                        b"mdhd" => {
+                            let (version, _, mut c) = full_box(body)?;
+                            c.skip(if version == 1 { 16 } else { 8 })?;
+                            t.timescale = u64::from(c.u32()?).max(1);
+                            c.skip(if version == 1 { 8 } else { 4 })?;
+                            lang_packed = unpack_language(c.u16()?);
+                        }
+                        b"hdlr" => {
+                            let (_, _, mut c) = full_box(body)?;
+                            c.skip(4)?;
+                            handler.copy_from_slice(c.take(4)?);
+                            c.skip(12)?;
+                            // Many muxers write a generic "SubtitleHandler" here.
+                            let name = cstring(c.rest());
+                            if !name.is_empty() && !name.ends_with("Handler") {
+                                t.info.name.get_or_insert(name);
+                            }
+                        }
+                        b"elng" => {
+                            let (_, _, c) = full_box(body)?;
+                            lang_bcp47 = Some(cstring(c.rest())).filter(|l| !l.is_empty());
+                        }
+                        b"minf" => {
+                            stbl = boxes(body)?
+                                .into_iter()
+                                .find(|(k, _)| k == b"stbl")
+                                .map(|(_, b)| b);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            b"udta" => {
+                for (kind, body) in boxes(body)? {
+                    if &kind == b"name" {
+                        t.info.name = Some(cstring(body)).filter(|n| !n.is_empty());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !is_subtitle_handler(&handler) {
+        return Ok(None);
+    }
+    t.info.language = lang_bcp47.or(lang_packed);
+
+    // Leading empty edits delay the track; the first real edit trims it.
+    let mut delay = 0u64;
+    for &(duration, media_time) in &edits {
+        if media_time == -1 {
+            delay += duration;
+            continue;
+        }
+        t.shift_ms = (delay * 1000 / movie_timescale) as i64
+            - (media_time as i128 * 1000 / t.timescale as i128) as i64;
+        break;
+    }
+
+    if let Some(stbl) = stbl {
+        parse_stbl(stbl, &mut t)?;
+    }
+    t.info.text = is_text_codec(&t.info.codec);
+    Ok(Some(t))
+}
+
+/// Builds the sample list of a progressive (non-fragmented) track.
+fn parse_stbl(stbl: &[u8], t: &mut Track) -> Result<()> {
+    let mut durations: Vec<(u32, u32)> = Vec::new();
+    let mut sizes: Vec<u64> = Vec::new();
+    let mut chunks: Vec<(u32, u32)> = Vec::new();
+    let mut offsets: Vec<u64> = Vec::new();
+
+    for (kind, body) in boxes(stbl)? {
+        let (_, _, mut c) = full_box(body)?;
+        match &kind {
+            b"stsd" => {
+                c.skip(4)?;
+                if let Some((codec, _)) = boxes(c.rest())?.first() {
+                    t.info.codec = String::from_utf8_lossy(codec).to_string();
+                }
+            }
+            b"stts" => {
+                for _ in 0..c.u32()? {
+                    durations.push((c.u32()?, c.u32()?));
+                }
+            }
+            b"stsz" => {
+                let fixed = c.u32()?;
+                let count = c.u32()?;
+                for _ in 0..count {
+                    sizes.push(u64::from(if fixed == 0 { c.u32()? } else { fixed }));
+                }
+            }
+            b"stsc" => {
+                for _ in 0..c.u32()? {
+                    chunks.push((c.u32()?, c.u32()?));
+                    c.skip(4)?;
+                }
+            }
+            b"stco" => {
+                for _ in 0..c.u32()? {
+                    offsets.push(u64::from(c.u32()?));
+                }
+            }
+            b"co64" => {
+                for _ in 0..c.u32()? {
+                    offsets.push(c.u64()?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut times = durations
+        .iter()
+        .flat_map(|&(count, delta)| std::iter::repeat_n(u64::from(delta), count as usize));
+    let mut time = 0u64;
+    let mut sizes = sizes.into_iter();
+    for (i, &chunk_offset) in offsets.iter().enumerate() {
+        let chunk = i as u32 + 1;
+        let per_chunk = chunks
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= chunk)
+            .map_or(0, |(_, n)| *n);
+        let mut offset = chunk_offset;
+        for _ in 0..per_chunk {
+            let Some(size) = sizes.next() else {
+                return Ok(());
+            };
+            let duration = times.next().unwrap_or(0);
+            t.samples.push(Sample {
+                offset,
+                size,
+                time,
+                duration,
+                data: (size == 0).then(Vec::new),
+            });
+            offset += size;
+            time += duration;
+        }
+    }
+    t.next_time = time;
+    Ok(())
+}
+
+/// Appends the samples a movie fragment holds for `t`.
+fn parse_moof(moof: &[u8], moof_start: u64, t: &mut Track) -> Result<()> {
+    for (kind, traf) in boxes(moof)? {
+        if &kind != b"traf" {
+            continue;
+        }
+        let mut base = moof_start;
+        let mut duration = t.default_duration;
+        let mut size = t.default_size;
+        let mut ours = false;
+        let mut data_end: Option<u64> = None;
+        for (kind, body) in boxes(traf)? {
+            let (version, flags, mut c) = full_box(body)?;
+            match &kind {
+                b"tfhd" => {
+                    ours = u64::from(c.u32()?) == t.info.number;
+                    if flags & 0x1 != 0 {
+                        base = c.u64()?;
+                    }
+                    if flags & 0x2 != 0 {
+                        c.skip(4)?;
+                    }
+                    if flags & 0x8 != 0 {
+                        duration = u64::from(c.u32()?);
+                    }
+                    if flags & 0x10 != 0 {
+                        size = u64::from(c.u32()?);
+                    }
+                }
+                b"tfdt" if ours => {
+                    t.next_time = if version == 1 {
+                        c.u64()?
+                    } else {
+                        u64::from(c.u32()?)
+                    };
+                }
+                b"trun" if ours => {
+                    let count = c.u32()?;
+                    let mut offset = if flags & 0x1 != 0 {
+                        base.wrapping_add_signed(i64::from(c.u32()? as i32))
+                    } else {
+                        data_end.unwrap_or(base)
+                    };
+                    if flags & 0x4 != 0 {
+                        c.skip(4)?;
+                    }
+                    for _ in 0..count {
+                        let d = if flags & 0x100 != 0 {
+                            u64::from(c.u32()?)
+                        } else {
+                            duration
+                        };
+                        let s = if flags & 0x200 != 0 {
+                            u64::from(c.u32()?)
+                        } else {
+                            size
+                        };
+                        c.skip(
+                            if flags & 0x400 != 0 { 4 } else { 0 }
+                                + if flags & 0x800 != 0 { 4 } else { 0 },
+                        )?;
+                        t.samples.push(Sample {
+                            offset,
+                            size: s,
+                            time: t.next_time,
+                            duration: d,
+                            data: (s == 0).then(Vec::new),
+                        });
+                        offset += s;
+                        t.next_time += d;
+                    }
+                    data_end = Some(offset);
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Cue texts (with WebVTT voice as speaker) carried by one sample.
+fn sample_texts(codec: &str, data: &[u8]) -> Result<Vec<(String, Option<String>)>> {
+    match codec {
+        "wvtt" => {
+            let mut out = Vec::new();
+            for (kind, body) in boxes(data)? {
+                if &kind != b"vttc" {
+                    continue;
+                }
+                for (kind, payl) in boxes(body)? {
+                    if &kind == b"payl" {
+                        let (text, speaker) = vtt_payload(&String::from_utf8_lossy(payl));
+                        if !text.is_empty() {
+                            out.push((text, speaker));
+                        }
+                    }
+                }
+            }
+            Ok(out)
+        }
+        _ => {
+            let mut c = Bytes::new(data);
+            let len = if data.len() >= 2 { c.u16()? } else { 0 } as usize;
+            let text = c.take(len.min(data.len().saturating_sub(2)))?;
+            let text = match text {
+                [0xFE, 0xFF, rest @ ..] => String::from_utf16_lossy(
+                    &rest
+                        .chunks_exact(2)
+                        .map(|p| u16::from_be_bytes([p[0], p[1]]))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => String::from_utf8_lossy(text).to_string(),
+            };
+            let text = clean_lines(&text.replace('\r', "\n"));
+            Ok(if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![(text, None)]
+            })
+        }
+    }
+}
+
+/// Strips WebVTT cue markup, keeping the first `<v Name>` as the speaker.
+fn vtt_payload(raw: &str) -> (String, Option<String>) {
+    let mut out = String::with_capacity(raw.len());
+    let mut speaker = None;
+    let mut rest = raw;
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|c| open + c) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let tag = &rest[open + 1..close];
+        if let Some(voice) = tag.strip_prefix('v')
+            && let Some((_, name)) = voice.split_once(' ')
+            && speaker.is_none()
+        {
+            speaker = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    let text = out
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ");
+    (clean_lines(&text), speaker)
+}
+
+/// ISO 639-2/T code packed as three 5-bit letters; "und" means unknown.
+fn unpack_language(packed: u16) -> Option<String> {
+    let code: String = [10, 5, 0]
+        .iter()
+        .map(|shift| char::from((((packed >> shift) & 0x1F) as u8) + 0x60))
+        .collect();
+    (code.chars().all(|c| c.is_ascii_lowercase()) && code != "und").then_some(code)
+}
+
+fn cstring(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).trim().to_string()
+}
+
+/// Child boxes of an in-memory container box.
+fn boxes(mut data: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    let mut out = Vec::new();
+    while data.len() >= 8 {
+        let mut c = Bytes::new(data);
+        let size32 = c.u32()?;
+        let mut kind = [0u8; 4];
+        kind.copy_from_slice(c.take(4)?);
+        let (header_len, size) = match size32 {
+            0 => (8, data.len() as u64),
+            1 => (16, c.u64()?),
+            n => (8, u64::from(n)),
+        };
+        if size < header_len as u64 || size > data.len() as u64 {
+            return Err(anyhow!(
+                "MP4: malformed '{}' box",
+                String::from_utf8_lossy(&kind)
+            ));
+        }
+        out.push((kind, &data[header_len..size as usize]));
+        data = &data[size as usize..];
+    }
+    Ok(out)
+}
+
+/// Splits a full box into version, flags and a cursor over the rest.
+fn full_box(data: &[u8]) -> Result<(u8, u32, Bytes<'_>)> {
+    let mut c = Bytes::new(data);
+    let vf = c.u32()?;
+    Ok(((vf >> 24) as u8, vf & 0x00FF_FFFF, c))
+}
+
+/// Big-endian cursor over box contents.
+struct Bytes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Bytes<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.data.len() < n {
+            return Err(anyhow!("MP4: truncated box"));
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<()> {
+        self.take(n).map(|_| ())
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+struct BoxHeader {
+    kind: [u8; 4],
+    /// Absolute offset of the box.
+    start: u64,
+    header_len: u64,
+    /// Total size; `None` extends to the end of the file.
+    size: Option<u64>,
+}
+
+impl BoxHeader {
+    fn body_len(&self) -> Result<u64> {
+        let size = self.size.ok_or_else(|| {
+            anyhow!(
+                "MP4: '{}' box runs to end of file",
+                String::from_utf8_lossy(&self.kind)
+            )
+        })?;
+        Ok(size - self.header_len)
+    }
+}
+
+/// Sequential top-level box reader that tracks the absolute file offset.
+struct BoxReader<'r> {
+    r: &'r mut dyn BufRead,
+    pos: u64,
+}
+
+impl BoxReader<'_> {
+    fn header(&mut self) -> Result<Option<BoxHeader>> {
+        let mut head = [0u8; 8];
+        let got = read_full(self.r, &mut head)?;
+        if got == 0 {
+            return Ok(None);
+        }
+        if got < 8 {
+            return Err(anyhow!("MP4: truncated box header"));
+        }
+        let start = self.pos;
+        self.pos += 8;
+        let mut kind = [0u8; 4];
+        kind.copy_from_slice(&head[4..]);
+        let (header_len, size) = match u32::from_be_bytes(head[..4].try_into()?) {
+            0 => (8, None),
+            1 => {
+                let mut large = [0u8; 8];
+                self.r
+                    .read_exact(&mut large)
+                    .context("MP4: truncated box header")?;
+                self.pos += 8;
+                (16, Some(u64::from_be_bytes(large)))
+            }
+            n => (8, Some(u64::from(n))),
+        };
+        if size.is_some_and(|s| s < header_len) {
+            return Err(anyhow!(
+                "MP4: malformed '{}' box",
+                String::from_utf8_lossy(&kind)
+            ));
+        }
+        Ok(Some(BoxHeader {
+            kind,
+            start,
+            header_len,
+            size,
+        }))
+    }
+
+    fn data(&mut self, len: u64) -> Result<Vec<u8>> {
+        if len > MAX_BOX_BYTES {
+            return Err(anyhow!("MP4: box of {len} bytes is too large"));
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.r.read_exact(&mut buf).context("MP4: truncated box")?;
+        self.pos += len;
+        Ok(buf)
+    }
+
+    fn skip(&mut self, len: u64) -> Result<()> {
+        let skipped = io::copy(&mut self.r.take(len), &mut io::sink())?;
+        self.pos += skipped;
+        if skipped < len {
+            return Err(anyhow!("MP4: truncated box"));
+        }
+        Ok(())
+    }
+
+    fn skip_box(&mut self, b: &BoxHeader) -> Result<()> {
+        match b.size {
+            Some(_) => self.skip(b.body_len()?),
+            None => {
+                self.pos += io::copy(&mut self.r, &mut io::sink())?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Streams through an `mdat` body, copying out the pending samples that
+    /// lie inside it.
+    fn read_samples(&mut self, b: &BoxHeader, samples: &mut [Sample]) -> Result<()> {
+        let body_start = b.start + b.header_len;
+        let end = b.size.map(|s| b.start + s);
+        let mut wanted: Vec<&mut Sample> = samples
+            .iter_mut()
+            .filter(|s| {
+                s.data.is_none()
+                    && s.offset >= body_start
+                    && end.is_none_or(|e| s.offset + s.size <= e)
+            })
+            .collect();
+        wanted.sort_by_key(|s| (s.offset, std::cmp::Reverse(s.size)));
+
+        // Muxers may point several samples at the same bytes.
+        let mut last: Option<(u64, Vec<u8>)> = None;
+        for s in wanted {
+            if s.offset >= self.pos {
+                self.skip(s.offset - self.pos)?;
+                let data = self.data(s.size)?;
+                s.data = Some(data.clone());
+                last = Some((s.offset, data));
+            } else if let Some((start, data)) = &last {
+                let from = (s.offset - start) as usize;
+                s.data = data.get(from..from + s.size as usize).map(<[u8]>::to_vec);
+            }
+        }
+
+        match end {
+            Some(e) => self.skip(e.saturating_sub(self.pos)),
+            None => self.skip_box(b),
+        }
+    }
+}
+
+/// Like `read_exact`, but reports how much was read before end of input.
+fn read_full(r: &mut dyn BufRead, buf: &mut [u8]) -> Result<usize> {
+    let mut got = 0;
+    while got < buf.len() {
+        match r.read(&mut buf[got..])? {
+            0 => break,
+            n => got += n,
+        }
+    }
+    Ok(got)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::timings;
+    use std::io::{Seek, Write};
+
+    fn bx(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn full(kind: &[u8; 4], version: u8, flags: u32, fields: &[&[u8]]) -> Vec<u8> {
+        let mut body = ((u32::from(version) << 24) | flags).to_be_bytes().to_vec();
+        body.extend(fields.concat());
+        bx(kind, &body)
+    }
+
+    fn be32(v: u32) -> [u8; 4] {
+        v.to_be_bytes()
+    }
+
+    fn pack_language(code: &str) -> [u8; 2] {
+        let b = code.as_bytes();
+        let packed = b
+            .iter()
+            .fold(0u16, |acc, &c| (acc << 5) | u16::from(c - 0x60));
+        packed.to_be_bytes()
+    }
+
+    /// A subtitle track whose sample table lives in `stbl`, plus any extra
+    /// `trak` children such as an edit list.
+    fn trak(id: u32, codec: &[u8; 4], stbl: &[Vec<u8>], extra: &[Vec<u8>]) -> Vec<u8> {
+        let tkhd = full(b"tkhd", 0, 1, &[&[0; 8], &be32(id), &[0; 8]]);
+        let mdhd = full(
+            b"mdhd",
+            0,
+            0,
+            &[
+                &[0; 8],
+                &be32(1000),
+                &[0; 4],
+                &pack_language("eng"),
+                &[0; 2],
+            ],
+        );
+        let hdlr = full(
+            b"hdlr",
+            0,
+            0,
+            &[&[0; 4], b"sbtl", &[0; 12], b"Commentary\0"],
+        );
+        let stsd = full(b"stsd", 0, 0, &[&be32(1), &bx(codec, &[0; 8])]);
+        let stbl = bx(b"stbl", &[vec![stsd], stbl.to_vec()].concat().concat());
+        let mdia = bx(b"mdia", &[mdhd, hdlr, bx(b"minf", &stbl)].concat());
+        bx(
+            b"trak",
+            &[vec![tkhd], extra.to_vec(), vec![mdia]].concat().concat(),
+        )
+    }
+
+    fn moov(traks: &[Vec<u8>], mvex: Option<Vec<u8>>) -> Vec<u8> {
+        let mvhd = full(b"mvhd", 0, 0, &[&[0; 8], &be32(1000), &[0; 4]]);
+        bx(
+            b"moov",
+            &[vec![mvhd], traks.to_vec(), mvex.into_iter().collect()]
+                .concat()
+                .concat(),
+        )
+    }
+
+    fn tx3g(text: &str) -> Vec<u8> {
+        let mut out = (text.len() as u16).to_be_bytes().to_vec();
+        out.extend_from_slice(text.as_bytes());
+        out
+    }
+
+    fn ftyp() -> Vec<u8> {
+        bx(b"ftyp", b"isom\0\0\0\0")
+    }
+
+    /// `ftyp`, `moov` and one `mdat` holding `samples` as a single chunk;
+    /// `mdat_first` puts the media data ahead of `moov`.
+    fn progressive(samples: &[(&str, u32)], extra: &[Vec<u8>], mdat_first: bool) -> Vec<u8> {
+        let data: Vec<Vec<u8>> = samples.iter().map(|(t, _)| tx3g(t)).collect();
+        let build = |chunk_offset: u32| {
+            let stts: Vec<u8> = samples
+                .iter()
+                .flat_map(|(_, d)| [be32(1), be32(*d)].concat())
+                .collect();
+            let stsz: Vec<u8> = data.iter().flat_map(|d| be32(d.len() as u32)).collect();
+            let n = samples.len() as u32;
+            let stbl = [
+                full(b"stts", 0, 0, &[&be32(n), &stts]),
+                full(b"stsz", 0, 0, &[&be32(0), &be32(n), &stsz]),
+                full(b"stsc", 0, 0, &[&be32(1), &be32(1), &be32(n), &be32(1)]),
+                full(b"stco", 0, 0, &[&be32(1), &be32(chunk_offset)]),
+            ];
+            moov(&[trak(2, b"tx3g", &stbl, extra)], None)
+        };
+        let mdat = bx(b"mdat", &data.concat());
+        let ftyp = ftyp();
+        let moov_len = build(0).len();
+        if mdat_first {
+            [ftyp.clone(), mdat, build(ftyp.len() as u32 + 8)].concat()
+        } else {
+            [
+                ftyp.clone(),
+                build((ftyp.len() + moov_len + 8) as u32),
+                mdat,
+            ]
+            .concat()
+        }
+    }
+
+    /// Reads `raw` as a stream and as a seekable file, which must agree.
+    fn extract(raw: &[u8], track: Option<u64>) -> Result<Transcript> {
+        let mut cfg = Config::default();
+        cfg.formats.mp4.track = track;
+        let streamed = extract_subtitles(&mut &raw[..], &cfg);
+        match (&streamed, extract_seekable(&mut io::Cursor::new(raw), &cfg)) {
+            (Ok(a), Ok(b)) => assert_eq!(timings(a), timings(&b)),
+            (a, b) => assert_eq!(a.is_ok(), b.is_ok()),
+        }
+        streamed
+    }
+
+    #[test]
+    fn progressive_tx3g() {
+        let raw = progressive(&[("Hello", 1000), ("", 500), ("Wor\rld", 1500)], &[], false);
+        let t = extract(&raw, None).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 1000, "Hello"), (1500, 3000, "Wor\nld")]
+        );
+        assert_eq!(t.meta.language.as_deref(), Some("eng"));
+        assert_eq!(
+            t.meta.tags.get("track_name").map(String::as_str),
+            Some("Commentary")
+        );
+    }
+
+    #[test]
+    fn media_data_before_moov_is_buffered() {
+        let raw = progressive(&[("Hello", 1000), ("World", 1000)], &[], true);
+        assert_eq!(
+            timings(&extract(&raw, None).unwrap()),
+            vec![(0, 1000, "Hello"), (1000, 2000, "World")]
+        );
+    }
+
+    #[test]
+    fn files_seek_past_media_data_larger_than_the_cap() {
+        let raw = progressive(&[("Hello", 1000), ("World", 1000)], &[], true);
+        let mdat_start = ftyp().len();
+        let mdat_len = u32::from_be_bytes(raw[mdat_start..mdat_start + 4].try_into().unwrap());
+        let moov = &raw[mdat_start + mdat_len as usize..];
+        let big = mdat_len + MAX_BOX_BYTES as u32;
+
+        // Sparse, so the gap inside `mdat` takes no disk space.
+        let path = std::env::temp_dir().join(format!("mp4-large-mdat-{}.mp4", std::process::id()));
+        let mut f = std::fs::File::create(&path).unwrap();
+        f.write_all(&raw[..mdat_start]).unwrap();
+        f.write_all(&big.to_be_bytes()).unwrap();
+        f.write_all(&raw[mdat_start + 4..mdat_start + mdat_len as usize])
+            .unwrap();
+        f.seek(SeekFrom::Start((mdat_start as u64) + u64::from(big)))
+            .unwrap();
+        f.write_all(moov).unwrap();
+        drop(f);
+
+        let cfg = Config::default();
+        let open = || io::BufReader::new(std::fs::File::open(&path).unwrap());
+        let t = Mp4
+            .read_seekable(Box::new(open()), &cfg)
+            .and_then(CueStream::collect_transcript)
+            .unwrap();
+        assert_eq!(timings(&t), vec![(0, 1000, "Hello"), (1000, 2000, "World")]);
+        // Streamed input still has to buffer it, and refuses.
+        assert!(
+            extract_subtitles(&mut open(), &cfg)
+                .unwrap_err()
+                .to_string()
+                .contains("too large")
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn edit_list_delays_the_track() {
+        let elst = full(
+            b"elst",
+            0,
+            0,
+            &[
+                &be32(2),
+                &be32(2000),
+                &be32(u32::MAX),
+                &be32(0x1_0000),
+                &be32(5000),
+                &be32(0),
+                &be32(0x1_0000),
+            ],
+        );
+        let raw = progressive(&[("Hello", 1000)], &[bx(b"edts", &elst)], false);
+        assert_eq!(
+            timings(&extract(&raw, None).unwrap()),
+            vec![(2000, 3000, "Hello")]
+        );
+    }
+
+    #[test]
+    fn lists_tracks() {
+        let raw = progressive(&[("Hello", 1000)], &[], false);
+        let tracks = list_tracks(&mut &raw[..]).unwrap();
+        let listed: Vec<_> = tracks
+            .iter()
+            .map(|t| {
+                (
+                    t.number,
+                    t.codec.as_str(),
+                    t.language.as_deref(),
+                    t.default,
+                    t.text,
+                )
+            })
+            .collect();
+        assert_eq!(listed, vec![(2, "tx3g", Some("eng"), true, true)]);
+        assert!(extract(&raw, Some(1)).is_err());
+    }
+
+    fn vttc(payload: &str) -> Vec<u8> {
+        bx(b"vttc", &bx(b"payl", payload.as_bytes()))
+    }
+
+    /// One `moof` + `mdat` pair; `tfdt` is optional so later fragments can
+    /// continue from the previous one.
+    fn fragment(samples: &[(Vec<u8>, u32)], tfdt: Option<u64>) -> Vec<u8> {
+        let build = |data_offset: u32| {
+            let tfhd = full(b"tfhd", 0, 0x02_0000, &[&be32(1)]);
+            let tfdt = tfdt.map(|t| full(b"tfdt", 1, 0, &[&t.to_be_bytes()]));
+            let entries: Vec<u8> = samples
+                .iter()
+                .flat_map(|(d, dur)| [be32(*dur), be32(d.len() as u32)].concat())
+                .collect();
+            let trun = full(
+                b"trun",
+                0,
+                0x1 | 0x100 | 0x200,
+                &[&be32(samples.len() as u32), &be32(data_offset), &entries],
+            );
+            let traf = bx(
+                b"traf",
+                &[vec![tfhd], tfdt.into_iter().collect(), vec![trun]]
+                    .concat()
+                    .concat(),
+            );
+            bx(b"moof", &[full(b"mfhd", 0, 0, &[&be32(1)]), traf].concat())
+        };
+        let moof_len = build(0).len();
+        let mdat: Vec<u8> = samples.iter().flat_map(|(d, _)| d.clone()).collect();
+        [build(moof_len as u32 + 8), bx(b"mdat", &mdat)].concat()
+    }
+
+    #[test]
+    fn fragmented_webvtt() {
+        let trex = full(
+            b"trex",
+            0,
+            0,
+            &[&be32(1), &be32(1), &be32(0), &be32(0), &be32(0)],
+        );
+        let init = [
+            ftyp(),
+            moov(&[trak(1, b"wvtt", &[], &[])], Some(bx(b"mvex", &trex))),
+        ]
+        .concat();
+        let first = fragment(
+            &[
+                (vttc("<v Ann>Hi &amp; welcome"), 1000),
+                (vttc("<v Ann>Hi &amp; welcome"), 1000),
+                (bx(b"vtte", &[]), 500),
+            ],
+            Some(1000),
+        );
+        let second = fragment(&[(vttc("<c.yellow>Bye</c>"), 1000)], None);
+
+        let t = extract(&[init, first, second].concat(), None).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(1000, 3000, "Hi & welcome"), (3500, 4500, "Bye")]
+        );
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("Ann"));
+        assert_eq!(t.cues[1].speaker, None);
+    }
+
+    #[test]
+    fn rejects_other_input() {
+        assert!(
+            extract(&ftyp(), None)
+                .unwrap_err()
+                .to_string()
+                .contains("no moov")
+        );
+        assert!(extract(b"WEBVTT\n\n", None).is_err());
+        assert!(boxes(&[0, 0, 0, 64, b'f', b'r', b'e', b'e']).is_err());
+        assert_eq!(
+            unpack_language(u16::from_be_bytes(pack_language("und"))),
+            None
+        );
+    }
+}
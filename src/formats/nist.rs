@@ -0,0 +1,578 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::{
+        asr::{AsrWord, group_words},
+        registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+    },
+    model::{Cue, Meta, Transcript, Word},
+};
+
+/// Kaldi/NIST CTM: one word per line, `file channel start duration word [conf]`.
+pub struct Ctm;
+
+/// NIST STM: one reference segment per line,
+/// `file channel speaker begin end [<label>] transcript`.
+pub struct Stm;
+
+/// NIST RTTM: `SPEAKER` turns (and optional `LEXEME` words) for diarization
+/// scoring.
+pub struct Rttm;
+
+impl FormatReader for Ctm {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_ctm(input, cfg)
+    }
+}
+
+impl FormatReader for Stm {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_stm(input)
+    }
+}
+
+impl FormatReader for Rttm {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_rttm(input, cfg)
+    }
+}
+
+impl FormatWriter for Ctm {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(LineSink {
+            cfg,
+            out,
+            line: ctm_lines,
+        }))
+    }
+}
+
+impl FormatWriter for Stm {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(LineSink {
+            cfg,
+            out,
+            line: stm_lines,
+        }))
+    }
+}
+
+impl FormatWriter for Rttm {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(LineSink {
+            cfg,
+            out,
+            line: rttm_lines,
+        }))
+    }
+}
+
+pub fn ctm_entry() -> FormatEntry {
+    FormatEntry::new("ctm", &["ctm"])
+        .config_section("ctm")
+        .sniff(|raw| {
+            let mut lines = content_lines(whole_lines(raw)).peekable();
+            lines.peek().is_some() && lines.all(|(_, f)| is_ctm_line(&f))
+        })
+        .reader(Ctm)
+        .writer(Ctm)
+}
+
+pub fn stm_entry() -> FormatEntry {
+    FormatEntry::new("stm", &["stm"])
+        .config_section("stm")
+        .sniff(|raw| {
+            first_line(raw).is_some_and(|f| {
+                f.len() >= 6 && !is_number(f[2]) && is_number(f[3]) && is_number(f[4])
+            })
+        })
+        .reader(Stm)
+        .writer(Stm)
+}
+
+pub fn rttm_entry() -> FormatEntry {
+    FormatEntry::new("rttm", &["rttm"])
+        .config_section("rttm")
+        .sniff(|raw| {
+            first_line(raw).is_some_and(|f| matches!(f[0], "SPEAKER" | "LEXEME" | "SPKR-INFO"))
+        })
+        .reader(Rttm)
+        .writer(Rttm)
+}
+
+/// Fields of the first line that is not blank or a `;;` comment.
+fn first_line(raw: &str) -> Option<Vec<&str>> {
+    content_lines(raw).next().map(|(_, f)| f)
+}
+
+/// `file channel start duration word [confidence]` with a real channel.
+fn is_ctm_line(f: &[&str]) -> bool {
+    (5..=6).contains(&f.len())
+        && matches!(f[1], "1" | "A" | "B")
+        && is_number(f[2])
+        && is_number(f[3])
+}
+
+/// Drops a last line the sniff buffer may have cut short.
+fn whole_lines(raw: &str) -> &str {
+    match raw.rfind('\n') {
+        Some(i) if !raw.ends_with('\n') => &raw[..i],
+        _ => raw,
+    }
+}
+
+/// Non-comment lines split on whitespace, with 1-based line numbers.
+fn content_lines(raw: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    raw.lines().enumerate().filter_map(|(i, l)| {
+        let l = l.trim();
+        (!l.is_empty() && !l.starts_with(";;")).then(|| (i + 1, l.split_whitespace().collect()))
+    })
+}
+
+fn is_number(s: &str) -> bool {
+    s.parse::<f64>().is_ok()
+}
+
+fn seconds(s: &str, line: usize) -> Result<i64> {
+    let secs: f64 = s
+        .parse()
+        .map_err(|_| anyhow!("line {line}: bad time '{s}'"))?;
+    Ok((secs * 1000.0).round() as i64)
+}
+
+pub fn parse_ctm(raw: &str, cfg: &Config) -> Result<Transcript> {
+    // Grouped per channel, so interleaved channels do not split each other.
+    let mut channels: Vec<(String, Vec<AsrWord>)> = Vec::new();
+    let mut meta = Meta::default();
+
+    for (line, f) in content_lines(raw) {
+        if f.len() < 5 {
+            return Err(anyhow!("line {line}: CTM needs at least 5 fields"));
+        }
+        meta.tags
+            .entry("file".to_string())
+            .or_insert_with(|| f[0].to_string());
+        let word = f[4];
+        // Non-speech tokens such as `<sil>`.
+        if word.starts_with('<') && word.ends_with('>') {
+            continue;
+        }
+        let start_ms = seconds(f[2], line)?;
+        let w = AsrWord {
+            start_ms,
+            end_ms: start_ms + seconds(f[3], line)?,
+            text: word.to_string(),
+            speaker: None,
+            confidence: f.get(5).and_then(|c| c.parse().ok()),
+            punctuation: false,
+        };
+        match channels.iter_mut().find(|(c, _)| c == f[1]) {
+            Some((_, words)) => words.push(w),
+            None => channels.push((f[1].to_string(), vec![w])),
+        }
+    }
+
+    let multichannel = channels.len() > 1;
+    let mut cues = Vec::new();
+    for (channel, mut words) in channels {
+        words.sort_by_key(|w| w.start_ms);
+        if multichannel {
+            for w in &mut words {
+                w.speaker = Some(format!("Channel {channel}"));
+            }
+        }
+        cues.extend(group_words(&words, &cfg.formats.json.asr));
+    }
+    cues.sort_by_key(|c| c.start_ms);
+
+    Ok(Transcript { cues, meta })
+}
+
+pub fn parse_stm(raw: &str) -> Result<Transcript> {
+    let mut cues = Vec::new();
+    let mut meta = Meta::default();
+
+    for (line, f) in content_lines(raw) {
+        if f.len() < 5 {
+            return Err(anyhow!("line {line}: STM needs at least 5 fields"));
+        }
+        meta.tags
+            .entry("file".to_string())
+            .or_insert_with(|| f[0].to_string());
+        let mut rest = &f[5..];
+        if rest.first().is_some_and(|t| t.starts_with('<')) {
+            let label_end = rest.iter().position(|t| t.ends_with('>')).unwrap_or(0);
+            rest = &rest[label_end + 1..];
+        }
+        let text = rest.join(" ");
+        if f[2] == "inter_segment_gap" || text == "ignore_time_segment_in_scoring" {
+            continue;
+        }
+        cues.push(Cue {
+            start_ms: seconds(f[3], line)?,
+            end_ms: seconds(f[4], line)?,
+            text,
+            speaker: Some(f[2].to_string()),
+            words: Vec::new(),
+        });
+    }
+    cues.sort_by_key(|c| c.start_ms);
+
+    Ok(Transcript { cues, meta })
+}
+
+/// `SPEAKER` lines become cues; `LEXEME` words fill in the text of the turn
+/// they fall in, or are grouped into cues when there are no turns.
+pub fn parse_rttm(raw: &str, cfg: &Config) -> Result<Transcript> {
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut lexemes: Vec<AsrWord> = Vec::new();
+    let mut meta = Meta::default();
+
+    for (line, f) in content_lines(raw) {
+        if !matches!(f[0], "SPEAKER" | "LEXEME") {
+            continue;
+        }
+        if f.len() < 8 {
+            return Err(anyhow!("line {line}: RTTM needs at least 8 fields"));
+        }
+        meta.tags
+            .entry("file".to_string())
+            .or_insert_with(|| f[1].to_string());
+        let start_ms = seconds(f[3], line)?;
+        let end_ms = start_ms + seconds(f[4], line)?;
+        let speaker = Some(f[7].to_string()).filter(|s| s != "<NA>");
+        if f[0] == "SPEAKER" {
+            cues.push(Cue {
+                start_ms,
+                end_ms,
+                text: String::new(),
+                speaker,
+                words: Vec::new(),
+            });
+        } else {
+            lexemes.push(AsrWord {
+                start_ms,
+                end_ms,
+                text: f[5].to_string(),
+                speaker,
+                confidence: f.get(8).and_then(|c| c.parse().ok()),
+                punctuation: false,
+            });
+        }
+    }
+
+    lexemes.sort_by_key(|w| w.start_ms);
+    if cues.is_empty() {
+        cues = group_words(&lexemes, &cfg.formats.json.asr);
+    } else {
+        cues.sort_by_key(|c| c.start_ms);
+        for w in &lexemes {
+            let mid = (w.start_ms + w.end_ms) / 2;
+            if let Some(c) = cues.iter_mut().find(|c| {
+                c.start_ms <= mid
+                    && mid <= c.end_ms
+                    && (w.speaker.is_none() || c.speaker == w.speaker)
+            }) {
+                if !c.text.is_empty() {
+                    c.text.push(' ');
+                }
+                c.text.push_str(&w.text);
+                c.words.push(w.to_word());
+            }
+        }
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+/// Writes each cue as one or more lines.
+struct LineSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    line: fn(&Cue, &Config, &mut dyn Write) -> Result<()>,
+}
+
+impl CueSink for LineSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let mut cue = cue.clone();
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        cue.start_ms = (cue.start_ms + offset).max(0);
+        cue.end_ms = (cue.end_ms + offset).max(0);
+        for w in &mut cue.words {
+            w.start_ms = (w.start_ms + offset).max(0);
+            w.end_ms = (w.end_ms + offset).max(0);
+        }
+        (self.line)(&cue, self.cfg, self.out)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+fn secs(ms: i64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// NIST fields are whitespace-separated, so names must be one token.
+fn token(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Cues without word timings get their words spread evenly over the cue.
+fn ctm_lines(cue: &Cue, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.ctm;
+    let words: Vec<Word> = if cue.words.is_empty() {
+        let tokens: Vec<&str> = cue.text.split_whitespace().collect();
+        let step = cue.duration_ms() / tokens.len().max(1) as i64;
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(i, t)| Word {
+                start_ms: cue.start_ms + step * i as i64,
+                end_ms: cue.start_ms + step * (i as i64 + 1),
+                text: t.to_string(),
+                confidence: None,
+            })
+            .collect()
+    } else {
+        cue.words.clone()
+    };
+
+    for w in words {
+        write!(
+            out,
+            "{} {} {} {} {}",
+            token(&c.file_id),
+            token(&c.channel),
+            secs(w.start_ms),
+            secs((w.end_ms - w.start_ms).max(0)),
+            token(&w.text)
+        )?;
+        if let Some(conf) = w.confidence {
+            write!(out, " {conf:.2}")?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn stm_lines(cue: &Cue, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.stm;
+    let speaker = cue.speaker.as_deref().unwrap_or(&c.default_speaker);
+    writeln!(
+        out,
+        "{} {} {} {} {} {}",
+        token(&c.file_id),
+        token(&c.channel),
+        token(speaker),
+        secs(cue.start_ms),
+        secs(cue.end_ms),
+        cue.text.split_whitespace().collect::<Vec<_>>().join(" ")
+    )?;
+    Ok(())
+}
+
+fn rttm_lines(cue: &Cue, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.rttm;
+    let speaker = cue.speaker.as_deref().unwrap_or(&c.default_speaker);
+    writeln!(
+        out,
+        "SPEAKER {} {} {} {} <NA> <NA> {} <NA> <NA>",
+        token(&c.file_id),
+        token(&c.channel),
+        secs(cue.start_ms),
+        secs(cue.duration_ms()),
+        token(speaker)
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, spoken, timings, word};
+
+    fn words(c: &Cue) -> Vec<(i64, i64, &str)> {
+        c.words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn ctm_roundtrip_keeps_word_timings() {
+        let cfg = Config::default();
+        let mut c = cue(1000, 2000, "Hello world");
+        c.words = vec![word(1000, 1400, "Hello"), word(1500, 2000, "world")];
+        c.words[1].confidence = Some(0.875);
+        let out = render(&Ctm, &Transcript::new(vec![c]), &cfg);
+        assert_eq!(
+            out,
+            "transcript 1 1.000 0.400 Hello\ntranscript 1 1.500 0.500 world 0.88\n"
+        );
+
+        let back = parse_ctm(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), vec![(1000, 2000, "Hello world")]);
+        assert_eq!(
+            words(&back.cues[0]),
+            vec![(1000, 1400, "Hello"), (1500, 2000, "world")]
+        );
+        assert_eq!(back.cues[0].words[1].confidence, Some(0.88));
+        assert_eq!(
+            back.meta.tags.get("file").map(String::as_str),
+            Some("transcript")
+        );
+    }
+
+    #[test]
+    fn ctm_spreads_untimed_words_over_the_cue() {
+        let mut cfg = Config::default();
+        cfg.formats.ctm.file_id = "my file".to_string();
+        let t = Transcript::new(vec![cue(0, 900, "a b c")]);
+        assert_eq!(
+            render(&Ctm, &t, &cfg),
+            "my_file 1 0.000 0.300 a\nmy_file 1 0.300 0.300 b\nmy_file 1 0.600 0.300 c\n"
+        );
+    }
+
+    #[test]
+    fn ctm_skips_noise_and_splits_channels() {
+        let cfg = Config::default();
+        let raw = ";; comment\n\
+                   f A 0.0 0.5 hi\n\
+                   f B 0.2 0.5 yo\n\
+                   f A 0.5 0.2 <sil>\n\
+                   f A 0.7 0.3 there\n";
+        let t = parse_ctm(raw, &cfg).unwrap();
+        assert_eq!(timings(&t), vec![(0, 1000, "hi there"), (200, 700, "yo")]);
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("Channel A"));
+        assert_eq!(t.cues[1].speaker.as_deref(), Some("Channel B"));
+
+        assert!(parse_ctm("f 1 0.0 0.5\n", &cfg).is_err());
+        assert!(parse_ctm("f 1 soon 0.5 hi\n", &cfg).is_err());
+    }
+
+    #[test]
+    fn ctm_sniff() {
+        let sniff = ctm_entry().sniff.unwrap();
+        assert!(sniff("f 1 0.00 0.50 hello 0.9\nf 1 0.50 0.40 wor"));
+        assert!(!sniff("We sold 100 200 units\n"));
+        assert!(!sniff("f 1 0.00 0.50 hello\nsome prose here\n"));
+        assert!(!sniff(""));
+    }
+
+    #[test]
+    fn stm_roundtrip_with_speakers() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(0, 1500, "Ann Lee", "Hello  there"),
+            cue(2000, 3000, "Bye"),
+        ]);
+        let out = render(&Stm, &t, &cfg);
+        assert_eq!(
+            out,
+            "transcript 1 Ann_Lee 0.000 1.500 Hello there\n\
+             transcript 1 unknown 2.000 3.000 Bye\n"
+        );
+        let back = parse_stm(&out).unwrap();
+        assert_eq!(
+            timings(&back),
+            vec![(0, 1500, "Hello there"), (2000, 3000, "Bye")]
+        );
+        assert_eq!(back.cues[0].speaker.as_deref(), Some("Ann_Lee"));
+    }
+
+    #[test]
+    fn stm_labels_and_ignored_segments() {
+        let raw = "f 1 spk 2.0 3.0 <o,f0,male> later\n\
+                   f 1 inter_segment_gap 1.0 2.0\n\
+                   f 1 spk 1.5 1.8 ignore_time_segment_in_scoring\n\
+                   f 1 spk 0.0 1.0 first\n";
+        assert_eq!(
+            timings(&parse_stm(raw).unwrap()),
+            vec![(0, 1000, "first"), (2000, 3000, "later")]
+        );
+        assert!(parse_stm("f 1 spk 0.0\n").is_err());
+    }
+
+    #[test]
+    fn stm_sniff() {
+        let sniff = stm_entry().sniff.unwrap();
+        assert!(sniff(";; header\nf 1 spk 0.0 1.0 hello\n"));
+        assert!(!sniff("f 1 0.00 0.50 hello 0.9\n"));
+    }
+
+    #[test]
+    fn rttm_roundtrip_of_speaker_turns() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![spoken(500, 2000, "A", "hi"), cue(2500, 3000, "yo")]);
+        let out = render(&Rttm, &t, &cfg);
+        assert_eq!(
+            out.lines().next(),
+            Some("SPEAKER transcript 1 0.500 1.500 <NA> <NA> A <NA> <NA>")
+        );
+        let back = parse_rttm(&out, &cfg).unwrap();
+        let turns: Vec<_> = back
+            .cues
+            .iter()
+            .map(|c| (c.start_ms, c.end_ms, c.speaker.as_deref()))
+            .collect();
+        assert_eq!(
+            turns,
+            vec![(500, 2000, Some("A")), (2500, 3000, Some("unknown"))]
+        );
+    }
+
+    #[test]
+    fn rttm_lexemes_fill_matching_turns() {
+        let cfg = Config::default();
+        let raw = "SPKR-INFO f 1 <NA> <NA> <NA> unknown A <NA> <NA>\n\
+                   SPEAKER f 1 0.0 2.0 <NA> <NA> A <NA> <NA>\n\
+                   SPEAKER f 1 1.0 2.0 <NA> <NA> B <NA> <NA>\n\
+                   LEXEME f 1 1.2 0.3 there lex B 0.9\n\
+                   LEXEME f 1 0.1 0.3 hi lex A\n\
+                   LEXEME f 1 1.5 0.2 all lex <NA>\n";
+        let t = parse_rttm(raw, &cfg).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 2000, "hi all"), (1000, 3000, "there")]
+        );
+        assert_eq!(t.cues[1].words[0].confidence, Some(0.9));
+    }
+
+    #[test]
+    fn rttm_lexemes_without_turns_are_grouped() {
+        let cfg = Config::default();
+        let raw = "LEXEME f 1 0.0 0.4 hello lex A\nLEXEME f 1 0.5 0.4 you lex A\n";
+        let t = parse_rttm(raw, &cfg).unwrap();
+        assert_eq!(timings(&t), vec![(0, 900, "hello you")]);
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("A"));
+        assert!(parse_rttm("SPEAKER f 1 0.0 1.0\n", &cfg).is_err());
+    }
+
+    #[test]
+    fn rttm_sniff() {
+        let sniff = rttm_entry().sniff.unwrap();
+        assert!(sniff("SPEAKER f 1 0.0 1.0 <NA> <NA> A <NA> <NA>\n"));
+        assert!(!sniff("Speaker one said hello\n"));
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use aspasia::{Moment, PlainSubtitle};
+use std::io::BufRead;
+
+use crate::{
+    config::Config,
+    model::{Cue, Transcript},
+};
+
+pub fn plain_to_transcript(plain: &PlainSubtitle) -> Transcript {
+    let cues: Vec<Cue> = plain
+        .events()
+        .iter()
+        .map(|e| Cue {
+            start_ms: moment_to_ms(&e.start),
+            end_ms: moment_to_ms(&e.end),
+            text: e.text.clone(),
+            speaker: None,
+            words: Vec::new(),
+        })
+        .collect();
+
+    Transcript::new(cues)
+}
+
+fn moment_to_ms(m: &Moment) -> i64 {
+    let h = m.hours();
+    let min = m.minutes();
+    let s = m.seconds();
+    let ms = m.ms();
+    ((h * 60 + min) * 60 + s) * 1000 + ms
+}
+
+/// Splits line-oriented input into blank-line separated blocks without
+/// reading more than one block ahead.
+pub fn text_blocks<'a>(input: Box<dyn BufRead + 'a>) -> impl Iterator<Item = Result<String>> + 'a {
+    let mut lines = input.lines();
+    std::iter::from_fn(move || {
+        let mut block = String::new();
+        loop {
+            match lines.next() {
+                Some(Ok(line)) => {
+                    let line = line.trim_end_matches('\r');
+                    if line.trim().is_empty() {
+                        if block.is_empty() {
+                            continue;
+                        }
+                        return Some(Ok(block));
+                    }
+                    block.push_str(line);
+                    block.push('\n');
+                }
+                Some(Err(e)) => return Some(Err(e.into())),
+                None if block.is_empty() => return None,
+                None => return Some(Ok(block)),
+            }
+        }
+    })
+}
+
+/// Display time for a cue of `text` at `chars_per_second`, within the policy's
+/// duration bounds; used wherever a source gives no end time.
+pub(crate) fn synth_duration_ms(text: &str, cfg: &Config) -> i64 {
+    let cps = cfg.policy.chars_per_second.max(1.0);
+    let raw = (text.chars().count() as f64 / cps * 1000.0).round() as i64;
+    raw.clamp(cfg.policy.min_duration_ms, cfg.policy.max_duration_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Read};
+
+    fn blocks(input: &'static str) -> Vec<String> {
+        text_blocks(Box::new(input.as_bytes()))
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn blocks_split_on_blank_lines() {
+        assert_eq!(
+            blocks("\n\na\nb\r\n\r\n  \n\nc"),
+            vec!["a\nb\n".to_string(), "c\n".to_string()]
+        );
+        assert!(blocks("\n \n").is_empty());
+    }
+
+    /// Fails every read, standing in for input that has not arrived yet.
+    struct Unreadable;
+
+    impl Read for Unreadable {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read past the first block"))
+        }
+    }
+
+    #[test]
+    fn blocks_do_not_read_ahead() {
+        let input = io::BufReader::new("first\n\n".as_bytes().chain(Unreadable));
+        let mut it = text_blocks(Box::new(input));
+        assert_eq!(it.next().unwrap().unwrap(), "first\n");
+        assert!(it.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn synthesized_duration_is_clamped() {
+        let cfg = Config::default();
+        assert_eq!(synth_duration_ms("", &cfg), cfg.policy.min_duration_ms);
+        assert_eq!(
+            synth_duration_ms(&"x".repeat(10_000), &cfg),
+            cfg.policy.max_duration_ms
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use anyhow::{Result, anyhow};
+use std::io::{BufRead, Seek, Write};
+
+use crate::{
+    config::Config,
+    model::{Cue, Meta, Transcript},
+};
+
+/// Input that can also seek, such as a regular file.
+pub trait SeekableRead: BufRead + Seek {}
+
+impl<T: BufRead + Seek> SeekableRead for T {}
+
+/// Cues produced one at a time, with whatever metadata the header carried.
+pub struct CueStream<'a> {
+    pub meta: Meta,
+    pub cues: Box<dyn Iterator<Item = Result<Cue>> + 'a>,
+}
+
+impl<'a> CueStream<'a> {
+    pub fn from_transcript(t: Transcript) -> Self {
+        Self {
+            meta: t.meta,
+            cues: Box::new(t.cues.into_iter().map(Ok)),
+        }
+    }
+
+    pub fn collect_transcript(self) -> Result<Transcript> {
+        let cues = self.cues.collect::<Result<Vec<Cue>>>()?;
+        Ok(Transcript {
+            cues,
+            meta: self.meta,
+        })
+    }
+}
+
+/// Parses raw input text into the canonical transcript model.
+pub trait FormatReader: Send + Sync {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript>;
+
+    /// Incremental variant of [`FormatReader::read`]. The default buffers the
+    /// whole input, so only formats that can parse piecewise override it.
+    fn read_stream<'a>(
+        &self,
+        mut input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        let mut raw = String::new();
+        input.read_to_string(&mut raw)?;
+        Ok(CueStream::from_transcript(self.read(&raw, cfg)?))
+    }
+
+    /// [`FormatReader::read_stream`] for input that can seek, so binary
+    /// formats can jump over data they do not need. The default streams it.
+    fn read_seekable<'a>(
+        &self,
+        input: Box<dyn SeekableRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        self.read_stream(input, cfg)
+    }
+}
+
+/// Receives cues one at a time and renders them as they arrive.
+pub trait CueSink {
+    fn push(&mut self, cue: &Cue) -> Result<()>;
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Renders the canonical transcript model into a format's text representation.
+pub trait FormatWriter: Send + Sync {
+    /// Writes any header and returns a sink for the cues that follow.
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>>;
+
+    fn write(&self, t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+        let mut sink = self.begin(&t.meta, cfg, out)?;
+        for cue in &t.cues {
+            sink.push(cue)?;
+        }
+        sink.finish()
+    }
+}
+
+pub type RenderFn<'a> = Box<dyn FnOnce(&Transcript, &mut dyn Write) -> Result<()> + 'a>;
+
+/// Sink for formats that need every cue before rendering anything; it holds
+/// the transcript and hands it to `render` on finish.
+pub struct CollectSink<'a> {
+    transcript: Transcript,
+    out: &'a mut dyn Write,
+    render: RenderFn<'a>,
+}
+
+impl<'a> CollectSink<'a> {
+    pub fn new(
+        meta: &Meta,
+        out: &'a mut dyn Write,
+        render: impl FnOnce(&Transcript, &mut dyn Write) -> Result<()> + 'a,
+    ) -> Self {
+        Self {
+            transcript: Transcript {
+                cues: Vec::new(),
+                meta: meta.clone(),
+            },
+            out,
+            render: Box::new(render),
+        }
+    }
+}
+
+impl CueSink for CollectSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        self.transcript.cues.push(cue.clone());
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let s = *self;
+        (s.render)(&s.transcript, s.out)
+    }
+}
+
+/// Everything the pipeline needs to know about one format.
+pub struct FormatEntry {
+    /// Name used on the command line (`--to`, `--from`).
+    pub name: &'static str,
+    /// File extensions (lowercase, without the dot) that select this format.
+    pub extensions: &'static [&'static str],
+    /// Key of the `[formats.<section>]` table this format reads its settings from.
+    pub config_section: Option<&'static str>,
+    /// Cheap content check used when the format cannot be inferred from the path.
+    pub sniff: Option<fn(&str) -> bool>,
+    pub reader: Option<Box<dyn FormatReader>>,
+    pub writer: Option<Box<dyn FormatWriter>>,
+}
+
+impl FormatEntry {
+    pub fn new(name: &'static str, extensions: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            extensions,
+            config_section: None,
+            sniff: None,
+            reader: None,
+            writer: None,
+        }
+    }
+
+    pub fn config_section(mut self, section: &'static str) -> Self {
+        self.config_section = Some(section);
+        self
+    }
+
+    pub fn sniff(mut self, f: fn(&str) -> bool) -> Self {
+        self.sniff = Some(f);
+        self
+    }
+
+    pub fn reader(mut self, r: impl FormatReader + 'static) -> Self {
+        self.reader = Some(Box::new(r));
+        self
+    }
+
+    pub fn writer(mut self, w: impl FormatWriter + 'static) -> Self {
+        self.writer = Some(Box::new(w));
+        self
+    }
+}
+
+/// Ordered set of known formats. Later registrations replace earlier ones with
+/// the same name, so downstream crates can override built-in formats.
+pub struct Registry {
+    entries: Vec<FormatEntry>,
+    fallback: &'static str,
+}
+
+impl Registry {
+    pub fn empty(fallback: &'static str) -> Self {
+        Self {
+            entries: Vec::new(),
+            fallback,
+        }
+    }
+
+    /// Registry populated with every format shipped in this crate.
+    pub fn builtin() -> Self {
+        use crate::formats::{
+            ass, audacity, docx, eaf, ebu_stl, epub, html, json, lrc, markdown, microdvd, mkv, mp4,
+            nist, sami, sbv, scc, speech_marks, srt, ssml, subviewer, textgrid, tsv, txt, vtt,
+            youtube,
+        };
+
+        let mut r = Self::empty("txt");
+        r.register(srt::entry());
+        r.register(vtt::entry());
+        r.register(ass::entry());
+        r.register(txt::entry());
+        r.register(tsv::entry());
+        r.register(json::entry());
+        r.register(sbv::entry());
+        r.register(youtube::json3_entry());
+        r.register(youtube::srv3_entry());
+        r.register(lrc::entry());
+        r.register(microdvd::entry());
+        r.register(subviewer::entry());
+        r.register(sami::entry());
+        r.register(scc::entry());
+        r.register(ebu_stl::entry());
+        r.register(mkv::entry());
+        r.register(mp4::entry());
+        r.register(nist::ctm_entry());
+        r.register(nist::stm_entry());
+        r.register(nist::rttm_entry());
+        r.register(textgrid::entry());
+        r.register(eaf::entry());
+        r.register(audacity::entry());
+        r.register(speech_marks::polly_entry());
+        r.register(ssml::entry());
+        r.register(epub::smil_entry());
+        r.register(epub::xhtml_entry());
+        r.register(html::entry());
+        r.register(markdown::entry());
+        r.register(docx::entry());
+        r
+    }
+
+    pub fn register(&mut self, entry: FormatEntry) {
+        if let Some(existing) = self.entries.iter_mut().find(|e| e.name == entry.name) {
+            tracing::debug!(format = entry.name, "replacing registered format");
+            *existing = entry;
+        } else {
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &FormatEntry> {
+        self.entries.iter()
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|e| e.name).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<&FormatEntry> {
+        let wanted = name.to_lowercase();
+        self.entries
+            .iter()
+            .find(|e| e.name == wanted)
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown format '{name}' (known: {})",
+                    self.names().join(", ")
+                )
+            })
+    }
+
+    pub fn fallback(&self) -> Result<&FormatEntry> {
+        self.get(self.fallback)
+    }
+
+    pub fn by_extension(&self, ext: &str) -> Option<&FormatEntry> {
+        self.all_by_extension(ext).into_iter().next()
+    }
+
+    /// Every format claiming `ext`; several formats may share one (e.g. `.sub`).
+    pub fn all_by_extension(&self, ext: &str) -> Vec<&FormatEntry> {
+        let ext = ext.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|e| e.extensions.iter().any(|x| *x == ext))
+            .collect()
+    }
+
+    /// Formats whose sniffer accepts `raw`, in registration order.
+    pub fn sniff(&self, raw: &str) -> Vec<&FormatEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.reader.is_some() && e.sniff.is_some_and(|f| f(raw)))
+            .collect()
+    }
+
+    pub fn is_known_config_section(&self, section: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.config_section == Some(section))
+    }
+
+    pub fn read(&self, name: &str, raw: &str, cfg: &Config) -> Result<Transcript> {
+        let entry = self.get(name)?;
+        let reader = entry
+            .reader
+            .as_ref()
+            .ok_or_else(|| anyhow!("format '{}' cannot be read", entry.name))?;
+        reader.read(raw, cfg)
+    }
+
+    pub fn write(
+        &self,
+        name: &str,
+        t: &Transcript,
+        cfg: &Config,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let entry = self.get(name)?;
+        let writer = entry
+            .writer
+            .as_ref()
+            .ok_or_else(|| anyhow!("format '{}' cannot be written", entry.name))?;
+        writer.write(t, cfg, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    #[test]
+    fn builtin_names_are_unique() {
+        let r = Registry::builtin();
+        let mut names = r.names();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+        assert_eq!(r.fallback().unwrap().name, "txt");
+    }
+
+    #[test]
+    fn lookup_by_name_and_extension() {
+        let r = Registry::builtin();
+        assert_eq!(r.get("SRT").unwrap().name, "srt");
+        assert_eq!(r.by_extension("SSA").unwrap().name, "ass");
+        assert_eq!(r.by_extension("txt").unwrap().name, "txt");
+        assert!(r.by_extension("nope").is_none());
+        let err = r.get("nope").err().unwrap().to_string();
+        assert!(err.contains("known: srt, vtt"));
+    }
+
+    #[test]
+    fn every_extension_names_one_format_or_a_sniffed_set() {
+        let r = Registry::builtin();
+        for e in r.entries() {
+            for ext in e.extensions {
+                let claimed = r.all_by_extension(ext);
+                assert!(
+                    claimed.len() == 1 || claimed.iter().all(|c| c.sniff.is_some()),
+                    ".{ext} is shared by formats without a sniffer"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn register_replaces_by_name() {
+        struct Upper;
+        impl FormatReader for Upper {
+            fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+                Ok(Transcript::new(vec![cue(0, 1, &input.to_uppercase())]))
+            }
+        }
+
+        let mut r = Registry::builtin();
+        let before = r.names().len();
+        r.register(FormatEntry::new("txt", &["txt"]).reader(Upper));
+        assert_eq!(r.names().len(), before);
+        let t = r.read("txt", "hi", &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(0, 1, "HI")]);
+        assert!(r.get("txt").unwrap().writer.is_none());
+    }
+
+    #[test]
+    fn write_through_registry() {
+        let r = Registry::builtin();
+        let cfg = Config::default();
+        let t = Transcript::new(vec![cue(0, 1000, "Hi")]);
+        let mut out = Vec::new();
+        r.write("srt", &t, &cfg, &mut out).unwrap();
+        let srt = r.get("srt").unwrap().writer.as_deref().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), render(srt, &t, &cfg));
+    }
+
+    #[test]
+    fn collect_sink_renders_on_finish() {
+        let mut out = Vec::new();
+        let mut sink = Box::new(CollectSink::new(&Meta::default(), &mut out, |t, out| {
+            write!(out, "{} cues", t.cues.len())?;
+            Ok(())
+        }));
+        sink.push(&cue(0, 1, "a")).unwrap();
+        sink.push(&cue(1, 2, "b")).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(out, b"2 cues");
+    }
+
+    #[test]
+    fn default_read_stream_buffers_whole_input() {
+        struct Lines;
+        impl FormatReader for Lines {
+            fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+                Ok(Transcript::new(
+                    input.lines().map(|l| cue(0, 1, l)).collect(),
+                ))
+            }
+        }
+
+        let cfg = Config::default();
+        let stream = Lines
+            .read_stream(Box::new("a\nb\n".as_bytes()), &cfg)
+            .unwrap();
+        let t = stream.collect_transcript().unwrap();
+        assert_eq!(timings(&t), vec![(0, 1, "a"), (0, 1, "b")]);
+    }
+
+    #[test]
+    fn stream_errors_surface_on_collect() {
+        let stream = CueStream {
+            meta: Meta::default(),
+            cues: Box::new(vec![Ok(cue(0, 1, "a")), Err(anyhow!("bad cue"))].into_iter()),
+        };
+        assert_eq!(
+            stream.collect_transcript().unwrap_err().to_string(),
+            "bad cue"
+        );
+    }
+}
@@ -0,0 +1,609 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::{
+        plain::synth_duration_ms,
+        registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+        tracks::SubtitleTrack,
+        xml::escape,
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct Sami;
+
+impl FormatReader for Sami {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_sami(input, cfg)
+    }
+}
+
+impl FormatWriter for Sami {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        write_sami_header(out, meta, cfg)?;
+        Ok(Box::new(SamiSink {
+            cfg,
+            out,
+            last_end: None,
+        }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("sami", &["smi", "sami"])
+        .config_section("sami")
+        .sniff(|raw| {
+            raw.trim_start_matches('\u{feff}')
+                .trim_start()
+                .get(..5)
+                .is_some_and(|h| h.eq_ignore_ascii_case("<SAMI"))
+        })
+        .reader(Sami)
+        .writer(Sami)
+}
+
+/// A language class declared in the `<STYLE>` block, e.g.
+/// `.KRCC { Name: Korean; lang: ko-KR; }`.
+#[derive(Debug, Clone, Default)]
+pub struct SamiClass {
+    pub class: String,
+    pub name: Option<String>,
+    pub lang: Option<String>,
+}
+
+/// Reads the class picked by `[formats.sami] class` or `track`, or the first
+/// one declared.
+pub fn parse_sami(input: &str, cfg: &Config) -> Result<Transcript> {
+    let mut split = split_sami(input, cfg)?;
+    if split.is_empty() {
+        return Ok(Transcript::new(Vec::new()));
+    }
+
+    let c = &cfg.formats.sami;
+    let idx = match (&c.class, c.track) {
+        (Some(wanted), _) => split
+            .iter()
+            .position(|(c, _)| c.class.eq_ignore_ascii_case(wanted))
+            .ok_or_else(|| {
+                let known: Vec<&str> = split.iter().map(|(c, _)| c.class.as_str()).collect();
+                anyhow!(
+                    "SAMI class '{wanted}' not found (available: {})",
+                    known.join(", ")
+                )
+            })?,
+        (None, Some(n)) => Some((n as usize).wrapping_sub(1))
+            .filter(|&i| i < split.len())
+            .ok_or_else(|| anyhow!("SAMI has no class {n}; see list-tracks"))?,
+        (None, None) => 0,
+    };
+    if split.len() > 1 {
+        tracing::info!(
+            class = split[idx].0.class.as_str(),
+            classes = split.len(),
+            "reading one SAMI language class"
+        );
+    }
+    Ok(split.swap_remove(idx).1)
+}
+
+/// Language classes with cues as tracks for `list-tracks`, numbered for
+/// `--track`.
+pub fn list_classes(input: &str) -> Result<Vec<SubtitleTrack>> {
+    Ok(split_sami(input, &Config::default())?
+        .into_iter()
+        .enumerate()
+        .map(|(i, (class, _))| SubtitleTrack {
+            number: i as u64 + 1,
+            codec: "class".to_string(),
+            language: class.lang,
+            name: Some(match class.name {
+                Some(n) if n != class.class => format!("{} [{n}]", class.class),
+                _ => class.class,
+            }),
+            text: true,
+            ..SubtitleTrack::default()
+        })
+        .collect())
+}
+
+/// Splits a SAMI document into one transcript per language class, in the
+/// order the classes are declared (undeclared classes follow in body order).
+pub fn split_sami(input: &str, cfg: &Config) -> Result<Vec<(SamiClass, Transcript)>> {
+    let input = input.trim_start_matches('\u{feff}');
+    let mut classes = parse_style_classes(input);
+    let title = extract_element_text(input, "title");
+
+    // (start, class, text) in document order; empty text clears the class.
+    let mut syncs: Vec<(i64, String, String)> = Vec::new();
+    let mut pos = 0;
+    while let Some(tag_start) = find_tag(input, pos, "sync") {
+        let tag_end = input[tag_start..]
+            .find('>')
+            .map(|i| tag_start + i)
+            .ok_or_else(|| anyhow!("SAMI: unterminated <SYNC> tag"))?;
+        let start_attr = tag_attr(&input[tag_start..tag_end], "start")
+            .ok_or_else(|| anyhow!("SAMI: <SYNC> without Start"))?;
+        let start: i64 = start_attr
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("SAMI: bad Start value '{start_attr}'"))?;
+
+        let body_end = [
+            find_tag(input, tag_end, "sync"),
+            find_tag(input, tag_end, "/body"),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(input.len());
+        for (class, text) in split_paragraphs(&input[tag_end + 1..body_end]) {
+            syncs.push((start, class, text));
+        }
+        pos = body_end;
+    }
+
+    for (_, class, _) in &syncs {
+        if !classes.iter().any(|c| c.class.eq_ignore_ascii_case(class)) {
+            classes.push(SamiClass {
+                class: class.clone(),
+                ..SamiClass::default()
+            });
+        }
+    }
+
+    let mut out = Vec::with_capacity(classes.len());
+    for class in classes {
+        let entries: Vec<(i64, &str)> = syncs
+            .iter()
+            .filter(|(_, c, _)| c.eq_ignore_ascii_case(&class.class))
+            .map(|(s, _, t)| (*s, t.as_str()))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+
+        let mut cues = Vec::new();
+        for (i, (start_ms, text)) in entries.iter().enumerate() {
+            if text.is_empty() {
+                continue;
+            }
+            // A cue stays up until the next sync for its class, usually `&nbsp;`.
+            let end_ms = entries
+                .get(i + 1)
+                .map(|(s, _)| *s)
+                .unwrap_or_else(|| start_ms + synth_duration_ms(text, cfg));
+            cues.push(Cue {
+                start_ms: *start_ms,
+                end_ms,
+                text: text.to_string(),
+                speaker: None,
+                words: Vec::new(),
+            });
+        }
+
+        let meta = Meta {
+            language: class.lang.clone(),
+            title: title.clone(),
+            ..Meta::default()
+        };
+        out.push((class, Transcript { cues, meta }));
+    }
+
+    Ok(out)
+}
+
+/// Class rules from every `<STYLE>` block; the generic `P` rule is skipped.
+fn parse_style_classes(input: &str) -> Vec<SamiClass> {
+    let mut classes: Vec<SamiClass> = Vec::new();
+    let mut pos = 0;
+    while let Some(open) = find_tag(input, pos, "style") {
+        let Some(body_start) = input[open..].find('>').map(|i| open + i + 1) else {
+            break;
+        };
+        let body_end = find_tag(input, body_start, "/style").unwrap_or(input.len());
+        let css = &input[body_start..body_end];
+
+        let mut rest = css;
+        while let Some(brace) = rest.find('{') {
+            let selector = rest[..brace]
+                .rsplit(['}', '\n', '>'])
+                .next()
+                .unwrap_or("")
+                .trim();
+            let Some(close) = rest[brace..].find('}').map(|i| brace + i) else {
+                break;
+            };
+            if let Some(class) = selector.strip_prefix('.') {
+                let mut c = SamiClass {
+                    class: class.trim().to_string(),
+                    ..SamiClass::default()
+                };
+                for decl in rest[brace + 1..close].split(';') {
+                    let Some((k, v)) = decl.split_once(':') else {
+                        continue;
+                    };
+                    let v = v.trim().to_string();
+                    match k.trim().to_lowercase().as_str() {
+                        "name" => c.name = Some(v),
+                        "lang" => c.lang = Some(v),
+                        _ => {}
+                    }
+                }
+                // A class may be styled again further down; keep one entry.
+                match classes
+                    .iter_mut()
+                    .find(|e| e.class.eq_ignore_ascii_case(&c.class))
+                {
+                    Some(existing) => {
+                        existing.name = existing.name.take().or(c.name);
+                        existing.lang = existing.lang.take().or(c.lang);
+                    }
+                    None => classes.push(c),
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+        pos = body_end;
+    }
+    classes
+}
+
+/// `(class, text)` for each `<P>` in a sync block; a block without any `<P>` is
+/// credited to an unnamed class.
+fn split_paragraphs(block: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut starts: Vec<(usize, usize, String)> = Vec::new();
+    let mut pos = 0;
+    while let Some(p) = find_tag(block, pos, "p") {
+        let end = block[p..].find('>').map(|i| p + i).unwrap_or(block.len());
+        let class = tag_attr(&block[p..end], "class").unwrap_or_default();
+        starts.push((p, (end + 1).min(block.len()), class));
+        pos = end;
+    }
+
+    if starts.is_empty() {
+        let text = html_to_text(block);
+        if !block.trim().is_empty() {
+            out.push((String::new(), text));
+        }
+        return out;
+    }
+
+    for (i, (_, body_start, class)) in starts.iter().enumerate() {
+        let body_end = starts.get(i + 1).map(|n| n.0).unwrap_or(block.len());
+        out.push((class.clone(), html_to_text(&block[*body_start..body_end])));
+    }
+    out
+}
+
+/// Byte offset of the next `<name` tag at or after `from`, case-insensitively.
+fn find_tag(s: &str, from: usize, name: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = from;
+    while let Some(rel) = s.get(i..)?.find('<') {
+        let at = i + rel;
+        let after = at + 1 + name.len();
+        let matches = s
+            .get(at + 1..after)
+            .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            && bytes.get(after).is_none_or(|b| !b.is_ascii_alphanumeric());
+        if matches {
+            return Some(at);
+        }
+        i = at + 1;
+    }
+    None
+}
+
+/// Attribute value from a tag's source (`<SYNC Start=1000`), quoted or not.
+fn tag_attr(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(rel) = lower[from..].find(name) {
+        let at = from + rel;
+        from = at + name.len();
+        let before_ok = lower[..at]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+        let rest = tag[from..].trim_start();
+        let (true, Some(value)) = (before_ok, rest.strip_prefix('=')) else {
+            continue;
+        };
+        let value = value.trim_start();
+        let v = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or(""),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(v.to_string());
+    }
+    None
+}
+
+fn extract_element_text(input: &str, name: &str) -> Option<String> {
+    let open = find_tag(input, 0, name)?;
+    let body_start = input[open..].find('>').map(|i| open + i + 1)?;
+    let body_end = find_tag(input, body_start, &format!("/{name}"))?;
+    Some(html_to_text(&input[body_start..body_end])).filter(|t| !t.is_empty())
+}
+
+/// Drops tags (keeping `<br>` as a line break), decodes entities and trims
+/// each line. `&nbsp;`-only paragraphs come back empty.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>').map(|i| open + i) else {
+            rest = &rest[open..];
+            break;
+        };
+        let name = rest[open + 1..close]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        if name.eq_ignore_ascii_case("br") {
+            text.push('\n');
+        }
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+
+    let decoded = decode_entities(&text);
+    decoded
+        .lines()
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// HTML entities seen in SAMI files, which are rarely well-formed XML.
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let tail = &rest[amp + 1..];
+        let semi = tail.find(';').filter(|&i| i <= 10);
+        let decoded = semi.and_then(|i| {
+            let name = &tail[..i];
+            let ch = match name.to_ascii_lowercase().as_str() {
+                "nbsp" => Some(' '),
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                n if n.starts_with("#x") => u32::from_str_radix(&n[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32),
+                n if n.starts_with('#') => n[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            ch.map(|c| (c, i))
+        });
+        match decoded {
+            Some((c, i)) => {
+                out.push(c);
+                rest = &tail[i + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = tail;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn write_sami_header(out: &mut dyn Write, meta: &Meta, cfg: &Config) -> Result<()> {
+    let sami = &cfg.formats.sami;
+    let lang = meta.language.as_deref().unwrap_or(&sami.lang);
+
+    writeln!(out, "<SAMI>")?;
+    writeln!(out, "<HEAD>")?;
+    if let Some(title) = &meta.title {
+        writeln!(out, "<TITLE>{}</TITLE>", escape(title))?;
+    }
+    writeln!(out, "<SAMIParam>")?;
+    writeln!(out, "  Metrics {{time:ms;}}")?;
+    writeln!(out, "  Spec {{MSFT:1.0;}}")?;
+    writeln!(out, "</SAMIParam>")?;
+    writeln!(out, "<STYLE TYPE=\"text/css\">")?;
+    writeln!(out, "<!--")?;
+    writeln!(out, "P {{ {} }}", sami.p_style.trim())?;
+    writeln!(
+        out,
+        ".{} {{ Name: {}; lang: {}; SAMIType: CC; }}",
+        sami.out_class, sami.out_name, lang
+    )?;
+    if let Some(extra) = sami.extra_css.as_deref().map(str::trim)
+        && !extra.is_empty()
+    {
+        writeln!(out, "{extra}")?;
+    }
+    writeln!(out, "-->")?;
+    writeln!(out, "</STYLE>")?;
+    writeln!(out, "</HEAD>")?;
+    writeln!(out, "<BODY>")?;
+    Ok(())
+}
+
+/// Writes each cue as it arrives; a blank `&nbsp;` sync closes a cue when
+/// there is a gap before the next one.
+struct SamiSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    last_end: Option<i64>,
+}
+
+impl SamiSink<'_> {
+    fn write_sync(&mut self, ms: i64, body: &str) -> Result<()> {
+        writeln!(
+            self.out,
+            "<SYNC Start={}><P Class={}>{}",
+            ms.max(0),
+            self.cfg.formats.sami.out_class,
+            body
+        )?;
+        Ok(())
+    }
+}
+
+impl CueSink for SamiSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        if let Some(end) = self.last_end.take()
+            && end < cue.start_ms
+        {
+            self.write_sync(end + offset, "&nbsp;")?;
+        }
+
+        let body = cue
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(escape)
+            .collect::<Vec<_>>()
+            .join("<br>");
+        self.write_sync(cue.start_ms + offset, &body)?;
+        self.last_end = Some(cue.end_ms);
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if let Some(end) = self.last_end.take() {
+            let offset = self.cfg.policy.timestamp_offset_ms;
+            self.write_sync(end + offset, "&nbsp;")?;
+        }
+        writeln!(self.out, "</BODY>")?;
+        writeln!(self.out, "</SAMI>")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    const BILINGUAL: &str = r#"<SAMI>
+<HEAD><TITLE>Demo</TITLE>
+<STYLE TYPE="text/css"><!--
+P { font-size: 20pt; }
+.ENUSCC { Name: English; lang: en-US; }
+.KRCC { Name: Korean; lang: ko-KR; }
+--></STYLE>
+</HEAD>
+<BODY>
+<SYNC Start=1000><P Class=ENUSCC>Hello<br>there
+<P Class=KRCC>안녕
+<SYNC Start=2000><P Class=ENUSCC>&nbsp;
+<SYNC Start=3000><P Class=KRCC>&lt;tag&gt;
+</BODY>
+</SAMI>"#;
+
+    #[test]
+    fn roundtrip() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![
+            cue(1000, 2000, "Fish & chips"),
+            cue(2000, 3000, "Two\nlines"),
+            cue(4000, 5000, "After a gap"),
+        ]);
+        t.meta.title = Some("Demo".to_string());
+
+        let out = render(&Sami, &t, &cfg);
+        assert!(out.starts_with("<SAMI>\n<HEAD>\n<TITLE>Demo</TITLE>\n"));
+        assert!(out.contains(".ENUSCC { Name: English; lang: en-US; SAMIType: CC; }"));
+        assert!(out.contains("<SYNC Start=1000><P Class=ENUSCC>Fish &amp; chips\n"));
+        assert!(out.contains("<SYNC Start=2000><P Class=ENUSCC>Two<br>lines\n"));
+        assert!(out.contains("<SYNC Start=3000><P Class=ENUSCC>&nbsp;\n"));
+
+        let back = parse_sami(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.meta.title.as_deref(), Some("Demo"));
+        assert_eq!(back.meta.language.as_deref(), Some("en-US"));
+    }
+
+    #[test]
+    fn reads_first_class_by_default() {
+        let cfg = Config::default();
+        let t = parse_sami(BILINGUAL, &cfg).unwrap();
+        assert_eq!(timings(&t), vec![(1000, 2000, "Hello\nthere")]);
+    }
+
+    #[test]
+    fn selects_class_by_name_or_track() {
+        let mut cfg = Config::default();
+        cfg.formats.sami.class = Some("krcc".to_string());
+        let by_name = parse_sami(BILINGUAL, &cfg).unwrap();
+        assert_eq!(by_name.meta.language.as_deref(), Some("ko-KR"));
+        assert_eq!(
+            timings(&by_name),
+            vec![
+                (1000, 3000, "안녕"),
+                (3000, 3000 + synth_duration_ms("<tag>", &cfg), "<tag>")
+            ]
+        );
+
+        cfg.formats.sami.class = None;
+        cfg.formats.sami.track = Some(2);
+        assert_eq!(
+            timings(&parse_sami(BILINGUAL, &cfg).unwrap()),
+            timings(&by_name)
+        );
+    }
+
+    #[test]
+    fn unknown_class_or_track_is_an_error() {
+        let mut cfg = Config::default();
+        cfg.formats.sami.class = Some("FRCC".to_string());
+        let err = parse_sami(BILINGUAL, &cfg).unwrap_err().to_string();
+        assert!(err.contains("available: ENUSCC, KRCC"));
+
+        cfg.formats.sami.class = None;
+        for n in [0, 3] {
+            cfg.formats.sami.track = Some(n);
+            assert!(parse_sami(BILINGUAL, &cfg).is_err());
+        }
+    }
+
+    #[test]
+    fn lists_classes_as_tracks() {
+        let tracks = list_classes(BILINGUAL).unwrap();
+        let listed: Vec<_> = tracks
+            .iter()
+            .map(|t| (t.number, t.name.as_deref(), t.language.as_deref()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (1, Some("ENUSCC [English]"), Some("en-US")),
+                (2, Some("KRCC [Korean]"), Some("ko-KR"))
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_sync_is_an_error() {
+        let cfg = Config::default();
+        assert!(parse_sami("<SAMI><BODY><SYNC Start=soon><P>Hi</BODY></SAMI>", &cfg).is_err());
+        assert!(parse_sami("<SAMI><BODY><SYNC><P>Hi</BODY></SAMI>", &cfg).is_err());
+    }
+}
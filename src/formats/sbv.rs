@@ -0,0 +1,184 @@
+use anyhow::{Result, anyhow};
+use std::io::{BufRead, Write};
+
+use crate::{
+    config::Config,
+    formats::{
+        plain::text_blocks,
+        registry::{CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        time::{format_sbv_timestamp, parse_time_to_ms},
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct Sbv;
+
+impl FormatReader for Sbv {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_sbv(input, cfg)
+    }
+
+    fn read_stream<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        _cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        let cues = text_blocks(input).filter_map(|block| match block {
+            Ok(block) => parse_sbv_block(&block).transpose(),
+            Err(e) => Some(Err(e)),
+        });
+        Ok(CueStream {
+            meta: Meta::default(),
+            cues: Box::new(cues),
+        })
+    }
+}
+
+impl FormatWriter for Sbv {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(SbvSink { cfg, out }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("sbv", &["sbv"])
+        .sniff(|raw| {
+            raw.trim_start_matches('\u{feff}')
+                .lines()
+                .find(|l| !l.trim().is_empty())
+                .is_some_and(|l| {
+                    // SubViewer uses the same layout with two-digit hundredths.
+                    let millis = l.split(',').next().and_then(|a| a.rsplit_once('.'));
+                    millis.is_some_and(|(_, f)| f.trim().len() == 3)
+                        && parse_sbv_time_range(l).is_ok()
+                })
+        })
+        .reader(Sbv)
+        .writer(Sbv)
+}
+
+pub fn parse_sbv(input: &str, cfg: &Config) -> Result<Transcript> {
+    Sbv.read_stream(Box::new(input.as_bytes()), cfg)?
+        .collect_transcript()
+}
+
+/// One `start,end` line followed by the cue text; blocks without a time line
+/// are skipped.
+fn parse_sbv_block(block: &str) -> Result<Option<Cue>> {
+    let mut lines = block.lines();
+    let Some(first) = lines.next() else {
+        return Ok(None);
+    };
+    let first = first.trim_start_matches('\u{feff}').trim();
+    if !first.contains(',') {
+        tracing::debug!(line = first, "skipping SBV block without time range");
+        return Ok(None);
+    }
+
+    let (start_ms, end_ms) = parse_sbv_time_range(first)?;
+    let text = lines
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(Some(Cue {
+        start_ms,
+        end_ms,
+        text,
+        speaker: None,
+        words: Vec::new(),
+    }))
+}
+
+fn parse_sbv_time_range(line: &str) -> Result<(i64, i64)> {
+    let (a, b) = line
+        .trim()
+        .split_once(',')
+        .ok_or_else(|| anyhow!("missing ',' in SBV time range: '{line}'"))?;
+    if !a.contains(':') || !b.contains(':') {
+        return Err(anyhow!("unrecognized SBV time range: '{line}'"));
+    }
+    let start = parse_time_to_ms(a)?;
+    let end = parse_time_to_ms(b)?;
+    Ok((start, end))
+}
+
+struct SbvSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+}
+
+impl CueSink for SbvSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        writeln!(
+            self.out,
+            "{},{}",
+            format_sbv_timestamp(cue.start_ms + self.cfg.policy.timestamp_offset_ms),
+            format_sbv_timestamp(cue.end_ms + self.cfg.policy.timestamp_offset_ms)
+        )?;
+        for line in cue.text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            writeln!(self.out, "{line}")?;
+        }
+        writeln!(self.out)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    fn sniff(raw: &str) -> bool {
+        (entry().sniff.unwrap())(raw)
+    }
+
+    #[test]
+    fn roundtrip() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            cue(1500, 4000, "Two\nlines"),
+            cue(3_723_004, 3_725_000, "Later"),
+        ]);
+        let out = render(&Sbv, &t, &cfg);
+        assert_eq!(
+            out,
+            "0:00:01.500,0:00:04.000\nTwo\nlines\n\n1:02:03.004,1:02:05.000\nLater\n\n"
+        );
+        assert_eq!(timings(&parse_sbv(&out, &cfg).unwrap()), timings(&t));
+    }
+
+    #[test]
+    fn skips_blocks_without_time_range() {
+        let cfg = Config::default();
+        let raw = "\u{feff}0:00:00.000,0:00:01.000\nHi\n\nstray note\n\n0:00:02.000,0:00:03.000\n  Bye  \n";
+        assert_eq!(
+            timings(&parse_sbv(raw, &cfg).unwrap()),
+            vec![(0, 1000, "Hi"), (2000, 3000, "Bye")]
+        );
+    }
+
+    #[test]
+    fn bad_time_range_is_an_error() {
+        let cfg = Config::default();
+        assert!(parse_sbv("0:00:00.000,soon\nHi\n", &cfg).is_err());
+    }
+
+    #[test]
+    fn sniff_leaves_subviewer_and_csv_alone() {
+        assert!(sniff("\n0:00:01.500,0:00:04.000\nHi\n"));
+        assert!(!sniff("00:00:01.50,00:00:04.00\nHi\n"));
+        assert!(!sniff("name,age\nAnn,3\n"));
+    }
+}
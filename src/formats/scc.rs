@@ -0,0 +1,943 @@
+use anyhow::{Result, anyhow};
+use std::{collections::BTreeSet, io::Write};
+
+use crate::{
+    config::Config,
+    formats::{
+        plain::synth_duration_ms,
+        registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+const HEADER: &str = "Scenarist_SCC V1.0";
+const ROWS: usize = 15;
+const COLS: usize = 32;
+/// Pop-on captions are limited to four rows at the bottom of the screen.
+const MAX_CAPTION_ROWS: usize = 4;
+
+pub struct Scc;
+
+impl FormatReader for Scc {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_scc(input, cfg)
+    }
+}
+
+impl FormatWriter for Scc {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        let channel = cfg.formats.scc.channel;
+        if !(1..=2).contains(&channel) {
+            return Err(anyhow!(
+                "SCC output supports caption channels 1 and 2, not {channel}"
+            ));
+        }
+        writeln!(out, "{HEADER}")?;
+        writeln!(out)?;
+        Ok(Box::new(SccSink {
+            cfg,
+            out,
+            channel_bit: if channel == 2 { 0x08 } else { 0x00 },
+            cursor: 0,
+            pending_clear: None,
+        }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("scc", &["scc"])
+        .config_section("scc")
+        .sniff(|raw| {
+            raw.trim_start_matches('\u{feff}')
+                .starts_with("Scenarist_SCC")
+        })
+        .reader(Scc)
+        .writer(Scc)
+}
+
+pub fn parse_scc(input: &str, cfg: &Config) -> Result<Transcript> {
+    let mut lines = input.trim_start_matches('\u{feff}').lines();
+    let header = lines.next().unwrap_or("").trim();
+    if !header.starts_with("Scenarist_SCC") {
+        return Err(anyhow!("SCC: missing '{HEADER}' header"));
+    }
+
+    let mut dec = Decoder::new(cfg);
+    for (line_no, line) in lines.enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (tc, data) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| anyhow!("SCC line {}: expected 'timecode<TAB>data'", line_no + 2))?;
+        let frame = parse_timecode(tc)?;
+
+        for (i, word) in data.split_whitespace().enumerate() {
+            let w = u16::from_str_radix(word, 16)
+                .ok()
+                .filter(|_| word.len() == 4)
+                .ok_or_else(|| anyhow!("SCC line {}: bad byte pair '{word}'", line_no + 2))?;
+            // Each byte pair takes one frame to transmit.
+            let now = frame_to_ms(frame + i as i64);
+            dec.feed(((w >> 8) as u8) & 0x7F, (w as u8) & 0x7F, now);
+        }
+    }
+
+    Ok(Transcript::new(dec.finish()))
+}
+
+/// `HH:MM:SS:FF` (non-drop) or `HH:MM:SS;FF` (drop-frame) to a frame count
+/// at 29.97 fps.
+fn parse_timecode(tc: &str) -> Result<i64> {
+    let drop = tc.contains([';', '.', ',']);
+    let parts: Vec<i64> = tc
+        .split([':', ';', '.', ','])
+        .map(|p| p.parse::<i64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| anyhow!("bad SCC timecode: '{tc}'"))?;
+    let [h, m, s, f] = parts[..] else {
+        return Err(anyhow!("bad SCC timecode: '{tc}'"));
+    };
+
+    let nominal = ((h * 60 + m) * 60 + s) * 30 + f;
+    if drop {
+        // Frame numbers 0 and 1 are skipped every minute except every tenth.
+        let minutes = h * 60 + m;
+        Ok(nominal - 2 * (minutes - minutes / 10))
+    } else {
+        Ok(nominal)
+    }
+}
+
+fn format_drop_frame_timecode(frame: i64) -> String {
+    let frame = frame.max(0);
+    let d = frame / 17_982;
+    let m = frame % 17_982;
+    let skipped = 18 * d + if m < 2 { 0 } else { 2 * ((m - 2) / 1_798) };
+    let n = frame + skipped;
+    format!(
+        "{:02}:{:02}:{:02};{:02}",
+        n / 108_000,
+        (n / 1_800) % 60,
+        (n / 30) % 60,
+        n % 30
+    )
+}
+
+fn frame_to_ms(frame: i64) -> i64 {
+    (frame as f64 * 1001.0 / 30.0).round() as i64
+}
+
+fn ms_to_frame(ms: i64) -> i64 {
+    (ms.max(0) as f64 * 30.0 / 1001.0).round() as i64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    PopOn,
+    RollUp,
+    PaintOn,
+}
+
+/// One caption memory: 15 rows of 32 cells.
+#[derive(Clone)]
+struct Screen {
+    cells: Vec<[char; COLS]>,
+}
+
+impl Screen {
+    fn new() -> Self {
+        Self {
+            cells: vec![[' '; COLS]; ROWS],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|r| *r = [' '; COLS]);
+    }
+
+    fn row_text(&self, row: usize) -> String {
+        let s: String = self.cells[row].iter().collect();
+        s.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn text(&self) -> String {
+        (0..ROWS)
+            .map(|r| self.row_text(r))
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// CEA-608 state machine for one caption channel; other channels and XDS
+/// packets are skipped.
+struct Decoder<'a> {
+    cfg: &'a Config,
+    channel: u8,
+    /// Channel named by the latest control code (0 while inside XDS).
+    active: u8,
+    /// The latest miscellaneous control code was a field 2 (CC3/CC4) one.
+    field2: bool,
+    mode: Mode,
+    displayed: Screen,
+    hidden: Screen,
+    row: usize,
+    col: usize,
+    /// When the current displayed caption appeared (pop-on, paint-on).
+    shown_since: Option<i64>,
+    /// When the first character of the current roll-up line arrived.
+    line_start: Option<i64>,
+    /// Control codes are sent twice; the immediate repeat is ignored.
+    last_ctrl: Option<(u8, u8)>,
+    cues: Vec<Cue>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(cfg: &'a Config) -> Self {
+        Self {
+            cfg,
+            channel: cfg.formats.scc.channel,
+            active: 0,
+            field2: false,
+            mode: Mode::PopOn,
+            displayed: Screen::new(),
+            hidden: Screen::new(),
+            row: ROWS - 1,
+            col: 0,
+            shown_since: None,
+            line_start: None,
+            last_ctrl: None,
+            cues: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, b1: u8, b2: u8, now: i64) {
+        match b1 {
+            0x10..=0x1F => {
+                if self.last_ctrl == Some((b1, b2)) {
+                    self.last_ctrl = None;
+                    return;
+                }
+                self.last_ctrl = Some((b1, b2));
+
+                let base = b1 & 0x17;
+                if matches!(base, 0x14 | 0x15) && (0x20..=0x2F).contains(&b2) {
+                    // Only miscellaneous control codes tell the fields apart;
+                    // PACs, mid-row and special characters stay in the field
+                    // the last one selected.
+                    self.field2 = base == 0x15;
+                }
+                let mut channel = if b1 & 0x08 != 0 { 2 } else { 1 };
+                if self.field2 {
+                    channel += 2;
+                }
+                self.active = channel;
+                if channel == self.channel {
+                    self.control(base, b2, now);
+                }
+            }
+            0x01..=0x0F => {
+                self.last_ctrl = None;
+                self.active = 0;
+            }
+            _ => {
+                self.last_ctrl = None;
+                if self.active != self.channel {
+                    return;
+                }
+                for b in [b1, b2] {
+                    if b >= 0x20 {
+                        self.put_char(basic_char(b), now);
+                    }
+                }
+            }
+        }
+    }
+
+    fn control(&mut self, base: u8, b2: u8, now: i64) {
+        match (base, b2) {
+            (0x14 | 0x15, 0x20..=0x2F) => self.misc(b2, now),
+            (0x17, 0x21..=0x23) => self.col = (self.col + usize::from(b2 - 0x20)).min(COLS - 1),
+            // Mid-row style changes occupy one cell, shown as a space.
+            (0x11, 0x20..=0x2F) => self.put_char(' ', now),
+            (0x11, 0x30..=0x3F) => self.put_char(special_char(b2), now),
+            (0x12 | 0x13, 0x20..=0x3F) => {
+                // Extended characters replace the basic fallback sent before them.
+                self.col = self.col.saturating_sub(1);
+                self.put_char(extended_char(base, b2), now);
+            }
+            (_, 0x40..=0x7F) => self.preamble(base, b2),
+            _ => {}
+        }
+    }
+
+    fn misc(&mut self, code: u8, now: i64) {
+        match code {
+            0x20 => {
+                self.leave_roll_up(now);
+                self.mode = Mode::PopOn;
+            }
+            0x21 => {
+                self.col = self.col.saturating_sub(1);
+                let (row, col) = (self.row, self.col);
+                self.target().cells[row][col] = ' ';
+            }
+            0x24 => {
+                let (row, col) = (self.row, self.col);
+                self.target().cells[row][col..].fill(' ');
+            }
+            0x25..=0x27 => {
+                if self.mode != Mode::RollUp {
+                    self.close_display(now);
+                    self.displayed.clear();
+                    self.mode = Mode::RollUp;
+                    self.row = ROWS - 1;
+                }
+                self.col = 0;
+            }
+            0x29 => {
+                self.leave_roll_up(now);
+                self.mode = Mode::PaintOn;
+            }
+            0x2C => {
+                self.flush_line(now);
+                self.close_display(now);
+                self.displayed.clear();
+            }
+            0x2D if self.mode == Mode::RollUp => {
+                self.flush_line(now);
+                self.displayed.cells[self.row] = [' '; COLS];
+                self.col = 0;
+            }
+            0x2E => self.hidden.clear(),
+            0x2F => {
+                self.leave_roll_up(now);
+                self.mode = Mode::PopOn;
+                self.close_display(now);
+                std::mem::swap(&mut self.displayed, &mut self.hidden);
+                self.shown_since = Some(now).filter(|_| !self.displayed.text().is_empty());
+            }
+            _ => {}
+        }
+    }
+
+    /// Preamble address codes move the cursor to a row and indent.
+    fn preamble(&mut self, base: u8, b2: u8) {
+        const ROW_PAIRS: [(usize, usize); 8] = [
+            (11, 11),
+            (1, 2),
+            (3, 4),
+            (12, 13),
+            (14, 15),
+            (5, 6),
+            (7, 8),
+            (9, 10),
+        ];
+        let (even, odd) = ROW_PAIRS[usize::from(base & 0x07)];
+        let row = if b2 & 0x20 != 0 { odd } else { even };
+        self.row = row - 1;
+        self.col = if b2 & 0x10 != 0 {
+            usize::from((b2 & 0x0E) >> 1) * 4
+        } else {
+            0
+        };
+    }
+
+    fn target(&mut self) -> &mut Screen {
+        match self.mode {
+            Mode::PopOn => &mut self.hidden,
+            Mode::RollUp | Mode::PaintOn => &mut self.displayed,
+        }
+    }
+
+    fn put_char(&mut self, ch: char, now: i64) {
+        let (row, col) = (self.row, self.col.min(COLS - 1));
+        self.target().cells[row][col] = ch;
+        self.col = (col + 1).min(COLS);
+
+        match self.mode {
+            Mode::PaintOn if self.shown_since.is_none() => self.shown_since = Some(now),
+            Mode::RollUp if self.line_start.is_none() => self.line_start = Some(now),
+            _ => {}
+        }
+    }
+
+    fn leave_roll_up(&mut self, now: i64) {
+        if self.mode == Mode::RollUp {
+            self.flush_line(now);
+            self.displayed.clear();
+        }
+    }
+
+    /// Ends the roll-up line being typed at the cursor row.
+    fn flush_line(&mut self, now: i64) {
+        if let Some(start) = self.line_start.take() {
+            let text = self.displayed.row_text(self.row);
+            self.push_cue(start, Some(now), text);
+        }
+    }
+
+    /// Ends the pop-on or paint-on caption currently on screen.
+    fn close_display(&mut self, now: i64) {
+        if let Some(start) = self.shown_since.take() {
+            let text = self.displayed.text();
+            self.push_cue(start, Some(now), text);
+        }
+    }
+
+    fn push_cue(&mut self, start_ms: i64, end_ms: Option<i64>, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        let end_ms = end_ms
+            .filter(|&e| e > start_ms)
+            .unwrap_or_else(|| start_ms + synth_duration_ms(&text, self.cfg));
+        self.cues.push(Cue {
+            start_ms,
+            end_ms,
+            text,
+            speaker: None,
+            words: Vec::new(),
+        });
+    }
+
+    fn finish(mut self) -> Vec<Cue> {
+        if let Some(start) = self.line_start.take() {
+            let text = self.displayed.row_text(self.row);
+            self.push_cue(start, None, text);
+        }
+        if let Some(start) = self.shown_since.take() {
+            let text = self.displayed.text();
+            self.push_cue(start, None, text);
+        }
+        self.cues.sort_by_key(|c| c.start_ms);
+        self.cues
+    }
+}
+
+/// Basic characters are ASCII except for a handful of accented letters.
+fn basic_char(b: u8) -> char {
+    match b {
+        0x2A => 'á',
+        0x5C => 'é',
+        0x5E => 'í',
+        0x5F => 'ó',
+        0x60 => 'ú',
+        0x7B => 'ç',
+        0x7C => '÷',
+        0x7D => 'Ñ',
+        0x7E => 'ñ',
+        0x7F => '█',
+        _ => char::from(b),
+    }
+}
+
+const SPECIAL: [char; 16] = [
+    '®', '°', '½', '¿', '™', '¢', '£', '♪', 'à', ' ', 'è', 'â', 'ê', 'î', 'ô', 'û',
+];
+
+/// Extended characters with the basic character sent ahead of each one for
+/// decoders that only know the basic set.
+const EXTENDED_12: [(char, char); 32] = [
+    ('Á', 'A'),
+    ('É', 'E'),
+    ('Ó', 'O'),
+    ('Ú', 'U'),
+    ('Ü', 'U'),
+    ('ü', 'u'),
+    ('‘', '\''),
+    ('¡', '!'),
+    ('*', '.'),
+    ('\'', '\''),
+    ('—', '-'),
+    ('©', 'c'),
+    ('℠', ' '),
+    ('•', '.'),
+    ('“', '"'),
+    ('”', '"'),
+    ('À', 'A'),
+    ('Â', 'A'),
+    ('Ç', 'C'),
+    ('È', 'E'),
+    ('Ê', 'E'),
+    ('Ë', 'E'),
+    ('ë', 'e'),
+    ('Î', 'I'),
+    ('Ï', 'I'),
+    ('ï', 'i'),
+    ('Ô', 'O'),
+    ('Ù', 'U'),
+    ('ù', 'u'),
+    ('Û', 'U'),
+    ('«', '"'),
+    ('»', '"'),
+];
+
+const EXTENDED_13: [(char, char); 32] = [
+    ('Ã', 'A'),
+    ('ã', 'a'),
+    ('Í', 'I'),
+    ('Ì', 'I'),
+    ('ì', 'i'),
+    ('Ò', 'O'),
+    ('ò', 'o'),
+    ('Õ', 'O'),
+    ('õ', 'o'),
+    ('{', '('),
+    ('}', ')'),
+    ('\\', '/'),
+    ('^', ' '),
+    ('_', '-'),
+    ('|', '!'),
+    ('~', '-'),
+    ('Ä', 'A'),
+    ('ä', 'a'),
+    ('Ö', 'O'),
+    ('ö', 'o'),
+    ('ß', 's'),
+    ('¥', 'Y'),
+    ('¤', ' '),
+    ('¦', '!'),
+    ('Å', 'A'),
+    ('å', 'a'),
+    ('Ø', 'O'),
+    ('ø', 'o'),
+    ('┌', '+'),
+    ('┐', '+'),
+    ('└', '+'),
+    ('┘', '+'),
+];
+
+fn special_char(b2: u8) -> char {
+    SPECIAL[usize::from(b2 & 0x0F)]
+}
+
+fn extended_char(base: u8, b2: u8) -> char {
+    let table = if base == 0x12 {
+        &EXTENDED_12
+    } else {
+        &EXTENDED_13
+    };
+    table[usize::from(b2 - 0x20)].0
+}
+
+/// How one character is sent on the wire.
+#[derive(Debug, Clone, Copy)]
+enum Encoded {
+    Basic(u8),
+    Special(u8),
+    Extended { b1: u8, b2: u8, fallback: u8 },
+}
+
+fn encode_char(ch: char) -> Option<Encoded> {
+    if let Some(b) = (0x20..=0x7Fu8).find(|&b| basic_char(b) == ch) {
+        return Some(Encoded::Basic(b));
+    }
+    if let Some(i) = SPECIAL.iter().position(|&c| c == ch) {
+        return Some(Encoded::Special(0x30 + i as u8));
+    }
+    for (b1, table) in [(0x12, &EXTENDED_12), (0x13, &EXTENDED_13)] {
+        if let Some(i) = table.iter().position(|&(c, _)| c == ch) {
+            let fallback = table[i].1;
+            return Some(Encoded::Extended {
+                b1,
+                b2: 0x20 + i as u8,
+                fallback: (0x20..=0x7Fu8)
+                    .find(|&b| basic_char(b) == fallback)
+                    .unwrap_or(0x20),
+            });
+        }
+    }
+    None
+}
+
+fn with_parity(b: u8) -> u8 {
+    if b.count_ones().is_multiple_of(2) {
+        b | 0x80
+    } else {
+        b
+    }
+}
+
+/// Writes pop-on captions, each loaded into non-displayed memory ahead of its
+/// start and flipped on screen with End Of Caption at the start frame.
+struct SccSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    channel_bit: u8,
+    /// First frame not yet taken by transmitted data.
+    cursor: i64,
+    /// Frame at which the caption on screen should be erased.
+    pending_clear: Option<i64>,
+}
+
+impl SccSink<'_> {
+    fn ctrl(&self, b1: u8, b2: u8) -> [[u8; 2]; 2] {
+        let pair = [b1 | self.channel_bit, b2];
+        [pair, pair]
+    }
+
+    fn emit(&mut self, frame: i64, words: &[[u8; 2]]) -> Result<i64> {
+        let frame = frame.max(self.cursor);
+        let data = words
+            .iter()
+            .map(|[a, b]| format!("{:02x}{:02x}", with_parity(*a), with_parity(*b)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(self.out, "{}\t{data}", format_drop_frame_timecode(frame))?;
+        writeln!(self.out)?;
+        self.cursor = frame + words.len() as i64;
+        Ok(frame)
+    }
+
+    /// Resume caption loading, erase non-displayed memory, then each row with
+    /// its preamble address and centring tab offset.
+    fn load_words(&self, rows: &[String]) -> Vec<[u8; 2]> {
+        let mut words = Vec::new();
+        words.extend(self.ctrl(0x14, 0x20));
+        words.extend(self.ctrl(0x14, 0x2E));
+
+        let first_row = ROWS - rows.len() + 1;
+        for (i, row) in rows.iter().enumerate() {
+            let indent = (COLS - row.chars().count().min(COLS)) / 2;
+            let (b1, b2) = preamble_code(first_row + i, indent / 4 * 4);
+            words.extend(self.ctrl(b1, b2));
+            if !indent.is_multiple_of(4) {
+                words.extend(self.ctrl(0x17, 0x20 + (indent % 4) as u8));
+            }
+
+            let mut pending: Option<u8> = None;
+            let flush = |words: &mut Vec<[u8; 2]>, pending: &mut Option<u8>| {
+                if let Some(b) = pending.take() {
+                    words.push([b, 0x00]);
+                }
+            };
+            for ch in row.chars() {
+                match encode_char(ch) {
+                    Some(Encoded::Basic(b)) => match pending.take() {
+                        Some(a) => words.push([a, b]),
+                        None => pending = Some(b),
+                    },
+                    Some(Encoded::Special(b2)) => {
+                        flush(&mut words, &mut pending);
+                        words.extend(self.ctrl(0x11, b2));
+                    }
+                    Some(Encoded::Extended { b1, b2, fallback }) => {
+                        match pending.take() {
+                            Some(a) => words.push([a, fallback]),
+                            None => words.push([fallback, 0x00]),
+                        }
+                        words.extend(self.ctrl(b1, b2));
+                    }
+                    None => {}
+                }
+            }
+            flush(&mut words, &mut pending);
+        }
+        words
+    }
+
+    fn caption(&mut self, rows: &[String], start: i64, end: i64) -> Result<()> {
+        let load = self.load_words(rows);
+        let len = load.len() as i64;
+        let edm = self.ctrl(0x14, 0x2C);
+        let mut loaded = false;
+
+        // Erase the previous caption if there is a gap before this one;
+        // loading can happen while it is still on screen.
+        if let Some(clear) = self.pending_clear.take().filter(|&c| c < start) {
+            let load_at = (start - len).max(self.cursor);
+            if clear + 2 <= load_at {
+                self.emit(clear, &edm)?;
+            } else if clear >= load_at + len {
+                self.emit(load_at, &load)?;
+                self.emit(clear, &edm)?;
+                loaded = true;
+            } else if clear - len >= self.cursor {
+                self.emit(clear - len, &load)?;
+                self.emit(clear, &edm)?;
+                loaded = true;
+            } else {
+                self.emit(clear, &edm)?;
+            }
+        }
+        if !loaded {
+            self.emit(start - len, &load)?;
+        }
+
+        let eoc = self.ctrl(0x14, 0x2F);
+        let shown_at = self.emit(start, &eoc)?;
+        if shown_at > start {
+            tracing::debug!(
+                frames = shown_at - start,
+                "SCC caption shown late; not enough time to load it"
+            );
+        }
+        self.pending_clear = Some(end.max(shown_at + 1));
+        Ok(())
+    }
+}
+
+impl CueSink for SccSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let offset = self.cfg.policy.timestamp_offset_ms;
+
+        let mut unsupported = BTreeSet::new();
+        let text: String = cue
+            .text
+            .chars()
+            .filter(|&c| {
+                let ok = c == '\n' || encode_char(c).is_some();
+                if !ok && !c.is_whitespace() {
+                    unsupported.insert(c);
+                }
+                ok || c.is_whitespace()
+            })
+            .map(|c| {
+                if c.is_whitespace() && c != '\n' {
+                    ' '
+                } else {
+                    c
+                }
+            })
+            .collect();
+        if !unsupported.is_empty() {
+            let chars: String = unsupported.into_iter().collect();
+            if self.cfg.formats.scc.strict {
+                return Err(anyhow!(
+                    "SCC cannot represent '{chars}' in cue at {} ms",
+                    cue.start_ms
+                ));
+            }
+            tracing::warn!(
+                start_ms = cue.start_ms,
+                chars = chars.as_str(),
+                "dropping characters outside the CEA-608 set"
+            );
+        }
+
+        let rows = wrap_rows(&text);
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let chunks: Vec<&[String]> = rows.chunks(MAX_CAPTION_ROWS).collect();
+        if chunks.len() > 1 {
+            tracing::warn!(
+                start_ms = cue.start_ms,
+                rows = rows.len(),
+                captions = chunks.len(),
+                "cue exceeds 4 rows of 32 columns; splitting into several SCC captions"
+            );
+        }
+
+        let start = ms_to_frame(cue.start_ms + offset);
+        let end = ms_to_frame(cue.end_ms + offset).max(start + 1);
+        let n = chunks.len() as i64;
+        for (k, rows) in chunks.into_iter().enumerate() {
+            let k = k as i64;
+            let span = end - start;
+            self.caption(rows, start + span * k / n, start + span * (k + 1) / n)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if let Some(clear) = self.pending_clear.take() {
+            let edm = self.ctrl(0x14, 0x2C);
+            self.emit(clear, &edm)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Word-wraps each text line to 32 columns, hard-splitting longer words.
+fn wrap_rows(text: &str) -> Vec<String> {
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let mut cur = String::new();
+        for word in line.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            while word.len() > COLS {
+                if !cur.is_empty() {
+                    rows.push(std::mem::take(&mut cur));
+                }
+                rows.push(word.drain(..COLS).collect());
+            }
+            let word: String = word.into_iter().collect();
+            let needed = cur.chars().count() + usize::from(!cur.is_empty()) + word.chars().count();
+            if needed > COLS && !cur.is_empty() {
+                rows.push(std::mem::take(&mut cur));
+            }
+            if !cur.is_empty() {
+                cur.push(' ');
+            }
+            cur.push_str(&word);
+        }
+        if !cur.is_empty() {
+            rows.push(cur);
+        }
+    }
+    rows
+}
+
+/// Preamble address code for a row (1-15) and an indent that is a multiple of 4.
+fn preamble_code(row: usize, indent: usize) -> (u8, u8) {
+    let (b1, second) = match row {
+        1 => (0x11, false),
+        2 => (0x11, true),
+        3 => (0x12, false),
+        4 => (0x12, true),
+        5 => (0x15, false),
+        6 => (0x15, true),
+        7 => (0x16, false),
+        8 => (0x16, true),
+        9 => (0x17, false),
+        10 => (0x17, true),
+        11 => (0x10, false),
+        12 => (0x13, false),
+        13 => (0x13, true),
+        14 => (0x14, false),
+        _ => (0x14, true),
+    };
+    let b2 = 0x40 | if second { 0x20 } else { 0x00 } | 0x10 | ((indent / 4) as u8) << 1;
+    (b1, b2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    /// Times as they survive a trip through 29.97 fps frames.
+    fn on_frames(t: &Transcript) -> Vec<(i64, i64, &str)> {
+        timings(t)
+            .into_iter()
+            .map(|(s, e, text)| {
+                (
+                    frame_to_ms(ms_to_frame(s)),
+                    frame_to_ms(ms_to_frame(e)),
+                    text,
+                )
+            })
+            .collect()
+    }
+
+    fn on_channel(channel: u8) -> Config {
+        let mut cfg = Config::default();
+        cfg.formats.scc.channel = channel;
+        cfg
+    }
+
+    #[test]
+    fn roundtrip_pop_on() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            cue(2000, 4000, "Café ♪ Über"),
+            cue(4000, 5500, "Two\nrows"),
+            cue(8000, 9000, "After a gap"),
+        ]);
+        let out = render(&Scc, &t, &cfg);
+        assert!(out.starts_with("Scenarist_SCC V1.0\n\n"));
+        assert!(out.contains(";"), "timecodes are drop-frame");
+        assert_eq!(timings(&parse_scc(&out, &cfg).unwrap()), on_frames(&t));
+    }
+
+    #[test]
+    fn roundtrip_on_channel_two() {
+        let cfg = on_channel(2);
+        let t = Transcript::new(vec![cue(2000, 3000, "Second channel")]);
+        let out = render(&Scc, &t, &cfg);
+        assert_eq!(timings(&parse_scc(&out, &cfg).unwrap()), on_frames(&t));
+        assert!(parse_scc(&out, &on_channel(1)).unwrap().cues.is_empty());
+    }
+
+    #[test]
+    fn long_cues_are_split_into_captions() {
+        let cfg = Config::default();
+        let text = "word ".repeat(40);
+        let out = render(&Scc, &Transcript::new(vec![cue(2000, 10_000, &text)]), &cfg);
+        let back = parse_scc(&out, &cfg).unwrap();
+        assert_eq!(back.cues.len(), 2);
+        assert!(
+            back.cues
+                .iter()
+                .all(|c| c.text.lines().count() <= MAX_CAPTION_ROWS)
+        );
+    }
+
+    #[test]
+    fn unsupported_characters() {
+        let t = Transcript::new(vec![cue(2000, 3000, "Hi 😀")]);
+        let out = render(&Scc, &t, &Config::default());
+        assert_eq!(
+            parse_scc(&out, &Config::default()).unwrap().cues[0].text,
+            "Hi"
+        );
+
+        let mut cfg = Config::default();
+        cfg.formats.scc.strict = true;
+        assert!(Scc.write(&t, &cfg, &mut Vec::new()).is_err());
+        assert!(Scc.write(&t, &on_channel(3), &mut Vec::new()).is_err());
+    }
+
+    /// CC1 "One" and CC3 "Hi" in the same stream; CC3 uses field 2
+    /// miscellaneous codes (0x15) but shares PAC and text bytes with CC1.
+    const TWO_FIELDS: &str = "Scenarist_SCC V1.0\n\n\
+        00:00:01:00\t9420 9420 94f2 94f2 4fee e580 942f 942f\n\n\
+        00:00:02:00\t1520 1520 94f2 94f2 c8e9 152f 152f\n\n\
+        00:00:03:00\t942c 942c\n\n\
+        00:00:04:00\t152c 152c\n";
+
+    #[test]
+    fn selects_cc1_or_cc3() {
+        assert_eq!(
+            timings(&parse_scc(TWO_FIELDS, &on_channel(1)).unwrap()),
+            vec![(frame_to_ms(36), frame_to_ms(90), "One")]
+        );
+        assert_eq!(
+            timings(&parse_scc(TWO_FIELDS, &on_channel(3)).unwrap()),
+            vec![(frame_to_ms(65), frame_to_ms(120), "Hi")]
+        );
+    }
+
+    #[test]
+    fn roll_up_lines_become_cues() {
+        let raw = "Scenarist_SCC V1.0\n\n\
+            00:00:01:00\t9425 9425 94ad 94ad 9470 9470 4f6e e580\n\n\
+            00:00:02:00\t94ad 94ad 5477 ef80\n\n\
+            00:00:03:00\t942c 942c\n";
+        assert_eq!(
+            timings(&parse_scc(raw, &Config::default()).unwrap()),
+            vec![
+                (frame_to_ms(36), frame_to_ms(60), "One"),
+                (frame_to_ms(62), frame_to_ms(90), "Two")
+            ]
+        );
+    }
+
+    #[test]
+    fn timecodes() {
+        assert_eq!(parse_timecode("00:01:00:00").unwrap(), 1800);
+        assert_eq!(parse_timecode("00:01:00;02").unwrap(), 1800);
+        assert_eq!(parse_timecode("00:10:00;00").unwrap(), 17_982);
+        assert_eq!(format_drop_frame_timecode(1800), "00:01:00;02");
+        assert_eq!(format_drop_frame_timecode(17_982), "00:10:00;00");
+        assert!(parse_timecode("01:00").is_err());
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        let cfg = Config::default();
+        assert!(parse_scc("00:00:01:00\t9420\n", &cfg).is_err());
+        assert!(parse_scc("Scenarist_SCC V1.0\n00:00:01:00\t94\n", &cfg).is_err());
+        assert!(parse_scc("Scenarist_SCC V1.0\n00:00:01:00\n", &cfg).is_err());
+    }
+}
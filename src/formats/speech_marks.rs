@@ -0,0 +1,351 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use std::fs;
+
+use crate::{
+    config::Config,
+    formats::{
+        asr::{AsrWord, group_words, number, seconds_to_ms},
+        plain::synth_duration_ms,
+        registry::{FormatEntry, FormatReader},
+        xml::{Element, Node, parse_document},
+    },
+    model::{Cue, Transcript, Word},
+};
+
+pub struct Polly;
+
+impl FormatReader for Polly {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_polly(input, cfg)
+    }
+}
+
+pub fn polly_entry() -> FormatEntry {
+    FormatEntry::new("polly", &["marks"])
+        .config_section("speech_marks")
+        .sniff(looks_like_polly)
+        .reader(Polly)
+}
+
+/// Amazon Polly speech marks are JSON Lines, one mark object per line.
+pub fn looks_like_polly(raw: &str) -> bool {
+    raw.lines()
+        .find(|l| !l.trim().is_empty())
+        .and_then(|l| serde_json::from_str::<Value>(l.trim()).ok())
+        .is_some_and(|v| v.get("time").is_some() && v.get("type").is_some_and(Value::is_string))
+}
+
+struct Mark {
+    time_ms: i64,
+    kind: String,
+    /// Byte offsets into the synthesized text.
+    start: Option<u64>,
+    end: Option<u64>,
+    value: String,
+}
+
+/// Sentence marks become cues and the word marks inside them their word
+/// timings. Polly only reports when a mark starts, so each ends where the
+/// next one begins and the last one gets a duration from its length.
+pub fn parse_polly(input: &str, cfg: &Config) -> Result<Transcript> {
+    let mut marks = Vec::new();
+    for (i, line) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let v: Value = serde_json::from_str(line.trim())
+            .with_context(|| format!("Polly speech marks: line {} is not JSON", i + 1))?;
+        let time_ms = v
+            .get("time")
+            .and_then(number)
+            .ok_or_else(|| anyhow!("Polly speech marks: line {} has no time", i + 1))?
+            .round() as i64;
+        marks.push(Mark {
+            time_ms,
+            kind: v["type"].as_str().unwrap_or("").to_string(),
+            start: v.get("start").and_then(Value::as_u64),
+            end: v.get("end").and_then(Value::as_u64),
+            value: v["value"].as_str().unwrap_or("").to_string(),
+        });
+    }
+    marks.sort_by_key(|m| m.time_ms);
+
+    let sentences: Vec<&Mark> = marks.iter().filter(|m| m.kind == "sentence").collect();
+    let words: Vec<&Mark> = marks.iter().filter(|m| m.kind == "word").collect();
+
+    if sentences.is_empty() {
+        let words: Vec<AsrWord> = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| AsrWord {
+                start_ms: w.time_ms,
+                end_ms: words
+                    .get(i + 1)
+                    .map_or(w.time_ms + synth_duration_ms(&w.value, cfg), |n| n.time_ms),
+                text: w.value.clone(),
+                speaker: None,
+                confidence: None,
+                punctuation: false,
+            })
+            .collect();
+        return Ok(Transcript::new(group_words(&words, &cfg.formats.json.asr)));
+    }
+
+    let mut cues = Vec::with_capacity(sentences.len());
+    for (i, s) in sentences.iter().enumerate() {
+        let next = sentences.get(i + 1).map(|n| n.time_ms);
+        let inside: Vec<&&Mark> = words
+            .iter()
+            .filter(|w| match (s.start, s.end, w.start) {
+                (Some(start), Some(end), Some(at)) => start <= at && at < end,
+                _ => w.time_ms >= s.time_ms && next.is_none_or(|n| w.time_ms < n),
+            })
+            .collect();
+        let end_ms = next.unwrap_or_else(|| match inside.last() {
+            Some(w) => w.time_ms + synth_duration_ms(&w.value, cfg),
+            None => s.time_ms + synth_duration_ms(&s.value, cfg),
+        });
+        cues.push(Cue {
+            start_ms: s.time_ms,
+            end_ms,
+            text: s.value.trim().to_string(),
+            speaker: None,
+            words: inside
+                .iter()
+                .enumerate()
+                .map(|(j, w)| Word {
+                    start_ms: w.time_ms,
+                    end_ms: inside.get(j + 1).map_or(end_ms, |n| n.time_ms),
+                    text: w.value.clone(),
+                    confidence: None,
+                })
+                .collect(),
+        });
+    }
+    Ok(Transcript::new(cues))
+}
+
+/// Google Cloud Text-to-Speech `timepoints`, returned for SSML `<mark>`s.
+pub fn looks_like_timepoints(v: &Value) -> bool {
+    v.get("timepoints").is_some_and(Value::is_array)
+}
+
+/// Each mark starts a cue that runs to the next mark. The cue text is what
+/// follows the mark in `[formats.speech_marks] ssml_source`, or the mark
+/// name when no SSML is configured; marks with no text after them only end
+/// the previous cue.
+pub fn parse_timepoints(v: &Value, cfg: &Config) -> Result<Transcript> {
+    let mut points: Vec<(String, i64)> = v["timepoints"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|p| {
+            let name = p
+                .get("markName")
+                .or_else(|| p.get("mark_name"))
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("SSML timepoint without markName"))?;
+            let time_ms = p
+                .get("timeSeconds")
+                .or_else(|| p.get("time_seconds"))
+                .and_then(seconds_to_ms)
+                .ok_or_else(|| anyhow!("SSML timepoint '{name}' has no timeSeconds"))?;
+            Ok((name.to_string(), time_ms))
+        })
+        .collect::<Result<_>>()?;
+    points.sort_by_key(|(_, t)| *t);
+
+    let texts = match &cfg.formats.speech_marks.ssml_source {
+        Some(path) => {
+            let ssml = fs::read_to_string(path)
+                .with_context(|| format!("failed reading SSML source {path}"))?;
+            Some(mark_texts(&ssml).with_context(|| format!("failed parsing SSML source {path}"))?)
+        }
+        None => None,
+    };
+
+    let mut cues = Vec::new();
+    for (i, (name, start_ms)) in points.iter().enumerate() {
+        let text = match &texts {
+            Some(texts) => match texts.iter().find(|(n, _)| n == name) {
+                Some((_, t)) => t.clone(),
+                None => {
+                    tracing::warn!(mark = name.as_str(), "SSML source has no such mark");
+                    continue;
+                }
+            },
+            None => name.clone(),
+        };
+        if text.is_empty() {
+            continue;
+        }
+        let end_ms = points
+            .get(i + 1)
+            .map_or(start_ms + synth_duration_ms(&text, cfg), |(_, t)| *t);
+        cues.push(Cue {
+            start_ms: *start_ms,
+            end_ms,
+            text,
+            speaker: None,
+            words: Vec::new(),
+        });
+    }
+    Ok(Transcript::new(cues))
+}
+
+/// The text between each `<mark name="..."/>` and the next one, in order.
+fn mark_texts(ssml: &str) -> Result<Vec<(String, String)>> {
+    let doc = parse_document(ssml)?;
+    let mut out: Vec<(String, String)> = Vec::new();
+    collect_marks(&doc, &mut out);
+    for (_, text) in &mut out {
+        *text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    Ok(out)
+}
+
+fn collect_marks(e: &Element, out: &mut Vec<(String, String)>) {
+    for c in &e.children {
+        match c {
+            Node::Element(m) if m.name.eq_ignore_ascii_case("mark") => {
+                out.push((m.attr("name").unwrap_or("").to_string(), String::new()));
+            }
+            Node::Element(child) => {
+                // Keeps words in neighbouring elements apart.
+                if let Some((_, text)) = out.last_mut() {
+                    text.push(' ');
+                }
+                collect_marks(child, out);
+            }
+            Node::Text(t) => {
+                if let Some((_, text)) = out.last_mut() {
+                    text.push_str(t);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{json::parse_json, testutil::timings};
+
+    const MARKS: &str = r#"{"time":0,"type":"sentence","start":0,"end":9,"value":"Hi there."}
+{"time":0,"type":"word","start":0,"end":2,"value":"Hi"}
+{"time":400,"type":"word","start":3,"end":8,"value":"there"}
+
+{"time":1100,"type":"viseme","value":"p"}
+{"time":1000,"type":"sentence","start":10,"end":14,"value":"Bye."}
+{"time":1000,"type":"word","start":10,"end":13,"value":"Bye"}
+"#;
+
+    /// Ten characters per second, so synthesized durations are easy to read.
+    fn cfg() -> Config {
+        let mut cfg = Config::default();
+        cfg.policy.chars_per_second = 10.0;
+        cfg.policy.min_duration_ms = 0;
+        cfg
+    }
+
+    fn words(c: &Cue) -> Vec<(i64, i64, &str)> {
+        c.words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn polly_sentences_with_words_by_offset() {
+        let t = parse_json(MARKS, &cfg()).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 1000, "Hi there."), (1000, 1300, "Bye.")]
+        );
+        assert_eq!(
+            words(&t.cues[0]),
+            vec![(0, 400, "Hi"), (400, 1000, "there")]
+        );
+        assert_eq!(words(&t.cues[1]), vec![(1000, 1300, "Bye")]);
+    }
+
+    #[test]
+    fn polly_words_fall_back_to_sentence_times() {
+        let raw: String = MARKS
+            .lines()
+            .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+            .map(|mut v| {
+                let m = v.as_object_mut().unwrap();
+                m.remove("start");
+                m.remove("end");
+                format!("{v}\n")
+            })
+            .collect();
+        let t = parse_polly(&raw, &cfg()).unwrap();
+        assert_eq!(t.cues[0].words.len(), 2);
+        assert_eq!(words(&t.cues[1]), vec![(1000, 1300, "Bye")]);
+    }
+
+    #[test]
+    fn polly_words_without_sentences_are_grouped() {
+        let raw = "{\"time\":0,\"type\":\"word\",\"value\":\"Hi\"}\n\
+                   {\"time\":400,\"type\":\"word\",\"value\":\"there\"}\n";
+        let t = parse_polly(raw, &cfg()).unwrap();
+        assert_eq!(timings(&t), vec![(0, 900, "Hi there")]);
+    }
+
+    #[test]
+    fn polly_errors_and_sniff() {
+        let err = parse_polly("{\"time\":0,\"type\":\"word\"}\nnope\n", &cfg()).unwrap_err();
+        assert!(err.to_string().contains("line 2 is not JSON"));
+        assert!(parse_polly("{\"type\":\"word\"}\n", &cfg()).is_err());
+
+        let sniff = polly_entry().sniff.unwrap();
+        assert!(sniff(MARKS));
+        assert!(!sniff("{\"time\": 1, \"text\": \"x\"}\n"));
+        assert!(!sniff("{\n  \"time\": 1,\n  \"type\": \"word\"\n}\n"));
+    }
+
+    #[test]
+    fn timepoints_use_mark_names_without_ssml() {
+        let raw = r#"{"timepoints":[
+            {"markName":"second","timeSeconds":1.5},
+            {"mark_name":"first","time_seconds":0.5}
+        ]}"#;
+        assert_eq!(
+            timings(&parse_json(raw, &cfg()).unwrap()),
+            vec![(500, 1500, "first"), (1500, 2100, "second")]
+        );
+        assert!(parse_json(r#"{"timepoints":[{"timeSeconds":1}]}"#, &cfg()).is_err());
+        assert!(parse_json(r#"{"timepoints":[{"markName":"a"}]}"#, &cfg()).is_err());
+    }
+
+    #[test]
+    fn timepoints_take_text_from_ssml_source() {
+        let path = std::env::temp_dir().join(format!("speech-marks-{}.ssml", std::process::id()));
+        fs::write(
+            &path,
+            r#"<speak><mark name="s1"/>Hello <emphasis>big</emphasis>
+world<mark name="s2"/><mark name="s3"/>End</speak>"#,
+        )
+        .unwrap();
+        let mut cfg = cfg();
+        cfg.formats.speech_marks.ssml_source = Some(path.to_string_lossy().into_owned());
+        let v = serde_json::json!({"timepoints": [
+            {"markName": "s1", "timeSeconds": 0.0},
+            {"markName": "s2", "timeSeconds": 1.0},
+            {"markName": "s3", "timeSeconds": 1.2},
+            {"markName": "gone", "timeSeconds": 2.0}
+        ]});
+        let t = parse_timepoints(&v, &cfg);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            timings(&t.unwrap()),
+            vec![(0, 1000, "Hello big world"), (1200, 2000, "End")]
+        );
+
+        cfg.formats.speech_marks.ssml_source = Some("/nonexistent/marks.ssml".to_string());
+        assert!(parse_timepoints(&v, &cfg).is_err());
+    }
+}
@@ -1,29 +1,120 @@
-use crate::{config::Config, formats::time::format_srt_timestamp, model::Transcript};
+use anyhow::{Result, anyhow};
+use std::io::{BufRead, Write};
 use textwrap::wrap;
 
-pub fn write_srt(t: &Transcript, cfg: &Config) -> String {
-    let mut out = String::new();
+use crate::{
+    config::Config,
+    formats::{
+        plain::{plain_to_transcript, text_blocks},
+        registry::{CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        time::format_srt_timestamp,
+    },
+    model::{Cue, Meta, Transcript},
+};
 
-    for (i, cue) in t.cues.iter().enumerate() {
-        out.push_str(&(i + 1).to_string());
-        out.push('\n');
+pub struct Srt;
 
-        out.push_str(&format!(
-            "{} --> {}\n",
-            format_srt_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms),
-            format_srt_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms)
+impl FormatReader for Srt {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_srt(input)
+    }
+
+    fn read_stream<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        _cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        tracing::info!("streaming SRT blocks via aspasia");
+        let cues = text_blocks(input).flat_map(|block| match block {
+            Ok(block) => match parse_srt_quietly(&block) {
+                Ok((t, _)) => t.cues.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(e) => vec![Err(e)],
+            },
+            Err(e) => vec![Err(e)],
+        });
+        Ok(CueStream {
+            meta: Meta::default(),
+            cues: Box::new(cues),
+        })
+    }
+}
+
+impl FormatWriter for Srt {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(SrtSink { cfg, out, index: 0 }))
+    }
+}
+
+struct SrtSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    index: usize,
+}
+
+impl CueSink for SrtSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        self.index += 1;
+        write_srt_cue(self.out, self.index, cue, self.cfg)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("srt", &["srt"])
+        .config_section("srt")
+        .reader(Srt)
+        .writer(Srt)
+}
+
+pub fn parse_srt(raw: &str) -> Result<Transcript> {
+    let (t, how) = parse_srt_quietly(raw)?;
+    tracing::info!("{how}");
+    Ok(t)
+}
+
+/// `parse_srt` without the log line, for streams parsed a block at a time.
+fn parse_srt_quietly(raw: &str) -> Result<(Transcript, &'static str)> {
+    if let Ok(srt) = raw.parse::<aspasia::SubRipSubtitle>() {
+        let plain = aspasia::PlainSubtitle::from(&srt);
+        return Ok((plain_to_transcript(&plain), "parsed as SRT via aspasia"));
+    }
+
+    if let Ok(vtt) = raw.parse::<aspasia::WebVttSubtitle>() {
+        let plain = aspasia::PlainSubtitle::from(&vtt);
+        return Ok((
+            plain_to_transcript(&plain),
+            "parsed as VTT via aspasia (fallback)",
         ));
+    }
 
-        let text = cue_text_for_export(cue.text.as_str(), cfg);
-        for line in wrap(&text, cfg.formats.srt.wrap_width) {
-            out.push_str(&line);
-            out.push('\n');
-        }
+    Err(anyhow!("failed to parse as SRT or VTT"))
+}
+
+fn write_srt_cue(out: &mut dyn Write, index: usize, cue: &Cue, cfg: &Config) -> Result<()> {
+    writeln!(out, "{index}")?;
+    writeln!(
+        out,
+        "{} --> {}",
+        format_srt_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms),
+        format_srt_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms)
+    )?;
 
-        out.push('\n');
+    let text = cue_text_for_export(cue.text.as_str(), cfg);
+    for line in wrap(&text, cfg.formats.srt.wrap_width) {
+        writeln!(out, "{line}")?;
     }
 
-    out
+    writeln!(out)?;
+    Ok(())
 }
 
 fn cue_text_for_export(text: &str, cfg: &Config) -> String {
@@ -54,3 +145,46 @@ fn normalize_ws(s: &str) -> String {
     }
     out.trim().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, read_stream, render, timings};
+
+    #[test]
+    fn roundtrip() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            cue(1000, 2500, "Hello there."),
+            cue(3_600_000, 3_601_000, "An hour in."),
+        ]);
+        let out = render(&Srt, &t, &cfg);
+        assert!(out.starts_with("1\n00:00:01,000 --> 00:00:02,500\nHello there.\n\n2\n"));
+        let back = parse_srt(&out).unwrap();
+        assert_eq!(timings(&t), timings(&back));
+    }
+
+    #[test]
+    fn streaming_matches_whole_read() {
+        let cfg = Config::default();
+        let raw = "1\n00:00:01,000 --> 00:00:02,000\nOne\n\n\n2\r\n00:00:03,000 --> 00:00:04,000\r\nTwo\r\nlines\r\n";
+        let streamed = read_stream(&Srt, raw.as_bytes(), &cfg);
+        assert_eq!(
+            timings(&streamed),
+            vec![(1000, 2000, "One"), (3000, 4000, "Two\nlines")]
+        );
+    }
+
+    #[test]
+    fn writer_applies_offset_and_wraps() {
+        let mut cfg = Config::default();
+        cfg.policy.timestamp_offset_ms = 500;
+        cfg.formats.srt.wrap_width = 10;
+        let t = Transcript::new(vec![cue(0, 1000, "one two three four")]);
+        let out = render(&Srt, &t, &cfg);
+        assert_eq!(
+            out,
+            "1\n00:00:00,500 --> 00:00:01,500\none two\nthree four\n\n"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use std::io::Write;
+
+use crate::{
+    config::{Config, SsmlCfg, SsmlCueElement},
+    formats::{
+        registry::{CollectSink, CueSink, FormatEntry, FormatWriter},
+        xml::escape,
+    },
+    model::{Meta, Transcript},
+};
+
+pub struct Ssml;
+
+impl FormatWriter for Ssml {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_ssml(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("ssml", &["ssml"])
+        .config_section("ssml")
+        .writer(Ssml)
+}
+
+/// A `<speak>` script with one `<s>` or `<p>` per cue. Silences between cues
+/// become `<break>`s, mapped speakers get a `<voice>`, and a `<mark>` before
+/// each cue names its 1-based index so speech marks map back to cues.
+fn write_ssml(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let c = &cfg.formats.ssml;
+    let el = match c.cue_element {
+        SsmlCueElement::S => "s",
+        SsmlCueElement::P => "p",
+    };
+    let offset = cfg.policy.timestamp_offset_ms;
+
+    write!(out, "<speak")?;
+    if let Some(lang) = &t.meta.language {
+        write!(out, " xml:lang=\"{}\"", escape(lang))?;
+    }
+    writeln!(out, ">")?;
+
+    let mut voice: Option<&String> = None;
+    let mut cursor = 0;
+    for (i, cue) in t.cues.iter().enumerate() {
+        let start_ms = (cue.start_ms + offset).max(0);
+        let end_ms = (cue.end_ms + offset).max(0);
+        let next_voice = cue.speaker.as_ref().and_then(|s| c.voices.get(s));
+        let indent = if next_voice.is_some() { "    " } else { "  " };
+        if next_voice == voice {
+            write_break(out, start_ms - cursor, c, indent)?;
+        } else {
+            // A speaker change puts the silence between the voice elements.
+            if voice.is_some() {
+                writeln!(out, "  </voice>")?;
+            }
+            write_break(out, start_ms - cursor, c, "  ")?;
+            if let Some(name) = next_voice {
+                writeln!(out, "  <voice name=\"{}\">", escape(name))?;
+            }
+        }
+        voice = next_voice;
+
+        if c.marks {
+            writeln!(
+                out,
+                "{indent}<mark name=\"{}{}\"/>",
+                escape(&c.mark_prefix),
+                i + 1
+            )?;
+        }
+        let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        writeln!(out, "{indent}<{el}>{}</{el}>", escape(&text))?;
+        cursor = cursor.max(end_ms);
+    }
+    if voice.is_some() {
+        writeln!(out, "  </voice>")?;
+    }
+    writeln!(out, "</speak>")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Engines cap a single break (Polly and Google at 10 s), so longer silences
+/// are clamped.
+fn write_break(out: &mut dyn Write, gap_ms: i64, c: &SsmlCfg, indent: &str) -> Result<()> {
+    if gap_ms >= c.min_break_ms && gap_ms > 0 {
+        writeln!(
+            out,
+            "{indent}<break time=\"{}ms\"/>",
+            gap_ms.min(c.max_break_ms)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{
+        speech_marks::parse_timepoints,
+        testutil::{cue, render, spoken, timings},
+        xml::parse_document,
+    };
+
+    #[test]
+    fn breaks_marks_and_escaping() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![
+            cue(500, 1000, "Fish  &\nchips"),
+            cue(1050, 2000, "No break"),
+            cue(30_000, 31_000, "Long pause"),
+        ]);
+        t.meta.language = Some("en-GB".to_string());
+        assert_eq!(
+            render(&Ssml, &t, &cfg),
+            "<speak xml:lang=\"en-GB\">\n\
+             \x20 <break time=\"500ms\"/>\n\
+             \x20 <mark name=\"cue-1\"/>\n\
+             \x20 <s>Fish &amp; chips</s>\n\
+             \x20 <mark name=\"cue-2\"/>\n\
+             \x20 <s>No break</s>\n\
+             \x20 <break time=\"10000ms\"/>\n\
+             \x20 <mark name=\"cue-3\"/>\n\
+             \x20 <s>Long pause</s>\n\
+             </speak>\n"
+        );
+    }
+
+    #[test]
+    fn voices_wrap_mapped_speakers() {
+        let mut cfg = Config::default();
+        cfg.formats.ssml.marks = false;
+        cfg.formats.ssml.cue_element = SsmlCueElement::P;
+        cfg.formats
+            .ssml
+            .voices
+            .insert("Ann".to_string(), "Joanna".to_string());
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "One"),
+            spoken(1000, 2000, "Ann", "Two"),
+            spoken(2500, 3000, "Bob", "Three"),
+        ]);
+        assert_eq!(
+            render(&Ssml, &t, &cfg),
+            "<speak>\n\
+             \x20 <voice name=\"Joanna\">\n\
+             \x20   <p>One</p>\n\
+             \x20   <p>Two</p>\n\
+             \x20 </voice>\n\
+             \x20 <break time=\"500ms\"/>\n\
+             \x20 <p>Three</p>\n\
+             </speak>\n"
+        );
+    }
+
+    #[test]
+    fn marks_map_timepoints_back_to_cues() {
+        let cfg = Config::default();
+        // The last cue ends where its synthesized minimum duration would.
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "Hello <world>"),
+            cue(1000, 2500, "Second"),
+            cue(2500, 3100, "Third"),
+        ]);
+        let ssml = render(&Ssml, &t, &cfg);
+        assert_eq!(parse_document(&ssml).unwrap().name, "speak");
+
+        let path = std::env::temp_dir().join(format!("ssml-roundtrip-{}.ssml", std::process::id()));
+        std::fs::write(&path, &ssml).unwrap();
+        let mut read_cfg = Config::default();
+        read_cfg.formats.speech_marks.ssml_source = Some(path.to_string_lossy().into_owned());
+        let points: Vec<_> = t
+            .cues
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                serde_json::json!({
+                    "markName": format!("cue-{}", i + 1),
+                    "timeSeconds": c.start_ms as f64 / 1000.0
+                })
+            })
+            .collect();
+        let back = parse_timepoints(&serde_json::json!({ "timepoints": points }), &read_cfg);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(timings(&back.unwrap()), timings(&t));
+    }
+}
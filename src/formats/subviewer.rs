@@ -0,0 +1,247 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+use crate::{
+    config::Config,
+    formats::{
+        registry::{CueSink, FormatEntry, FormatReader, FormatWriter},
+        time::parse_time_to_ms,
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct SubViewer;
+
+impl FormatReader for SubViewer {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_subviewer(input)
+    }
+}
+
+impl FormatWriter for SubViewer {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        write_subviewer_header(out, meta)?;
+        Ok(Box::new(SubViewerSink { cfg, out }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("subviewer", &["sub"])
+        .sniff(|raw| {
+            let first = raw
+                .trim_start_matches('\u{feff}')
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty())
+                .unwrap_or("");
+            first.eq_ignore_ascii_case("[INFORMATION]") || is_time_line(first)
+        })
+        .reader(SubViewer)
+        .writer(SubViewer)
+}
+
+pub fn parse_subviewer(input: &str) -> Result<Transcript> {
+    let mut meta = Meta::default();
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut in_info = false;
+    let mut current: Option<Cue> = None;
+
+    for (line_no, raw_line) in input.lines().enumerate() {
+        let line = raw_line.trim().trim_start_matches('\u{feff}');
+
+        if line.eq_ignore_ascii_case("[INFORMATION]") {
+            in_info = true;
+            continue;
+        }
+        if line.eq_ignore_ascii_case("[END INFORMATION]") {
+            in_info = false;
+            continue;
+        }
+        if in_info {
+            apply_info_tag(&mut meta, line);
+            continue;
+        }
+
+        if line.is_empty() {
+            cues.extend(current.take());
+            continue;
+        }
+
+        if current.is_none() {
+            if is_time_line(line) {
+                let (a, b) = line
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("SubViewer line {}: bad time range", line_no + 1))?;
+                current = Some(Cue {
+                    start_ms: parse_time_to_ms(a)?,
+                    end_ms: parse_time_to_ms(b)?,
+                    text: String::new(),
+                    speaker: None,
+                    words: Vec::new(),
+                });
+            } else {
+                // `[SUBTITLE]` and the `[COLF]...` style line carry no text.
+                tracing::debug!(line = line_no + 1, "skipping SubViewer header line");
+            }
+            continue;
+        }
+
+        if let Some(cue) = current.as_mut() {
+            if !cue.text.is_empty() {
+                cue.text.push('\n');
+            }
+            cue.text
+                .push_str(&line.replace("[br]", "\n").replace("[BR]", "\n"));
+        }
+    }
+    cues.extend(current.take());
+
+    Ok(Transcript { cues, meta })
+}
+
+fn apply_info_tag(meta: &mut Meta, line: &str) {
+    let Some(rest) = line.strip_prefix('[') else {
+        return;
+    };
+    let Some((key, value)) = rest.split_once(']') else {
+        return;
+    };
+    let value = value.trim();
+    if value.is_empty() {
+        return;
+    }
+    match key.to_uppercase().as_str() {
+        "TITLE" => meta.title = Some(value.to_string()),
+        other => {
+            meta.tags.insert(other.to_lowercase(), value.to_string());
+        }
+    }
+}
+
+/// `hh:mm:ss.cc,hh:mm:ss.cc` with two-digit hundredths.
+fn is_time_line(line: &str) -> bool {
+    let Some((a, b)) = line.split_once(',') else {
+        return false;
+    };
+    [a, b].iter().all(|t| {
+        let t = t.trim();
+        t.len() == 11
+            && t.split_once('.').is_some_and(|(hms, cc)| {
+                hms.split(':').count() == 3
+                    && cc.len() == 2
+                    && t.chars()
+                        .all(|c| c.is_ascii_digit() || c == ':' || c == '.')
+            })
+    })
+}
+
+fn format_subviewer_timestamp(ms_in: i64) -> String {
+    let ms = ms_in.max(0);
+    let centis = (ms % 1000) / 10;
+    let total_seconds = ms / 1000;
+    let sec = total_seconds % 60;
+    let total_minutes = total_seconds / 60;
+    let min = total_minutes % 60;
+    let hour = total_minutes / 60;
+    format!("{hour:02}:{min:02}:{sec:02}.{centis:02}")
+}
+
+fn write_subviewer_header(out: &mut dyn Write, meta: &Meta) -> Result<()> {
+    writeln!(out, "[INFORMATION]")?;
+    writeln!(out, "[TITLE]{}", meta.title.as_deref().unwrap_or(""))?;
+    for key in ["author", "source", "prg", "filepath"] {
+        let value = meta.tags.get(key).map(String::as_str).unwrap_or("");
+        writeln!(out, "[{}]{value}", key.to_uppercase())?;
+    }
+    writeln!(out, "[DELAY]0")?;
+    writeln!(out, "[CD TRACK]0")?;
+    writeln!(out, "[COMMENT]")?;
+    writeln!(out, "[END INFORMATION]")?;
+    writeln!(out, "[SUBTITLE]")?;
+    writeln!(out, "[COLF]&HFFFFFF,[STYLE]no,[SIZE]18,[FONT]Arial")?;
+    Ok(())
+}
+
+struct SubViewerSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+}
+
+impl CueSink for SubViewerSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        writeln!(
+            self.out,
+            "{},{}",
+            format_subviewer_timestamp(cue.start_ms + offset),
+            format_subviewer_timestamp(cue.end_ms + offset)
+        )?;
+        let text = cue
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("[br]");
+        writeln!(self.out, "{text}")?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, render, timings};
+
+    #[test]
+    fn roundtrip_with_information_block() {
+        let cfg = Config::default();
+        let mut t = Transcript::new(vec![
+            cue(1000, 2500, "One\nTwo"),
+            cue(3_600_000, 3_601_000, "Later"),
+        ]);
+        t.meta.title = Some("Film".to_string());
+        t.meta.tags.insert("author".to_string(), "Ann".to_string());
+
+        let out = render(&SubViewer, &t, &cfg);
+        assert!(out.starts_with("[INFORMATION]\n[TITLE]Film\n[AUTHOR]Ann\n"));
+        assert!(out.contains("\n00:00:01.00,00:00:02.50\nOne[br]Two\n\n"));
+
+        let back = parse_subviewer(&out).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.meta.title.as_deref(), Some("Film"));
+        assert_eq!(
+            back.meta.tags.get("author").map(String::as_str),
+            Some("Ann")
+        );
+    }
+
+    #[test]
+    fn headerless_input_and_multiline_text() {
+        let raw = "00:00:01.00,00:00:02.00\nfirst\nsecond\n\n00:00:03.00,00:00:04.00\nthird";
+        assert_eq!(
+            timings(&parse_subviewer(raw).unwrap()),
+            vec![(1000, 2000, "first\nsecond"), (3000, 4000, "third")]
+        );
+    }
+
+    #[test]
+    fn time_lines() {
+        assert!(is_time_line("00:00:01.00,00:00:02.00"));
+        assert!(!is_time_line("0:00:01.000,0:00:02.000"));
+        assert!(!is_time_line("00:00:01,000 --> 00:00:02,000"));
+        assert!(!is_time_line("hello, world"));
+        assert_eq!(format_subviewer_timestamp(3_723_456), "01:02:03.45");
+    }
+}
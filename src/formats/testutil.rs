@@ -0,0 +1,98 @@
+//! Helpers shared by the format tests.
+
+use flate2::{Crc, read::DeflateDecoder};
+use std::io::Read;
+
+use crate::{
+    config::Config,
+    formats::registry::{FormatReader, FormatWriter},
+    model::{Cue, Transcript, Word},
+};
+
+pub fn cue(start_ms: i64, end_ms: i64, text: &str) -> Cue {
+    Cue {
+        start_ms,
+        end_ms,
+        text: text.to_string(),
+        speaker: None,
+        words: Vec::new(),
+    }
+}
+
+pub fn spoken(start_ms: i64, end_ms: i64, speaker: &str, text: &str) -> Cue {
+    Cue {
+        speaker: Some(speaker.to_string()),
+        ..cue(start_ms, end_ms, text)
+    }
+}
+
+pub fn word(start_ms: i64, end_ms: i64, text: &str) -> Word {
+    Word {
+        start_ms,
+        end_ms,
+        text: text.to_string(),
+        confidence: None,
+    }
+}
+
+pub fn render_bytes(w: &dyn FormatWriter, t: &Transcript, cfg: &Config) -> Vec<u8> {
+    let mut out = Vec::new();
+    w.write(t, cfg, &mut out).unwrap();
+    out
+}
+
+pub fn render(w: &dyn FormatWriter, t: &Transcript, cfg: &Config) -> String {
+    String::from_utf8(render_bytes(w, t, cfg)).unwrap()
+}
+
+/// Reads `input` through the streaming entry point, as the pipeline does.
+pub fn read_stream(r: &dyn FormatReader, input: &[u8], cfg: &Config) -> Transcript {
+    r.read_stream(Box::new(input), cfg)
+        .and_then(|s| s.collect_transcript())
+        .unwrap()
+}
+
+/// Start, end and text of every cue, for compact assertions.
+pub fn timings(t: &Transcript) -> Vec<(i64, i64, &str)> {
+    t.cues
+        .iter()
+        .map(|c| (c.start_ms, c.end_ms, c.text.as_str()))
+        .collect()
+}
+
+/// Name and contents of every entry of a ZIP archive without a comment,
+/// found through the central directory and checked against the local headers
+/// and CRCs.
+pub fn unzip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let u16_at = |i: usize| u16::from_le_bytes([zip[i], zip[i + 1]]) as usize;
+    let u32_at = |i: usize| u32::from_le_bytes(zip[i..i + 4].try_into().unwrap()) as usize;
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(end), 0x0605_4b50, "missing end of central directory");
+    let count = u16_at(end + 10);
+    let mut at = u32_at(end + 16);
+    assert_eq!(at + u32_at(end + 12), end, "central directory size");
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        assert_eq!(u32_at(at), 0x0201_4b50, "central directory entry");
+        let (crc, packed, len) = (u32_at(at + 16), u32_at(at + 20), u32_at(at + 24));
+        let name_len = u16_at(at + 28);
+        let local = u32_at(at + 42);
+        let name = String::from_utf8(zip[at + 46..at + 46 + name_len].to_vec()).unwrap();
+        at += 46 + name_len + u16_at(at + 30) + u16_at(at + 32);
+
+        assert_eq!(u32_at(local), 0x0403_4b50, "local header of {name}");
+        assert_eq!(&zip[local + 30..local + 30 + name_len], name.as_bytes());
+        let data_at = local + 30 + name_len + u16_at(local + 28);
+        let mut data = Vec::new();
+        DeflateDecoder::new(&zip[data_at..data_at + packed])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), len, "size of {name}");
+        let mut sum = Crc::new();
+        sum.update(&data);
+        assert_eq!(sum.sum() as usize, crc, "CRC of {name}");
+        entries.push((name, data));
+    }
+    entries
+}
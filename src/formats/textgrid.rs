@@ -0,0 +1,470 @@
+use anyhow::{Result, anyhow};
+use encoding_rs::{Encoding, UTF_8};
+use std::io::{BufRead, Write};
+
+use crate::{
+    config::Config,
+    formats::{
+        registry::{CollectSink, CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        tracks::SubtitleTrack,
+    },
+    model::{Cue, Meta, Transcript},
+};
+
+pub struct TextGrid;
+
+impl FormatReader for TextGrid {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_textgrid(input, cfg)
+    }
+
+    /// Praat saves UTF-16 when the text needs it, so decode by BOM first.
+    fn read_stream<'a>(
+        &self,
+        mut input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        let mut raw = Vec::new();
+        input.read_to_end(&mut raw)?;
+        Ok(CueStream::from_transcript(parse_textgrid(
+            &decode(&raw),
+            cfg,
+        )?))
+    }
+}
+
+pub fn decode(raw: &[u8]) -> String {
+    let encoding = Encoding::for_bom(raw).map_or(UTF_8, |(e, _)| e);
+    encoding.decode(raw).0.into_owned()
+}
+
+impl FormatWriter for TextGrid {
+    fn begin<'a>(
+        &self,
+        meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(CollectSink::new(meta, out, move |t, out| {
+            write_textgrid(t, cfg, out)
+        })))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("textgrid", &["textgrid"])
+        .config_section("textgrid")
+        .sniff(|raw| raw.contains("ooTextFile") && raw.contains("TextGrid"))
+        .reader(TextGrid)
+        .writer(TextGrid)
+}
+
+/// One interval tier; point tiers carry no durations and are skipped.
+#[derive(Debug, Clone)]
+pub struct Tier {
+    pub name: String,
+    pub intervals: Vec<(i64, i64, String)>,
+}
+
+/// Reads the tier chosen by `[formats.textgrid] tier` or `track`, or all
+/// interval tiers merged with the tier name as speaker.
+pub fn parse_textgrid(input: &str, cfg: &Config) -> Result<Transcript> {
+    let tiers = parse_tiers(input)?;
+    let c = &cfg.formats.textgrid;
+    let selected: Vec<&Tier> = match (&c.tier, c.track) {
+        (Some(name), _) => vec![tiers.iter().find(|t| &t.name == name).ok_or_else(|| {
+            anyhow!(
+                "TextGrid has no interval tier '{name}'; available: {}",
+                tier_names(&tiers)
+            )
+        })?],
+        (None, Some(n)) => vec![
+            tiers
+                .get((n as usize).wrapping_sub(1))
+                .ok_or_else(|| anyhow!("TextGrid has no interval tier {n}; see list-tracks"))?,
+        ],
+        (None, None) => tiers.iter().collect(),
+    };
+    let merge = selected.len() > 1;
+
+    let mut cues: Vec<Cue> = selected
+        .iter()
+        .flat_map(|tier| {
+            tier.intervals
+                .iter()
+                .map(move |(start_ms, end_ms, text)| Cue {
+                    start_ms: *start_ms,
+                    end_ms: *end_ms,
+                    text: text.clone(),
+                    speaker: merge.then(|| tier.name.clone()),
+                    words: Vec::new(),
+                })
+        })
+        .collect();
+    cues.sort_by_key(|c| c.start_ms);
+    Ok(Transcript::new(cues))
+}
+
+/// Interval tiers as tracks for `list-tracks`, numbered for `--track`.
+pub fn list_tiers(input: &str) -> Result<Vec<SubtitleTrack>> {
+    Ok(parse_tiers(input)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, tier)| SubtitleTrack {
+            number: i as u64 + 1,
+            codec: "IntervalTier".to_string(),
+            name: Some(tier.name),
+            text: true,
+            ..SubtitleTrack::default()
+        })
+        .collect())
+}
+
+fn tier_names(tiers: &[Tier]) -> String {
+    tiers
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Flag(String),
+}
+
+/// Praat's text format, long or short: only numbers, quoted strings and
+/// `<flags>` matter, and everything else (`xmin =`, `item [1]:`) is a label.
+fn tokens(input: &str) -> Vec<Token> {
+    let mut out = Vec::new();
+    let mut chars = input.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            while let Some(c) = chars.next() {
+                if c == '"' {
+                    // A doubled quote is an escaped quote.
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                s.push(c);
+            }
+            out.push(Token::Str(s));
+        } else if c == '!' {
+            // Comment to end of line.
+            chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            if word.starts_with('<') && word.ends_with('>') {
+                out.push(Token::Flag(word));
+            } else if let Ok(n) = word.parse() {
+                out.push(Token::Number(n));
+            }
+        }
+    }
+    out
+}
+
+fn parse_tiers(input: &str) -> Result<Vec<Tier>> {
+    let mut toks = tokens(input).into_iter();
+    if next_string(&mut toks, "file type")? != "ooTextFile"
+        || next_string(&mut toks, "object class")? != "TextGrid"
+    {
+        return Err(anyhow!("not a Praat TextGrid text file"));
+    }
+    let ms = |secs: f64| (secs * 1000.0).round() as i64;
+
+    next_number(&mut toks, "xmin")?;
+    next_number(&mut toks, "xmax")?;
+    match toks.next() {
+        Some(Token::Flag(f)) if f == "<exists>" => {}
+        _ => return Ok(Vec::new()),
+    }
+    let size = next_number(&mut toks, "tier count")? as usize;
+
+    let mut tiers = Vec::with_capacity(size);
+    for _ in 0..size {
+        let class = next_string(&mut toks, "tier class")?;
+        let name = next_string(&mut toks, "tier name")?;
+        next_number(&mut toks, "tier xmin")?;
+        next_number(&mut toks, "tier xmax")?;
+        let count = next_number(&mut toks, "interval count")? as usize;
+        let mut intervals = Vec::new();
+        for _ in 0..count {
+            if class == "IntervalTier" {
+                let start = next_number(&mut toks, "interval xmin")?;
+                let end = next_number(&mut toks, "interval xmax")?;
+                let text = next_string(&mut toks, "interval text")?;
+                if !text.trim().is_empty() {
+                    intervals.push((ms(start), ms(end), text.trim().to_string()));
+                }
+            } else {
+                next_number(&mut toks, "point time")?;
+                next_string(&mut toks, "point mark")?;
+            }
+        }
+        if class == "IntervalTier" {
+            tiers.push(Tier { name, intervals });
+        } else {
+            tracing::info!(tier = name.as_str(), "skipping TextGrid point tier");
+        }
+    }
+    Ok(tiers)
+}
+
+fn next_number(toks: &mut impl Iterator<Item = Token>, what: &str) -> Result<f64> {
+    match toks.next() {
+        Some(Token::Number(n)) => Ok(n),
+        other => Err(anyhow!("TextGrid: expected {what}, found {other:?}")),
+    }
+}
+
+fn next_string(toks: &mut impl Iterator<Item = Token>, what: &str) -> Result<String> {
+    match toks.next() {
+        Some(Token::Str(s)) => Ok(s),
+        other => Err(anyhow!("TextGrid: expected {what}, found {other:?}")),
+    }
+}
+
+/// Cues grouped into named tiers, one per speaker when configured.
+fn group_tiers<'c>(cues: &'c [Cue], cfg: &Config) -> Vec<(String, Vec<&'c Cue>)> {
+    let c = &cfg.formats.textgrid;
+    let mut tiers: Vec<(String, Vec<&Cue>)> = Vec::new();
+    for cue in cues {
+        let name = match &cue.speaker {
+            Some(s) if c.tier_per_speaker => s.clone(),
+            _ => c.default_tier.clone(),
+        };
+        match tiers.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, list)) => list.push(cue),
+            None => tiers.push((name, vec![cue])),
+        }
+    }
+    if tiers.is_empty() {
+        tiers.push((c.default_tier.clone(), Vec::new()));
+    }
+    tiers
+}
+
+fn write_textgrid(t: &Transcript, cfg: &Config, out: &mut dyn Write) -> Result<()> {
+    let offset = cfg.policy.timestamp_offset_ms;
+    let shifted: Vec<Cue> = t
+        .cues
+        .iter()
+        .map(|c| Cue {
+            start_ms: (c.start_ms + offset).max(0),
+            end_ms: (c.end_ms + offset).max(0),
+            ..c.clone()
+        })
+        .collect();
+    let xmax = shifted.iter().map(|c| c.end_ms).max().unwrap_or(0);
+    let tiers = group_tiers(&shifted, cfg);
+    let short = cfg.formats.textgrid.short_form;
+
+    writeln!(out, "File type = \"ooTextFile\"")?;
+    writeln!(out, "Object class = \"TextGrid\"")?;
+    writeln!(out)?;
+    if short {
+        writeln!(out, "0\n{}\n<exists>\n{}", secs(xmax), tiers.len())?;
+    } else {
+        writeln!(out, "xmin = 0 \nxmax = {} \ntiers? <exists> ", secs(xmax))?;
+        writeln!(out, "size = {} \nitem []: ", tiers.len())?;
+    }
+
+    for (i, (name, cues)) in tiers.iter().enumerate() {
+        let intervals = tile(cues, xmax);
+        if short {
+            writeln!(out, "\"IntervalTier\"\n{}", quote(name))?;
+            writeln!(out, "0\n{}\n{}", secs(xmax), intervals.len())?;
+        } else {
+            writeln!(out, "    item [{}]:", i + 1)?;
+            writeln!(out, "        class = \"IntervalTier\" ")?;
+            writeln!(out, "        name = {} ", quote(name))?;
+            writeln!(out, "        xmin = 0 \n        xmax = {} ", secs(xmax))?;
+            writeln!(out, "        intervals: size = {} ", intervals.len())?;
+        }
+        for (j, (start, end, text)) in intervals.iter().enumerate() {
+            if short {
+                writeln!(out, "{}\n{}\n{}", secs(*start), secs(*end), quote(text))?;
+            } else {
+                writeln!(out, "        intervals [{}]:", j + 1)?;
+                writeln!(out, "            xmin = {} ", secs(*start))?;
+                writeln!(out, "            xmax = {} ", secs(*end))?;
+                writeln!(out, "            text = {} ", quote(text))?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Praat tiers cover `0..xmax` without gaps or overlaps: empty intervals
+/// fill the gaps and overlapping cues are clipped to the previous end.
+fn tile(cues: &[&Cue], xmax: i64) -> Vec<(i64, i64, String)> {
+    let mut out = Vec::new();
+    let mut cursor = 0;
+    for c in cues {
+        let start = c.start_ms.max(cursor);
+        if c.end_ms <= start {
+            tracing::warn!(start_ms = c.start_ms, "dropping cue that overlaps its tier");
+            continue;
+        }
+        if start > cursor {
+            out.push((cursor, start, String::new()));
+        }
+        out.push((start, c.end_ms, c.text.clone()));
+        cursor = c.end_ms;
+    }
+    if cursor < xmax || out.is_empty() {
+        out.push((cursor, xmax.max(cursor), String::new()));
+    }
+    out
+}
+
+fn secs(ms: i64) -> String {
+    format!("{}", ms as f64 / 1000.0)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, read_stream, render, spoken, timings};
+
+    const SHORT: &str = "File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n\n\
+        0\n3\n<exists>\n2\n\
+        \"TextTier\"\n\"bells\"\n0\n3\n1\n1.5\n\"ding\"\n\
+        \"IntervalTier\"\n\"words\"\n0\n3\n3\n\
+        0\n1\n\"\"\n1\n2.25\n\"say \"\"hi\"\"\"\n2.25\n3\n\"\"\n";
+
+    fn with_tier(tier: Option<&str>, track: Option<u64>) -> Config {
+        let mut cfg = Config::default();
+        cfg.formats.textgrid.tier = tier.map(str::to_string);
+        cfg.formats.textgrid.track = track;
+        cfg
+    }
+
+    #[test]
+    fn long_form_roundtrip_with_tier_per_speaker() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(500, 1500, "Ann", "Hello"),
+            spoken(1000, 2500, "Bob", "Hi \"you\""),
+            spoken(2500, 3000, "Ann", "Bye"),
+        ]);
+        let out = render(&TextGrid, &t, &cfg);
+        assert!(out.contains("size = 2 \nitem []: \n    item [1]:\n"));
+        assert!(out.contains("        name = \"Ann\" \n"));
+        assert!(out.contains("            text = \"Hi \"\"you\"\"\" \n"));
+
+        let back = parse_textgrid(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        let speakers: Vec<_> = back.cues.iter().map(|c| c.speaker.as_deref()).collect();
+        assert_eq!(speakers, vec![Some("Ann"), Some("Bob"), Some("Ann")]);
+    }
+
+    #[test]
+    fn short_form_roundtrip_on_one_tier() {
+        let mut cfg = Config::default();
+        cfg.formats.textgrid.short_form = true;
+        cfg.formats.textgrid.tier_per_speaker = false;
+        let t = Transcript::new(vec![spoken(0, 1000, "Ann", "One"), cue(1500, 2000, "Two")]);
+        let out = render(&TextGrid, &t, &cfg);
+        assert!(out.contains("<exists>\n1\n\"IntervalTier\"\n\"transcript\"\n0\n2\n3\n"));
+        let back = parse_textgrid(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.cues[0].speaker, None);
+    }
+
+    #[test]
+    fn tiles_gaps_and_clips_overlaps() {
+        let c = [
+            cue(1000, 2000, "a"),
+            cue(1500, 1800, "b"),
+            cue(1500, 2500, "c"),
+        ];
+        let refs: Vec<&Cue> = c.iter().collect();
+        assert_eq!(
+            tile(&refs, 3000),
+            vec![
+                (0, 1000, String::new()),
+                (1000, 2000, "a".to_string()),
+                (2000, 2500, "c".to_string()),
+                (2500, 3000, String::new()),
+            ]
+        );
+        assert_eq!(tile(&[], 0), vec![(0, 0, String::new())]);
+    }
+
+    #[test]
+    fn short_form_skips_point_tiers_and_unescapes_quotes() {
+        let t = parse_textgrid(SHORT, &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(1000, 2250, "say \"hi\"")]);
+        assert_eq!(t.cues[0].speaker, None);
+        let tiers = list_tiers(SHORT).unwrap();
+        assert_eq!(tiers.len(), 1);
+        assert_eq!(tiers[0].name.as_deref(), Some("words"));
+    }
+
+    #[test]
+    fn selects_tiers_by_name_or_number() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "a"),
+            spoken(0, 500, "Bob", "b"),
+        ]);
+        let out = render(&TextGrid, &t, &cfg);
+
+        let bob = parse_textgrid(&out, &with_tier(Some("Bob"), None)).unwrap();
+        assert_eq!(timings(&bob), vec![(0, 500, "b")]);
+        assert_eq!(bob.cues[0].speaker, None);
+        let first = parse_textgrid(&out, &with_tier(None, Some(1))).unwrap();
+        assert_eq!(timings(&first), vec![(0, 1000, "a")]);
+
+        let err = parse_textgrid(&out, &with_tier(Some("Cy"), None)).unwrap_err();
+        assert!(err.to_string().contains("available: Ann, Bob"));
+        assert!(parse_textgrid(&out, &with_tier(None, Some(0))).is_err());
+        assert!(parse_textgrid(&out, &with_tier(None, Some(3))).is_err());
+    }
+
+    #[test]
+    fn utf16_input_is_decoded_by_bom() {
+        let mut raw = vec![0xFF, 0xFE];
+        raw.extend(SHORT.encode_utf16().flat_map(u16::to_le_bytes));
+        let t = read_stream(&TextGrid, &raw, &Config::default());
+        assert_eq!(timings(&t), vec![(1000, 2250, "say \"hi\"")]);
+    }
+
+    #[test]
+    fn rejects_other_and_truncated_input() {
+        let cfg = Config::default();
+        assert!(
+            parse_textgrid(
+                "File type = \"ooTextFile\"\nObject class = \"Pitch 1\"\n",
+                &cfg
+            )
+            .is_err()
+        );
+        assert!(parse_textgrid(&SHORT[..SHORT.len() - 20], &cfg).is_err());
+        let empty = "File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n0\n1\n<absent>\n";
+        assert!(parse_textgrid(empty, &cfg).unwrap().cues.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{Result, anyhow};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn format_srt_timestamp(ms: i64) -> String {
     format_timestamp(ms, ',', true)
@@ -8,16 +9,28 @@ pub fn format_vtt_timestamp(ms: i64) -> String {
     format_timestamp(ms, '.', false)
 }
 
+/// YouTube SBV style: unpadded hours, `H:MM:SS.mmm`.
+pub fn format_sbv_timestamp(ms_in: i64) -> String {
+    let ms = ms_in.max(0);
+    let milli = ms % 1000;
+    let total_seconds = ms / 1000;
+    let sec = total_seconds % 60;
+    let total_minutes = total_seconds / 60;
+    let min = total_minutes % 60;
+    let hour = total_minutes / 60;
+    format!("{hour}:{min:02}:{sec:02}.{milli:03}")
+}
+
 fn format_timestamp(ms_in: i64, ms_sep: char, _force_hours: bool) -> String {
     let ms = ms_in.max(0);
 
     let total_seconds = ms / 1000;
-    let milli = (ms % 1000) as i64;
+    let milli = ms % 1000;
 
-    let sec = (total_seconds % 60) as i64;
+    let sec = total_seconds % 60;
     let total_minutes = total_seconds / 60;
-    let min = (total_minutes % 60) as i64;
-    let hour = (total_minutes / 60) as i64;
+    let min = total_minutes % 60;
+    let hour = total_minutes / 60;
 
     format!("{hour:02}:{min:02}:{sec:02}{ms_sep}{milli:03}")
 }
@@ -82,3 +95,22 @@ pub fn parse_time_range_arrow(line: &str) -> Result<(i64, i64)> {
     let end = parse_time_to_ms(b.trim())?;
     Ok((start, end))
 }
+
+/// Current UTC date and time as `(year, month, day, seconds into the day)`.
+pub fn utc_now() -> (i64, i64, i64, i64) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    // Days since 1970-01-01 to a civil date.
+    let z = secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, secs.rem_euclid(86_400))
+}
@@ -0,0 +1,109 @@
+use anyhow::{Result, anyhow};
+
+/// A subtitle track found inside a media container.
+#[derive(Debug, Clone, Default)]
+pub struct SubtitleTrack {
+    /// Number passed to `--track`.
+    pub number: u64,
+    pub codec: String,
+    pub language: Option<String>,
+    pub name: Option<String>,
+    pub default: bool,
+    pub forced: bool,
+    /// Text subtitles can be converted; bitmap (VobSub, PGS) and other
+    /// unsupported codecs cannot.
+    pub text: bool,
+}
+
+/// Text codecs that map onto cues: Matroska codec IDs and MP4 sample entry
+/// types (`text` is QuickTime's tx3g ancestor).
+pub(crate) fn is_text_codec(codec: &str) -> bool {
+    matches!(
+        codec,
+        "S_TEXT/UTF8"
+            | "S_TEXT/ASCII"
+            | "S_TEXT/ASS"
+            | "S_TEXT/SSA"
+            | "S_ASS"
+            | "S_SSA"
+            | "S_TEXT/WEBVTT"
+            | "tx3g"
+            | "text"
+            | "wvtt"
+    )
+}
+
+/// Trims the lines of a cue payload and drops the empty ones.
+pub(crate) fn clean_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_track_list(tracks: &[SubtitleTrack]) -> String {
+    let mut out = String::new();
+    for t in tracks {
+        let mut flags = Vec::new();
+        if t.default {
+            flags.push("default");
+        }
+        if t.forced {
+            flags.push("forced");
+        }
+        if !t.text {
+            flags.push("not convertible");
+        }
+        let mut line = format!(
+            "{:<4} {:<16} {:<6}",
+            t.number,
+            t.codec,
+            t.language.as_deref().unwrap_or("-")
+        );
+        if let Some(name) = &t.name {
+            line.push(' ');
+            line.push_str(name);
+        }
+        if !flags.is_empty() {
+            line.push_str(&format!(" ({})", flags.join(", ")));
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// The requested track, or else the default-flagged text track, or else the
+/// first text track.
+pub fn pick_track(tracks: &[SubtitleTrack], wanted: Option<u64>) -> Result<&SubtitleTrack> {
+    let available = || {
+        let list = format_track_list(tracks);
+        if list.is_empty() {
+            "none".to_string()
+        } else {
+            format!("\n{}", list.trim_end())
+        }
+    };
+
+    let track = match wanted {
+        Some(n) => tracks
+            .iter()
+            .find(|t| t.number == n)
+            .ok_or_else(|| anyhow!("no subtitle track {n}; available: {}", available()))?,
+        None => tracks
+            .iter()
+            .filter(|t| t.text)
+            .find(|t| t.default)
+            .or_else(|| tracks.iter().find(|t| t.text))
+            .ok_or_else(|| anyhow!("no text subtitle track found; available: {}", available()))?,
+    };
+    if !track.text {
+        return Err(anyhow!(
+            "track {} ({}) does not hold text subtitles that can be converted",
+            track.number,
+            track.codec
+        ));
+    }
+    Ok(track)
+}
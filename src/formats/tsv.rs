@@ -1,28 +1,72 @@
 use anyhow::{Result, anyhow};
-use csv::{ReaderBuilder, WriterBuilder};
+use csv::{ReaderBuilder, Writer, WriterBuilder};
+use std::io::{BufRead, Read, Write};
 
 use crate::{
     config::Config,
-    formats::time::{format_vtt_timestamp, parse_time_to_ms},
-    model::{Cue, Transcript},
+    formats::{
+        registry::{CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        time::{format_vtt_timestamp, parse_time_to_ms},
+    },
+    model::{Cue, Meta, Transcript},
 };
 
-pub fn write_tsv(t: &Transcript, cfg: &Config) -> Result<String> {
-    let mut wtr = WriterBuilder::new().delimiter(b'\t').from_writer(vec![]);
+pub struct Tsv;
 
-    let cols = cfg.formats.tsv.columns.clone();
-    wtr.write_record(&cols)?;
+impl FormatReader for Tsv {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_tsv(input, cfg)
+    }
+
+    fn read_stream<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        tsv_stream(input, cfg)
+    }
+}
+
+impl FormatWriter for Tsv {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        let mut wtr = WriterBuilder::new().delimiter(b'\t').from_writer(out);
+        wtr.write_record(&cfg.formats.tsv.columns)?;
+        Ok(Box::new(TsvSink { cfg, wtr }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("tsv", &["tsv"])
+        .config_section("tsv")
+        .reader(Tsv)
+        .writer(Tsv)
+}
 
-    for cue in &t.cues {
+struct TsvSink<'a> {
+    cfg: &'a Config,
+    wtr: Writer<&'a mut dyn Write>,
+}
+
+impl CueSink for TsvSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let cols = &self.cfg.formats.tsv.columns;
         let mut row: Vec<String> = Vec::with_capacity(cols.len());
-        for c in &cols {
-            row.push(value_for_column(c, cue, cfg));
+        for c in cols {
+            row.push(value_for_column(c, cue, self.cfg));
         }
-        wtr.write_record(&row)?;
+        self.wtr.write_record(&row)?;
+        Ok(())
     }
 
-    let data = wtr.into_inner().map_err(|e| anyhow!(e.to_string()))?;
-    Ok(String::from_utf8(data)?)
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.wtr.flush()?;
+        Ok(())
+    }
 }
 
 fn value_for_column(col: &str, cue: &Cue, cfg: &Config) -> String {
@@ -44,9 +88,11 @@ fn fmt_time(ms: i64, cfg: &Config) -> String {
 }
 
 pub fn parse_tsv(input: &str, cfg: &Config) -> Result<Transcript> {
-    let mut rdr = ReaderBuilder::new()
-        .delimiter(b'\t')
-        .from_reader(input.as_bytes());
+    tsv_stream(input.as_bytes(), cfg)?.collect_transcript()
+}
+
+fn tsv_stream<'a>(input: impl Read + 'a, cfg: &'a Config) -> Result<CueStream<'a>> {
+    let mut rdr = ReaderBuilder::new().delimiter(b'\t').from_reader(input);
 
     let headers = rdr.headers()?.clone();
     let start_ix = headers
@@ -63,9 +109,7 @@ pub fn parse_tsv(input: &str, cfg: &Config) -> Result<Transcript> {
         .ok_or_else(|| anyhow!("missing 'text' column"))?;
     let speaker_ix = headers.iter().position(|h| h == "speaker");
 
-    let mut cues = Vec::new();
-
-    for rec in rdr.records() {
+    let cues = rdr.into_records().map(move |rec| {
         let rec = rec?;
         let start_s = rec.get(start_ix).unwrap_or("").trim();
         let end_s = rec.get(end_ix).unwrap_or("").trim();
@@ -79,15 +123,19 @@ pub fn parse_tsv(input: &str, cfg: &Config) -> Result<Transcript> {
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty());
 
-        cues.push(Cue {
+        Ok(Cue {
             start_ms,
             end_ms,
             text,
             speaker,
-        });
-    }
-
-    Ok(Transcript::new(cues))
+            words: Vec::new(),
+        })
+    });
+
+    Ok(CueStream {
+        meta: Meta::default(),
+        cues: Box::new(cues),
+    })
 }
 
 fn parse_tsv_time(s: &str, cfg: &Config) -> Result<i64> {
@@ -100,3 +148,39 @@ fn parse_tsv_time(s: &str, cfg: &Config) -> Result<i64> {
         _ => parse_time_to_ms(s),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, read_stream, render, spoken, timings};
+
+    #[test]
+    fn roundtrip_with_speakers() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(1000, 2000, "Ann", "Hi\tthere"),
+            cue(2500, 3000, "Plain"),
+        ]);
+        let out = render(&Tsv, &t, &cfg);
+        assert!(out.starts_with("start\tend\ttext\tspeaker\n1000\t2000\t"));
+        let back = read_stream(&Tsv, out.as_bytes(), &cfg);
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(back.cues[0].speaker.as_deref(), Some("Ann"));
+        assert_eq!(back.cues[1].speaker, None);
+    }
+
+    #[test]
+    fn seconds_units() {
+        let mut cfg = Config::default();
+        cfg.formats.tsv.time_units = "seconds".to_string();
+        let t = parse_tsv("start\tend\ttext\n1.5\t2.25\tHi\n", &cfg).unwrap();
+        assert_eq!(timings(&t), vec![(1500, 2250, "Hi")]);
+    }
+
+    #[test]
+    fn missing_column_is_an_error() {
+        let cfg = Config::default();
+        let err = parse_tsv("start\ttext\n0\tHi\n", &cfg).unwrap_err();
+        assert!(err.to_string().contains("'end'"));
+    }
+}
@@ -1,83 +1,413 @@
 use anyhow::{Result, anyhow};
+use std::io::{BufRead, Write};
 
 use crate::{
-    config::Config,
-    formats::time::{format_vtt_timestamp, parse_time_range_arrow},
-    model::{Cue, Transcript},
+    config::{Config, TxtMode},
+    formats::{
+        plain::synth_duration_ms,
+        registry::{CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        time::{format_vtt_timestamp, parse_time_range_arrow, parse_time_to_ms},
+    },
+    model::{Cue, Meta, Transcript},
 };
 
-pub fn write_txt(t: &Transcript, cfg: &Config) -> String {
-    let mode = cfg.formats.txt.mode.to_lowercase();
-    let mut out = String::new();
-
-    for cue in &t.cues {
-        if mode == "text_only" {
-            out.push_str(cue.text.trim());
-            out.push('\n');
-        } else {
-            out.push_str(&format!(
-                "[{} --> {}] ",
-                format_vtt_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms),
-                format_vtt_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms),
-            ));
-            out.push_str(cue.text.trim());
-            out.push('\n');
+pub struct Txt;
+
+impl FormatReader for Txt {
+    fn read(&self, input: &str, cfg: &Config) -> Result<Transcript> {
+        parse_txt(input, cfg)
+    }
+
+    fn read_stream<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        let mut state = TxtLineParser::new(cfg);
+        let cues = input
+            .lines()
+            .enumerate()
+            .filter_map(move |(line_no, line)| match line {
+                Ok(line) => state.parse_line(line_no, &line).transpose(),
+                Err(e) => Some(Err(e.into())),
+            });
+        Ok(CueStream {
+            meta: Meta::default(),
+            cues: Box::new(cues),
+        })
+    }
+}
+
+impl FormatWriter for Txt {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        Ok(Box::new(TxtSink {
+            cfg,
+            out,
+            line: String::new(),
+            speaker: None,
+            last_end: 0,
+            last_marker: None,
+            wrote: false,
+        }))
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("txt", &["txt"])
+        .config_section("txt")
+        .reader(Txt)
+        .writer(Txt)
+}
+
+struct TxtSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+    /// Script line or paragraph still being extended by following cues.
+    line: String,
+    speaker: Option<String>,
+    last_end: i64,
+    last_marker: Option<i64>,
+    wrote: bool,
+}
+
+impl TxtSink<'_> {
+    fn flush_line(&mut self) -> Result<()> {
+        if self.line.is_empty() {
+            return Ok(());
+        }
+        // Prose paragraphs are set apart by a blank line.
+        if self.wrote && self.cfg.formats.txt.mode != TxtMode::Speaker {
+            writeln!(self.out)?;
         }
+        writeln!(self.out, "{}", self.line)?;
+        self.line.clear();
+        self.wrote = true;
+        Ok(())
     }
+}
+
+impl CueSink for TxtSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        let c = &self.cfg.formats.txt;
+        let offset = self.cfg.policy.timestamp_offset_ms;
+        match c.mode {
+            TxtMode::TextOnly => writeln!(self.out, "{}", cue.text.trim())?,
+            TxtMode::TimestampRange => writeln!(
+                self.out,
+                "[{} --> {}] {}{}",
+                format_vtt_timestamp(cue.start_ms + offset),
+                format_vtt_timestamp(cue.end_ms + offset),
+                cue.speaker
+                    .as_ref()
+                    .map(|s| format!("{s}: "))
+                    .unwrap_or_default(),
+                cue.text.trim()
+            )?,
+            TxtMode::Speaker | TxtMode::Paragraph | TxtMode::IntervalTimestamps => {
+                let start_ms = (cue.start_ms + offset).max(0);
+                let marker = c.mode == TxtMode::IntervalTimestamps
+                    && self
+                        .last_marker
+                        .is_none_or(|m| start_ms - m >= c.interval_ms);
+                let paused =
+                    c.mode == TxtMode::Paragraph && start_ms - self.last_end >= c.paragraph_gap_ms;
+                if self.line.is_empty() || self.speaker != cue.speaker || marker || paused {
+                    self.flush_line()?;
+                    if marker {
+                        self.last_marker = Some(start_ms);
+                        self.line.push_str(&format!("{} ", hms(start_ms)));
+                    }
+                    if let Some(s) = &cue.speaker {
+                        self.line.push_str(&format!("{s}: "));
+                    }
+                    self.speaker = cue.speaker.clone();
+                } else {
+                    self.line.push(' ');
+                }
+                let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                self.line.push_str(&text);
+                self.last_end = self.last_end.max(cue.end_ms + offset);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush_line()?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
 
-    out
+/// `[hh:mm:ss]`, read back as a cue start.
+fn hms(ms: i64) -> String {
+    let secs = ms / 1000;
+    format!(
+        "[{:02}:{:02}:{:02}]",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
 }
 
 pub fn parse_txt(input: &str, cfg: &Config) -> Result<Transcript> {
+    let mut state = TxtLineParser::new(cfg);
     let mut cues: Vec<Cue> = Vec::new();
-    let mut cursor_ms: i64 = 0;
 
     for (line_no, raw_line) in input.lines().enumerate() {
+        if let Some(cue) = state.parse_line(line_no, raw_line)? {
+            cues.push(cue);
+        }
+    }
+
+    Ok(Transcript::new(cues))
+}
+
+/// Line-at-a-time TXT parsing; the cursor carries synthesized timings forward.
+struct TxtLineParser<'a> {
+    cfg: &'a Config,
+    cursor_ms: i64,
+}
+
+impl<'a> TxtLineParser<'a> {
+    fn new(cfg: &'a Config) -> Self {
+        Self { cfg, cursor_ms: 0 }
+    }
+
+    fn parse_line(&mut self, line_no: usize, raw_line: &str) -> Result<Option<Cue>> {
+        let cfg = self.cfg;
         let line = raw_line.trim();
         if line.is_empty() {
-            continue;
+            return Ok(None);
         }
 
-        if let Some(rest) = line.strip_prefix('[') {
-            if let Some((range, text_part)) = rest.split_once(']') {
-                let (start, end) = parse_time_range_arrow(range.trim())?;
-                let text = text_part.trim().to_string();
-                cues.push(Cue {
+        // `[start --> end] text`, or `[hh:mm:ss] text` with only a start;
+        // other bracketed openings such as `[Music]` are text.
+        let mut start = None;
+        let mut text = line;
+        if let Some(rest) = line.strip_prefix('[')
+            && let Some((stamp, text_part)) = rest.split_once(']')
+        {
+            if stamp.contains("-->") {
+                let (start, end) = parse_time_range_arrow(stamp.trim())?;
+                let (speaker, text) = self.speaker_and_text(text_part.trim());
+                return Ok(Some(Cue {
                     start_ms: start,
                     end_ms: end,
                     text,
-                    speaker: None,
-                });
-                continue;
+                    speaker,
+                    words: Vec::new(),
+                }));
+            }
+            if stamp.contains(':')
+                && let Ok(ms) = parse_time_to_ms(stamp)
+            {
+                start = Some(ms);
+                text = text_part.trim();
             }
         }
 
-        if !cfg.policy.synthesize_timings {
+        if start.is_none() && !cfg.policy.synthesize_timings {
             return Err(anyhow!(
                 "TXT line {} has no timestamps and synthesize_timings=false",
                 line_no + 1
             ));
         }
 
-        let dur = synth_duration_ms(line, cfg);
-        let start = cursor_ms;
-        let end = cursor_ms + dur;
+        let (speaker, text) = self.speaker_and_text(text);
+        let start = start.unwrap_or(self.cursor_ms);
+        let end = start + synth_duration_ms(&text, cfg);
+
+        self.cursor_ms = end + cfg.policy.gap_ms;
 
-        cues.push(Cue {
+        Ok(Some(Cue {
             start_ms: start,
             end_ms: end,
-            text: line.to_string(),
-            speaker: None,
-        });
+            text,
+            speaker,
+            words: Vec::new(),
+        }))
+    }
 
-        cursor_ms = end + cfg.policy.gap_ms;
+    fn speaker_and_text(&self, text: &str) -> (Option<String>, String) {
+        if self.cfg.formats.txt.speaker_labels
+            && let Some((name, rest)) = split_speaker(text)
+        {
+            return (Some(name.to_string()), rest.to_string());
+        }
+        (None, text.to_string())
     }
+}
 
-    Ok(Transcript::new(cues))
+/// `NAME: line`, where the name is a few words starting with a letter, so
+/// clock times and URLs stay text.
+fn split_speaker(text: &str) -> Option<(&str, &str)> {
+    let (name, rest) = text.split_once(':')?;
+    let name = name.trim();
+    let ok = rest.starts_with(char::is_whitespace)
+        && !rest.trim().is_empty()
+        && name.chars().next().is_some_and(char::is_alphabetic)
+        && name.chars().count() <= 32
+        && name.split_whitespace().count() <= 4;
+    ok.then(|| (name, rest.trim()))
 }
 
-fn synth_duration_ms(text: &str, cfg: &Config) -> i64 {
-    let cps = cfg.policy.chars_per_second.max(1.0);
-    let raw = (text.chars().count() as f64 / cps * 1000.0).round() as i64;
-    raw.clamp(cfg.policy.min_duration_ms, cfg.policy.max_duration_ms)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, read_stream, render, spoken, timings};
+
+    fn mode(mode: TxtMode) -> Config {
+        let mut cfg = Config::default();
+        cfg.formats.txt.mode = mode;
+        cfg
+    }
+
+    fn dialogue() -> Transcript {
+        Transcript::new(vec![
+            spoken(0, 1000, "Ann", "One"),
+            spoken(1500, 2000, "Ann", "two\nlines"),
+            spoken(5000, 6000, "Ann", "Three"),
+            spoken(6000, 7000, "Bob", "Four"),
+        ])
+    }
+
+    fn speakers(t: &Transcript) -> Vec<(Option<&str>, &str)> {
+        t.cues
+            .iter()
+            .map(|c| (c.speaker.as_deref(), c.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn timestamp_range_roundtrip() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            cue(1000, 2500, " Hello "),
+            cue(3_600_000, 3_601_000, "Later"),
+        ]);
+        let out = render(&Txt, &t, &cfg);
+        assert_eq!(
+            out,
+            "[00:00:01.000 --> 00:00:02.500] Hello\n[01:00:00.000 --> 01:00:01.000] Later\n"
+        );
+        assert_eq!(
+            timings(&parse_txt(&out, &cfg).unwrap()),
+            vec![(1000, 2500, "Hello"), (3_600_000, 3_601_000, "Later")]
+        );
+        assert_eq!(render(&Txt, &t, &mode(TxtMode::TextOnly)), "Hello\nLater\n");
+    }
+
+    #[test]
+    fn timestamp_range_roundtrip_keeps_speakers() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![
+            spoken(0, 1000, "Ann", "One"),
+            cue(1500, 2000, "Narration"),
+            spoken(2000, 3000, "Bob", "Two"),
+        ]);
+        let out = render(&Txt, &t, &cfg);
+        assert!(out.starts_with("[00:00:00.000 --> 00:00:01.000] Ann: One\n"));
+        let back = parse_txt(&out, &cfg).unwrap();
+        assert_eq!(timings(&back), timings(&t));
+        assert_eq!(speakers(&back), speakers(&t));
+    }
+
+    #[test]
+    fn speaker_script_roundtrip() {
+        let cfg = mode(TxtMode::Speaker);
+        let out = render(&Txt, &dialogue(), &cfg);
+        assert_eq!(out, "Ann: One two lines Three\nBob: Four\n");
+        let back = parse_txt(&out, &cfg).unwrap();
+        assert_eq!(
+            speakers(&back),
+            vec![(Some("Ann"), "One two lines Three"), (Some("Bob"), "Four")]
+        );
+        // Synthesized timings follow on from each other.
+        assert_eq!(
+            back.cues[1].start_ms,
+            back.cues[0].end_ms + cfg.policy.gap_ms
+        );
+    }
+
+    #[test]
+    fn paragraphs_break_on_pauses_and_speakers() {
+        let out = render(&Txt, &dialogue(), &mode(TxtMode::Paragraph));
+        assert_eq!(out, "Ann: One two lines\n\nAnn: Three\n\nBob: Four\n");
+    }
+
+    #[test]
+    fn interval_markers_are_read_back_as_starts() {
+        let mut cfg = mode(TxtMode::IntervalTimestamps);
+        cfg.formats.txt.speaker_labels = false;
+        let t = Transcript::new(vec![
+            cue(0, 1000, "a"),
+            cue(10_000, 11_000, "b"),
+            cue(31_000, 32_000, "c"),
+        ]);
+        let out = render(&Txt, &t, &cfg);
+        assert_eq!(out, "[00:00:00] a b\n\n[00:00:31] c\n");
+        let starts: Vec<_> = parse_txt(&out, &cfg)
+            .unwrap()
+            .cues
+            .iter()
+            .map(|c| (c.start_ms, c.text.clone()))
+            .collect();
+        assert_eq!(
+            starts,
+            vec![(0, "a b".to_string()), (31_000, "c".to_string())]
+        );
+    }
+
+    #[test]
+    fn start_markers_speakers_and_bracketed_text() {
+        let cfg = Config::default();
+        let raw = "[00:00:01] Alice: Hi there\n\
+                   [Music] playing\n\
+                   Meet at 10:30 today\n\
+                   see https://example.com\n\
+                   12: not a name\n\
+                   A very long speaker name here: text\n";
+        let t = parse_txt(raw, &cfg).unwrap();
+        assert_eq!(t.cues[0].start_ms, 1000);
+        assert_eq!(
+            speakers(&t),
+            vec![
+                (Some("Alice"), "Hi there"),
+                (None, "[Music] playing"),
+                (None, "Meet at 10:30 today"),
+                (None, "see https://example.com"),
+                (None, "12: not a name"),
+                (None, "A very long speaker name here: text"),
+            ]
+        );
+        assert!(t.cues[1].start_ms > t.cues[0].end_ms);
+    }
+
+    #[test]
+    fn strict_timing_and_bad_ranges() {
+        let mut cfg = Config::default();
+        cfg.policy.synthesize_timings = false;
+        assert!(parse_txt("[00:00:01] ok\n", &cfg).is_ok());
+        let err = parse_txt("[00:00:01] ok\n\nno time\n", &cfg).unwrap_err();
+        assert!(err.to_string().starts_with("TXT line 3 has no timestamps"));
+        assert!(parse_txt("[soon --> later] x\n", &Config::default()).is_err());
+    }
+
+    #[test]
+    fn streaming_matches_whole_read() {
+        let cfg = Config::default();
+        let raw =
+            "Ann: One\n[00:00:05] Two\r\n\nThree\n[00:00:09.000 --> 00:00:10.000] Bob: Four\n";
+        let whole = parse_txt(raw, &cfg).unwrap();
+        let streamed = read_stream(&Txt, raw.as_bytes(), &cfg);
+        assert_eq!(timings(&streamed), timings(&whole));
+        assert_eq!(speakers(&streamed), speakers(&whole));
+        assert_eq!(whole.cues.len(), 4);
+    }
 }
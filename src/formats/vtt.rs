@@ -1,27 +1,121 @@
-use crate::{config::Config, formats::time::format_vtt_timestamp, model::Transcript};
+use anyhow::{Result, anyhow};
+use std::io::{BufRead, Write};
 use textwrap::wrap;
 
-pub fn write_vtt(t: &Transcript, cfg: &Config) -> String {
-    let mut out = String::new();
-    out.push_str("WEBVTT\n\n");
+use crate::{
+    config::Config,
+    formats::{
+        plain::{plain_to_transcript, text_blocks},
+        registry::{CueSink, CueStream, FormatEntry, FormatReader, FormatWriter},
+        time::format_vtt_timestamp,
+    },
+    model::{Cue, Meta, Transcript},
+};
 
-    for cue in &t.cues {
-        out.push_str(&format!(
-            "{} --> {}\n",
-            format_vtt_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms),
-            format_vtt_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms)
+pub struct Vtt;
+
+impl FormatReader for Vtt {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_vtt(input)
+    }
+
+    fn read_stream<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        _cfg: &'a Config,
+    ) -> Result<CueStream<'a>> {
+        // Each block is parsed on its own behind a synthetic header; the real
+        // header block parses to no cues.
+        tracing::info!("streaming VTT blocks via aspasia");
+        let cues = text_blocks(input).flat_map(|block| match block {
+            Ok(block) => match parse_vtt_quietly(&format!("WEBVTT\n\n{block}")) {
+                Ok((t, _)) => t.cues.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(e) => vec![Err(e)],
+            },
+            Err(e) => vec![Err(e)],
+        });
+        Ok(CueStream {
+            meta: Meta::default(),
+            cues: Box::new(cues),
+        })
+    }
+}
+
+impl FormatWriter for Vtt {
+    fn begin<'a>(
+        &self,
+        _meta: &Meta,
+        cfg: &'a Config,
+        out: &'a mut dyn Write,
+    ) -> Result<Box<dyn CueSink + 'a>> {
+        out.write_all(b"WEBVTT\n\n")?;
+        Ok(Box::new(VttSink { cfg, out }))
+    }
+}
+
+struct VttSink<'a> {
+    cfg: &'a Config,
+    out: &'a mut dyn Write,
+}
+
+impl CueSink for VttSink<'_> {
+    fn push(&mut self, cue: &Cue) -> Result<()> {
+        write_vtt_cue(self.out, cue, self.cfg)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+pub fn entry() -> FormatEntry {
+    FormatEntry::new("vtt", &["vtt"])
+        .config_section("vtt")
+        .sniff(|raw| raw.trim_start_matches('\u{feff}').starts_with("WEBVTT"))
+        .reader(Vtt)
+        .writer(Vtt)
+}
+
+pub fn parse_vtt(raw: &str) -> Result<Transcript> {
+    let (t, how) = parse_vtt_quietly(raw)?;
+    tracing::info!("{how}");
+    Ok(t)
+}
+
+/// `parse_vtt` without the log line, for streams parsed a block at a time.
+fn parse_vtt_quietly(raw: &str) -> Result<(Transcript, &'static str)> {
+    if let Ok(vtt) = raw.parse::<aspasia::WebVttSubtitle>() {
+        let plain = aspasia::PlainSubtitle::from(&vtt);
+        return Ok((plain_to_transcript(&plain), "parsed as VTT via aspasia"));
+    }
+
+    if let Ok(srt) = raw.parse::<aspasia::SubRipSubtitle>() {
+        let plain = aspasia::PlainSubtitle::from(&srt);
+        return Ok((
+            plain_to_transcript(&plain),
+            "parsed as SRT via aspasia (fallback)",
         ));
+    }
 
-        let text = cue_text_for_export(cue.text.as_str(), cfg);
-        for line in wrap(&text, cfg.formats.vtt.wrap_width) {
-            out.push_str(&line);
-            out.push('\n');
-        }
+    Err(anyhow!("failed to parse as SRT or VTT"))
+}
+
+fn write_vtt_cue(out: &mut dyn Write, cue: &Cue, cfg: &Config) -> Result<()> {
+    writeln!(
+        out,
+        "{} --> {}",
+        format_vtt_timestamp(cue.start_ms + cfg.policy.timestamp_offset_ms),
+        format_vtt_timestamp(cue.end_ms + cfg.policy.timestamp_offset_ms)
+    )?;
 
-        out.push('\n');
+    let text = cue_text_for_export(cue.text.as_str(), cfg);
+    for line in wrap(&text, cfg.formats.vtt.wrap_width) {
+        writeln!(out, "{line}")?;
     }
 
-    out
+    writeln!(out)?;
+    Ok(())
 }
 
 fn cue_text_for_export(text: &str, cfg: &Config) -> String {
@@ -52,3 +146,26 @@ fn normalize_ws(s: &str) -> String {
     }
     out.trim().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::{cue, read_stream, render, timings};
+
+    #[test]
+    fn roundtrip() {
+        let cfg = Config::default();
+        let t = Transcript::new(vec![cue(1000, 2500, "Hello"), cue(61_000, 62_000, "Again")]);
+        let out = render(&Vtt, &t, &cfg);
+        assert!(out.starts_with("WEBVTT\n\n"));
+        assert_eq!(timings(&parse_vtt(&out).unwrap()), timings(&t));
+    }
+
+    #[test]
+    fn streaming_skips_header_block() {
+        let cfg = Config::default();
+        let raw = "WEBVTT - title\nKind: captions\n\n00:00:01.000 --> 00:00:02.000\nOne\n\nid\n00:00:03.000 --> 00:00:04.000\nTwo\n";
+        let t = read_stream(&Vtt, raw.as_bytes(), &cfg);
+        assert_eq!(timings(&t), vec![(1000, 2000, "One"), (3000, 4000, "Two")]);
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use crate::{
+    formats::{asr::number, time::parse_time_to_ms},
+    model::{Cue, Meta, Transcript, Word},
+};
+
+/// whisper.cpp `-oj`/`-ojf` output: a `transcription` array of segments.
+pub fn looks_like(v: &Value) -> bool {
+    v.get("transcription").is_some_and(Value::is_array)
+}
+
+pub fn parse(v: &Value) -> Result<Transcript> {
+    let segments = v["transcription"]
+        .as_array()
+        .ok_or_else(|| anyhow!("whisper.cpp: transcription must be an array"))?;
+    let mut cues = Vec::with_capacity(segments.len());
+
+    for (i, seg) in segments.iter().enumerate() {
+        let (start_ms, end_ms) =
+            span(seg).ok_or_else(|| anyhow!("whisper.cpp: segment {i} has no timing"))?;
+        let words = match seg.get("tokens").and_then(Value::as_array) {
+            Some(tokens) => tokens_to_words(tokens),
+            None => Vec::new(),
+        };
+        cues.push(Cue {
+            start_ms,
+            end_ms,
+            text: seg
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+            // Only set by `--diarize` on stereo input.
+            speaker: seg
+                .get("speaker")
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty() && *s != "?")
+                .map(|s| format!("Speaker {s}")),
+            words,
+        });
+    }
+
+    let language = v
+        .pointer("/result/language")
+        .or_else(|| v.pointer("/params/language"))
+        .and_then(Value::as_str)
+        .filter(|l| *l != "auto")
+        .map(str::to_string);
+    let mut meta = Meta {
+        language,
+        ..Meta::default()
+    };
+    if let Some(model) = v.pointer("/model/type").and_then(Value::as_str) {
+        meta.tags.insert("model".to_string(), model.to_string());
+    }
+
+    Ok(Transcript { cues, meta })
+}
+
+/// `offsets` are milliseconds; `timestamps` are `HH:MM:SS,mmm` strings.
+fn span(v: &Value) -> Option<(i64, i64)> {
+    let from_offsets = || {
+        let o = v.get("offsets")?;
+        Some((number(o.get("from")?)? as i64, number(o.get("to")?)? as i64))
+    };
+    let from_timestamps = || {
+        let t = v.get("timestamps")?;
+        Some((
+            parse_time_to_ms(t.get("from")?.as_str()?).ok()?,
+            parse_time_to_ms(t.get("to")?.as_str()?).ok()?,
+        ))
+    };
+    from_offsets().or_else(from_timestamps)
+}
+
+/// Joins sub-word tokens into words: a leading space starts a new word and
+/// special tokens such as `[_BEG_]` are dropped. Confidence is the mean token
+/// probability.
+fn tokens_to_words(tokens: &[Value]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut probs: Vec<f64> = Vec::new();
+    let close = |words: &mut Vec<Word>, probs: &mut Vec<f64>| {
+        if let Some(w) = words.last_mut()
+            && !probs.is_empty()
+        {
+            w.confidence = Some(probs.iter().sum::<f64>() / probs.len() as f64);
+        }
+        probs.clear();
+    };
+
+    for tok in tokens {
+        let Some(text) = tok.get("text").and_then(Value::as_str) else {
+            continue;
+        };
+        if text.starts_with("[_") && text.ends_with(']') {
+            continue;
+        }
+        let Some((start_ms, end_ms)) = span(tok) else {
+            continue;
+        };
+        if text.starts_with(' ') || words.is_empty() {
+            close(&mut words, &mut probs);
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            words.push(Word {
+                start_ms,
+                end_ms,
+                text: text.to_string(),
+                confidence: None,
+            });
+        } else if let Some(w) = words.last_mut() {
+            w.text.push_str(text);
+            w.end_ms = w.end_ms.max(end_ms);
+        }
+        if let Some(p) = tok.get("p").and_then(number) {
+            probs.push(p);
+        }
+    }
+    close(&mut words, &mut probs);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        formats::{json::parse_json, testutil::timings},
+    };
+    use serde_json::json;
+
+    fn token(text: &str, from: i64, to: i64, p: f64) -> Value {
+        json!({"text": text, "offsets": {"from": from, "to": to}, "p": p})
+    }
+
+    #[test]
+    fn segments_tokens_and_speakers() {
+        let v = json!({
+            "model": {"type": "base"},
+            "params": {"language": "auto"},
+            "result": {"language": "de"},
+            "transcription": [{
+                "timestamps": {"from": "00:00:00,000", "to": "00:00:01,500"},
+                "offsets": {"from": 0, "to": 1500},
+                "text": " Hallo Welt",
+                "speaker": "0",
+                "tokens": [
+                    token("[_BEG_]", 0, 0, 1.0),
+                    token(" Hal", 0, 300, 0.8),
+                    token("lo", 300, 600, 0.6),
+                    token(" Welt", 700, 1500, 0.9)
+                ]
+            }]
+        });
+        let t = parse_json(&v.to_string(), &Config::default()).unwrap();
+        assert_eq!(timings(&t), vec![(0, 1500, "Hallo Welt")]);
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("Speaker 0"));
+        let words: Vec<_> = t.cues[0]
+            .words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str(), w.confidence))
+            .collect();
+        assert_eq!(
+            words,
+            vec![(0, 600, "Hallo", Some(0.7)), (700, 1500, "Welt", Some(0.9))]
+        );
+        assert_eq!(t.meta.language.as_deref(), Some("de"));
+        assert_eq!(t.meta.tags.get("model").map(String::as_str), Some("base"));
+    }
+
+    #[test]
+    fn timestamps_without_offsets() {
+        let v = json!({
+            "params": {"language": "auto"},
+            "transcription": [{
+                "timestamps": {"from": "00:00:01,000", "to": "00:00:02,250"},
+                "text": "Hi",
+                "speaker": "?"
+            }]
+        });
+        let t = parse(&v).unwrap();
+        assert_eq!(timings(&t), vec![(1000, 2250, "Hi")]);
+        assert_eq!(t.cues[0].speaker, None);
+        assert_eq!(t.meta.language, None);
+    }
+
+    #[test]
+    fn untimed_segment_is_an_error() {
+        assert!(parse(&json!({"transcription": [{"text": "Hi"}]})).is_err());
+    }
+
+    #[test]
+    fn whisperx_segments_keep_speakers_and_words() {
+        let v = json!({"language": "en", "segments": [{
+            "start": 0.5, "end": 2.0, "text": " Hello 1984", "speaker": "SPEAKER_00",
+            "words": [
+                {"word": "Hello", "start": 0.5, "end": 0.9, "score": 0.95},
+                {"word": "1984"}
+            ]
+        }]});
+        let t = parse_json(&v.to_string(), &Config::default()).unwrap();
+        assert_eq!(t.cues[0].speaker.as_deref(), Some("SPEAKER_00"));
+        let words: Vec<_> = t.cues[0]
+            .words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str(), w.confidence))
+            .collect();
+        // Unaligned words (numbers, symbols) inherit the previous end.
+        assert_eq!(
+            words,
+            vec![(500, 900, "Hello", Some(0.95)), (900, 900, "1984", None)]
+        );
+    }
+}
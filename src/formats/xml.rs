@@ -0,0 +1,151 @@
+use anyhow::{Result, anyhow};
+use quick_xml::{Reader, XmlVersion, escape::unescape, events::Event};
+
+/// Minimal element tree for the XML-based formats; namespaces are dropped and
+/// only local names are kept.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match c {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements()
+            .filter(move |e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Depth-first search for every descendant element with the given name.
+    pub fn descendants_named<'a>(&'a self, name: &str, out: &mut Vec<&'a Element>) {
+        for e in self.elements() {
+            if e.name.eq_ignore_ascii_case(name) {
+                out.push(e);
+            }
+            e.descendants_named(name, out);
+        }
+    }
+
+    /// Concatenated text of this element and all descendants.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        collect_text(self, &mut out);
+        out
+    }
+}
+
+fn collect_text(e: &Element, out: &mut String) {
+    for c in &e.children {
+        match c {
+            Node::Text(t) => out.push_str(t),
+            Node::Element(child) => collect_text(child, out),
+        }
+    }
+}
+
+pub fn parse_document(input: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(input.trim_start_matches('\u{feff}'));
+    let mut stack: Vec<Element> = vec![Element::default()];
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(s) => stack.push(start_element(&s)?),
+            Event::Empty(s) => {
+                let el = start_element(&s)?;
+                push_child(&mut stack, Node::Element(el))?;
+            }
+            Event::End(_) => {
+                let el = stack
+                    .pop()
+                    .filter(|_| !stack.is_empty())
+                    .ok_or_else(|| anyhow!("unbalanced XML end tag"))?;
+                push_child(&mut stack, Node::Element(el))?;
+            }
+            Event::Text(t) => push_text(&mut stack, &t.xml10_content())?,
+            Event::CData(t) => push_text(&mut stack, &t)?,
+            Event::GeneralRef(r) => {
+                let entity = format!("&{};", &*r);
+                let resolved =
+                    unescape(&entity).map_err(|e| anyhow!("bad XML entity '{entity}': {e}"))?;
+                push_text(&mut stack, &resolved)?;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(anyhow!("unexpected end of XML document"));
+    }
+    let doc = stack.pop().unwrap_or_default();
+    doc.elements()
+        .next()
+        .cloned()
+        .ok_or_else(|| anyhow!("XML document has no root element"))
+}
+
+fn start_element(s: &quick_xml::events::BytesStart<'_>) -> Result<Element> {
+    let name = s.local_name().as_ref().to_string();
+    let mut attrs = Vec::new();
+    for a in s.attributes() {
+        let a = a?;
+        let key = a.key.local_name().as_ref().to_string();
+        let value = a.normalized_value(XmlVersion::Implicit1_0)?.into_owned();
+        attrs.push((key, value));
+    }
+    Ok(Element {
+        name,
+        attrs,
+        children: Vec::new(),
+    })
+}
+
+fn push_child(stack: &mut [Element], node: Node) -> Result<()> {
+    stack
+        .last_mut()
+        .ok_or_else(|| anyhow!("XML content outside of root element"))?
+        .children
+        .push(node);
+    Ok(())
+}
+
+fn push_text(stack: &mut [Element], text: &str) -> Result<()> {
+    let parent = stack
+        .last_mut()
+        .ok_or_else(|| anyhow!("XML content outside of root element"))?;
+    if let Some(Node::Text(prev)) = parent.children.last_mut() {
+        prev.push_str(text);
+    } else {
+        parent.children.push(Node::Text(text.to_string()));
+    }
+    Ok(())
+}
+
+/// Escapes text for use in element content or attribute values.
+pub fn escape(s: &str) -> String {
+    quick_xml::escape::escape(s).into_owned()
+}
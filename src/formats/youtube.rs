@@ -0,0 +1,376 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    formats::{
+        registry::{FormatEntry, FormatReader},
+        xml,
+    },
+    model::{Cue, Transcript, Word},
+};
+
+pub struct Json3;
+
+impl FormatReader for Json3 {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_json3(input)
+    }
+}
+
+pub struct Srv3;
+
+impl FormatReader for Srv3 {
+    fn read(&self, input: &str, _cfg: &Config) -> Result<Transcript> {
+        parse_srv3(input)
+    }
+}
+
+pub fn json3_entry() -> FormatEntry {
+    FormatEntry::new("json3", &["json3"])
+        .sniff(|raw| {
+            raw.trim_start().starts_with('{')
+                && raw.contains("\"events\"")
+                && (raw.contains("\"wireMagic\"") || raw.contains("\"tStartMs\""))
+        })
+        .reader(Json3)
+}
+
+pub fn srv3_entry() -> FormatEntry {
+    FormatEntry::new("srv3", &["srv3"])
+        .sniff(|raw| raw.contains("<timedtext"))
+        .reader(Srv3)
+}
+
+/// One timedtext event before rolling-caption cleanup.
+struct RawEvent {
+    start_ms: i64,
+    dur_ms: i64,
+    append: bool,
+    /// `(offset from event start, text)` per segment.
+    segs: Vec<(i64, String)>,
+}
+
+pub fn parse_json3(input: &str) -> Result<Transcript> {
+    let v: Value = serde_json::from_str(input)?;
+    let events = v
+        .get("events")
+        .and_then(|e| e.as_array())
+        .ok_or_else(|| anyhow!("json3: missing 'events' array"))?;
+
+    let mut raw = Vec::with_capacity(events.len());
+    for ev in events {
+        let Some(segs) = ev.get("segs").and_then(|s| s.as_array()) else {
+            continue;
+        };
+        let start_ms = ev
+            .get("tStartMs")
+            .and_then(|x| x.as_i64())
+            .ok_or_else(|| anyhow!("json3: event missing 'tStartMs'"))?;
+        let dur_ms = ev.get("dDurationMs").and_then(|x| x.as_i64()).unwrap_or(0);
+        let append = ev.get("aAppend").and_then(|x| x.as_i64()).unwrap_or(0) != 0;
+
+        let segs = segs
+            .iter()
+            .map(|s| {
+                let off = s.get("tOffsetMs").and_then(|x| x.as_i64()).unwrap_or(0);
+                let text = s.get("utf8").and_then(|x| x.as_str()).unwrap_or("");
+                (off, text.to_string())
+            })
+            .collect();
+
+        raw.push(RawEvent {
+            start_ms,
+            dur_ms,
+            append,
+            segs,
+        });
+    }
+
+    Ok(Transcript::new(collapse_rolling(events_to_cues(raw))))
+}
+
+pub fn parse_srv3(input: &str) -> Result<Transcript> {
+    let root = xml::parse_document(input)?;
+    if !root.name.eq_ignore_ascii_case("timedtext") {
+        return Err(anyhow!(
+            "srv3: root element is <{}>, not <timedtext>",
+            root.name
+        ));
+    }
+    let body = root.child("body").unwrap_or(&root);
+
+    let mut raw = Vec::new();
+    for p in body.children_named("p") {
+        let start_ms =
+            parse_ms_attr(p, "t")?.ok_or_else(|| anyhow!("srv3: <p> missing 't' attribute"))?;
+        let dur_ms = parse_ms_attr(p, "d")?.unwrap_or(0);
+        let append = p.attr("a").is_some_and(|a| a != "0");
+
+        let mut segs = Vec::new();
+        for child in &p.children {
+            match child {
+                xml::Node::Text(t) => segs.push((0, t.clone())),
+                xml::Node::Element(s) if s.name.eq_ignore_ascii_case("s") => {
+                    segs.push((parse_ms_attr(s, "t")?.unwrap_or(0), s.text()));
+                }
+                xml::Node::Element(other) => segs.push((0, other.text())),
+            }
+        }
+
+        raw.push(RawEvent {
+            start_ms,
+            dur_ms,
+            append,
+            segs,
+        });
+    }
+
+    Ok(Transcript::new(collapse_rolling(events_to_cues(raw))))
+}
+
+fn parse_ms_attr(e: &xml::Element, name: &str) -> Result<Option<i64>> {
+    e.attr(name)
+        .map(|v| {
+            v.trim()
+                .parse::<i64>()
+                .map_err(|_| anyhow!("bad '{name}' value on <{}>: '{v}'", e.name))
+        })
+        .transpose()
+}
+
+fn events_to_cues(events: Vec<RawEvent>) -> Vec<Cue> {
+    let mut cues = Vec::with_capacity(events.len());
+
+    for ev in events {
+        let text: String = ev.segs.iter().map(|(_, t)| t.as_str()).collect();
+        // Appended events only carry the line break of a rolling window.
+        if ev.append && text.trim().is_empty() {
+            continue;
+        }
+        let text = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.is_empty() {
+            continue;
+        }
+
+        let end_ms = ev.start_ms + ev.dur_ms;
+        let mut words = Vec::new();
+        for (i, (off, seg)) in ev.segs.iter().enumerate() {
+            let seg = seg.trim();
+            if seg.is_empty() {
+                continue;
+            }
+            let start = ev.start_ms + off;
+            let end = ev
+                .segs
+                .get(i + 1)
+                .map(|(next, _)| ev.start_ms + next)
+                .unwrap_or(end_ms)
+                .max(start);
+            words.push(Word {
+                start_ms: start,
+                end_ms: end,
+                text: seg.to_string(),
+                confidence: None,
+            });
+        }
+        // Plain captions come as one segment; per-word timing only means
+        // something when the event was split.
+        if words.len() < 2 {
+            words.clear();
+        }
+
+        cues.push(Cue {
+            start_ms: ev.start_ms,
+            end_ms,
+            text,
+            speaker: None,
+            words,
+        });
+    }
+
+    cues
+}
+
+/// Rolling captions re-show the previous line while the next one is typed in,
+/// so consecutive events overlap in time and repeat each other's words. Drop
+/// the repeated lead-in words and clip every cue at the start of the next.
+fn collapse_rolling(cues: Vec<Cue>) -> Vec<Cue> {
+    let mut out: Vec<Cue> = Vec::with_capacity(cues.len());
+
+    for mut cue in cues {
+        let Some(prev) = out.last_mut() else {
+            out.push(cue);
+            continue;
+        };
+
+        if cue.start_ms < prev.end_ms {
+            let prev_tokens: Vec<&str> = prev.text.split_whitespace().collect();
+            let cur_tokens: Vec<&str> = cue.text.split_whitespace().collect();
+            let k = overlap_len(&prev_tokens, &cur_tokens);
+
+            if k == cur_tokens.len() {
+                prev.end_ms = prev.end_ms.max(cue.end_ms);
+                continue;
+            }
+
+            if k > 0 {
+                drop_leading_word_tokens(&mut cue.words, k);
+                cue.text = strip_leading_tokens(&cue.text, k);
+            }
+
+            let new_start = cue
+                .words
+                .first()
+                .map(|w| w.start_ms)
+                .unwrap_or(cue.start_ms)
+                .max(prev.start_ms + 1);
+            cue.words.retain(|w| w.start_ms >= new_start);
+            cue.start_ms = new_start;
+            prev.end_ms = prev.end_ms.min(cue.start_ms);
+            if let Some(last) = prev.words.last_mut() {
+                last.end_ms = last.end_ms.min(prev.end_ms);
+            }
+        }
+
+        out.push(cue);
+    }
+
+    out
+}
+
+/// Longest `k` where the last `k` tokens of `prev` equal the first `k` of `cur`.
+fn overlap_len(prev: &[&str], cur: &[&str]) -> usize {
+    let max = prev.len().min(cur.len());
+    (1..=max)
+        .rev()
+        .find(|&k| prev[prev.len() - k..] == cur[..k])
+        .unwrap_or(0)
+}
+
+/// Drops the words holding the first `k` tokens of a cue; a segment that
+/// straddles the boundary keeps only its remaining tokens.
+fn drop_leading_word_tokens(words: &mut Vec<Word>, k: usize) {
+    let mut left = k;
+    while left > 0 && !words.is_empty() {
+        let n = words[0].text.split_whitespace().count();
+        if n <= left {
+            words.remove(0);
+            left -= n;
+        } else {
+            words[0].text = strip_leading_tokens(&words[0].text, left);
+            left = 0;
+        }
+    }
+}
+
+fn strip_leading_tokens(text: &str, k: usize) -> String {
+    let mut rest = text;
+    for _ in 0..k {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = &rest[end..];
+    }
+    rest.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::timings;
+
+    #[test]
+    fn json3_events_with_word_segments() {
+        let raw = r#"{"wireMagic":"pb3","events":[
+            {"tStartMs":0,"dDurationMs":900,"id":1},
+            {"tStartMs":1000,"dDurationMs":2000,"segs":[{"utf8":"hello"},{"utf8":" world","tOffsetMs":600}]},
+            {"tStartMs":3000,"dDurationMs":1000,"segs":[{"utf8":"bye"}]}
+        ]}"#;
+        let t = parse_json3(raw).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(1000, 3000, "hello world"), (3000, 4000, "bye")]
+        );
+        let words: Vec<_> = t.cues[0]
+            .words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str()))
+            .collect();
+        assert_eq!(words, vec![(1000, 1600, "hello"), (1600, 3000, "world")]);
+        assert!(t.cues[1].words.is_empty());
+    }
+
+    #[test]
+    fn json3_rolling_captions_are_collapsed() {
+        let raw = r#"{"events":[
+            {"tStartMs":0,"dDurationMs":4000,"segs":[{"utf8":"one"},{"utf8":" two","tOffsetMs":500}]},
+            {"tStartMs":2000,"dDurationMs":10,"aAppend":1,"segs":[{"utf8":"\n"}]},
+            {"tStartMs":2000,"dDurationMs":4000,"segs":[{"utf8":"two"},{"utf8":" three","tOffsetMs":1000}]}
+        ]}"#;
+        assert_eq!(
+            timings(&parse_json3(raw).unwrap()),
+            vec![(0, 3000, "one two"), (3000, 6000, "three")]
+        );
+    }
+
+    #[test]
+    fn rolling_overlap_drops_words_when_segments_hold_several_tokens() {
+        let raw = r#"{"events":[
+            {"tStartMs":0,"dDurationMs":4000,"segs":[{"utf8":"one"},{"utf8":" two","tOffsetMs":500}]},
+            {"tStartMs":2000,"dDurationMs":4000,"segs":[{"utf8":"two three"},{"utf8":" four","tOffsetMs":1000}]}
+        ]}"#;
+        let t = parse_json3(raw).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(0, 2000, "one two"), (2000, 6000, "three four")]
+        );
+        let words: Vec<_> = t.cues[1]
+            .words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms, w.text.as_str()))
+            .collect();
+        assert_eq!(words, vec![(2000, 3000, "three"), (3000, 6000, "four")]);
+    }
+
+    #[test]
+    fn json3_missing_events_is_an_error() {
+        assert!(parse_json3(r#"{"tStartMs":0}"#).is_err());
+    }
+
+    #[test]
+    fn srv3_paragraphs_and_segments() {
+        let raw = r#"<?xml version="1.0" encoding="utf-8"?>
+<timedtext format="3"><body>
+<p t="500" d="1500">Plain &amp; simple</p>
+<p t="2000" d="2000"><s>split</s><s t="800"> words</s></p>
+</body></timedtext>"#;
+        let t = parse_srv3(raw).unwrap();
+        assert_eq!(
+            timings(&t),
+            vec![(500, 2000, "Plain & simple"), (2000, 4000, "split words")]
+        );
+        assert_eq!(t.cues[1].words[1].start_ms, 2800);
+    }
+
+    #[test]
+    fn srv3_rejects_other_xml() {
+        let err = parse_srv3("<tt><body/></tt>").unwrap_err().to_string();
+        assert!(err.contains("not <timedtext>"));
+        assert!(parse_srv3(r#"<timedtext><body><p t="x">a</p></body></timedtext>"#).is_err());
+    }
+
+    #[test]
+    fn sniffs() {
+        let json3 = json3_entry().sniff.unwrap();
+        assert!(json3(r#"{"wireMagic":"pb3","events":[]}"#));
+        assert!(!json3(r#"{"events":[{"start":0}]}"#));
+        let srv3 = srv3_entry().sniff.unwrap();
+        assert!(srv3("<?xml version=\"1.0\"?><timedtext format=\"3\">"));
+        assert!(!srv3("<tt xmlns=\"http://www.w3.org/ns/ttml\">"));
+    }
+}
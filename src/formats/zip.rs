@@ -0,0 +1,136 @@
+use anyhow::{Result, anyhow};
+use flate2::{Compression, Crc, write::DeflateEncoder};
+use std::io::Write;
+
+use crate::formats::time::utc_now;
+
+/// Minimal ZIP archive writer for the OOXML-based outputs: deflated entries
+/// written in order, without ZIP64, to a stream that need not be seekable.
+pub struct ZipWriter<'a> {
+    out: &'a mut dyn Write,
+    offset: u32,
+    central: Vec<u8>,
+    entries: u16,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+impl<'a> ZipWriter<'a> {
+    pub fn new(out: &'a mut dyn Write) -> Self {
+        let (y, mo, d, secs) = utc_now();
+        Self {
+            out,
+            offset: 0,
+            central: Vec::new(),
+            entries: 0,
+            dos_time: ((secs / 3600) << 11 | (secs / 60 % 60) << 5 | (secs % 60 / 2)) as u16,
+            dos_date: (((y - 1980).max(0)) << 9 | mo << 5 | d) as u16,
+        }
+    }
+
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let mut crc = Crc::new();
+        crc.update(data);
+        let mut enc = DeflateEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(data)?;
+        let packed = enc.finish()?;
+        let (packed_len, len) = (size(packed.len())?, size(data.len())?);
+        let name_len = u16::try_from(name.len()).map_err(|_| anyhow!("zip: name too long"))?;
+
+        // Fields shared by the local header and the central directory entry,
+        // from "version needed" through the extra field length.
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&8u16.to_le_bytes());
+        common.extend_from_slice(&self.dos_time.to_le_bytes());
+        common.extend_from_slice(&self.dos_date.to_le_bytes());
+        common.extend_from_slice(&crc.sum().to_le_bytes());
+        common.extend_from_slice(&packed_len.to_le_bytes());
+        common.extend_from_slice(&len.to_le_bytes());
+        common.extend_from_slice(&name_len.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        self.out.write_all(&0x0403_4b50u32.to_le_bytes())?;
+        self.out.write_all(&common)?;
+        self.out.write_all(name.as_bytes())?;
+        self.out.write_all(&packed)?;
+
+        self.central
+            .extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        self.central.extend_from_slice(&common);
+        // Comment length, disk number, internal and external attributes.
+        self.central.extend_from_slice(&[0; 10]);
+        self.central.extend_from_slice(&self.offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+
+        self.offset = self
+            .offset
+            .checked_add(30 + u32::from(name_len) + packed_len)
+            .ok_or_else(|| anyhow!("zip: archive too large"))?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        self.out.write_all(&self.central)?;
+        self.out.write_all(&0x0605_4b50u32.to_le_bytes())?;
+        self.out.write_all(&[0; 4])?;
+        self.out.write_all(&self.entries.to_le_bytes())?;
+        self.out.write_all(&self.entries.to_le_bytes())?;
+        self.out
+            .write_all(&size(self.central.len())?.to_le_bytes())?;
+        self.out.write_all(&self.offset.to_le_bytes())?;
+        self.out.write_all(&0u16.to_le_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+fn size(n: usize) -> Result<u32> {
+    u32::try_from(n).map_err(|_| anyhow!("zip: entry too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::testutil::unzip;
+
+    #[test]
+    fn entries_roundtrip_in_order() {
+        let big = "transcript ".repeat(1000);
+        let mut out = Vec::new();
+        let mut zip = ZipWriter::new(&mut out);
+        zip.add("a.txt", b"hello").unwrap();
+        zip.add("dir/empty", b"").unwrap();
+        zip.add("big.xml", big.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        // Repetitive text is actually deflated.
+        assert!(out.len() < big.len() / 4);
+        let entries = unzip(&out);
+        let names: Vec<_> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["a.txt", "dir/empty", "big.xml"]);
+        assert_eq!(entries[0].1, b"hello");
+        assert!(entries[1].1.is_empty());
+        assert_eq!(entries[2].1, big.as_bytes());
+    }
+
+    #[test]
+    fn empty_archive() {
+        let mut out = Vec::new();
+        ZipWriter::new(&mut out).finish().unwrap();
+        assert_eq!(out.len(), 22);
+        assert!(unzip(&out).is_empty());
+    }
+
+    #[test]
+    fn dos_timestamp_fields_are_in_range() {
+        let mut out = Vec::new();
+        let zip = ZipWriter::new(&mut out);
+        let (time, date) = (zip.dos_time, zip.dos_date);
+        assert!(time >> 11 < 24 && (time >> 5) & 0x3F < 60 && time & 0x1F < 30);
+        assert!((1..=12).contains(&((date >> 5) & 0xF)) && date & 0x1F >= 1);
+    }
+}
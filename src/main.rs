@@ -1,11 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-mod cli;
-mod config;
-mod formats;
-mod model;
-mod pipeline;
+use sub_convtr::{cli, config, formats::registry::Registry, pipeline};
 
 fn main() -> Result<()> {
     let args = cli::Args::parse();
@@ -22,5 +18,13 @@ fn main() -> Result<()> {
             print!("{s}");
             Ok(())
         }
+        cli::Command::ListFormats => {
+            print!("{}", pipeline::list_formats(&Registry::builtin()));
+            Ok(())
+        }
+        cli::Command::ListTracks(cmd) => {
+            print!("{}", pipeline::list_tracks(&cmd, &Registry::builtin())?);
+            Ok(())
+        }
     }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
@@ -28,6 +29,15 @@ impl Transcript {
 pub struct Meta {
     pub source: Option<String>,
     pub language: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+    /// Format-specific header tags with no dedicated field.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +47,8 @@ pub struct Cue {
     pub text: String,
     #[serde(default)]
     pub speaker: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
 }
 
 impl Cue {
@@ -44,3 +56,13 @@ impl Cue {
         (self.end_ms - self.start_ms).max(0)
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Word {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    /// Recogniser confidence in `0.0..=1.0`, when the source reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
@@ -1,188 +1,471 @@
 use anyhow::{Context, Result, anyhow};
-use std::{fs, path::Path};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
 
 use crate::{
-    cli::{ConvertCmd, Format},
+    cli::{ConvertCmd, ListTracksCmd},
     config::Config,
-    formats,
-    model::{Cue, Transcript},
+    formats::{
+        eaf, mkv, mp4,
+        plain::synth_duration_ms,
+        registry::{CueStream, FormatEntry, FormatWriter, Registry},
+        sami, textgrid,
+        tracks::{SubtitleTrack, format_track_list},
+    },
+    model::Cue,
 };
 
 pub fn run_convert(cmd: ConvertCmd, cfg: &Config) -> Result<()> {
-    let span = tracing::info_span!("convert", input = cmd.input.as_str(), to = ?cmd.to);
+    run_convert_with(cmd, cfg, &Registry::builtin())
+}
+
+pub fn run_convert_with(cmd: ConvertCmd, cfg: &Config, registry: &Registry) -> Result<()> {
+    let span = tracing::info_span!("convert", input = cmd.input.as_str(), to = cmd.to.as_str());
     let _g = span.enter();
 
-    let input_format = cmd
-        .from
-        .unwrap_or_else(|| infer_format_from_path_or_dash(&cmd.input));
-    tracing::info!(?input_format, "input format selected");
+    if cmd.split {
+        return run_split(cmd, cfg, registry);
+    }
 
-    let raw = read_input_to_string(&cmd.input)?;
-    tracing::info!(bytes = raw.len(), "read input");
+    warn_unknown_config_sections(cfg, registry);
 
-    let mut transcript = parse_any(&raw, input_format, cfg)
-        .with_context(|| format!("failed parsing input as {:?}", input_format))?;
+    let overridden;
+    let cfg = match cmd_overrides(&cmd, cfg) {
+        Some(c) => {
+            overridden = c;
+            &overridden
+        }
+        None => cfg,
+    };
 
-    apply_policies(&mut transcript, cfg);
+    let output_format = registry.get(&cmd.to)?;
+    let writer = output_format
+        .writer
+        .as_deref()
+        .ok_or_else(|| anyhow!("format '{}' cannot be written", output_format.name))?;
 
-    log_transcript_summary(&transcript, cfg);
+    let out_path = if cmd.stdout {
+        None
+    } else {
+        let p = derive_output_path(&cmd, output_format)?;
+        check_overwrite(&p, cmd.overwrite)?;
+        Some(p)
+    };
+
+    let input = open_input(&cmd.input)?;
+
+    let (input_format, stream) = match &cmd.from {
+        Some(name) => {
+            let input_format = registry.get(name)?;
+            tracing::info!(input_format = input_format.name, "input format forced");
+            (
+                input_format.name,
+                read_stream_with(input_format, input, cfg)?,
+            )
+        }
+        None => read_inferred(&cmd.input, input, cfg, registry)?,
+    };
+
+    match out_path {
+        None => {
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            convert_stream(stream, input_format, writer, cfg, &mut out)?;
+            tracing::info!(mode = "stdout", "wrote output");
+        }
+        Some(path) => {
+            let file = fs::File::create(&path)
+                .with_context(|| format!("failed creating output file: {path}"))?;
+            let mut out = BufWriter::new(file);
+            if let Err(e) = convert_stream(stream, input_format, writer, cfg, &mut out) {
+                drop(out);
+                let _ = fs::remove_file(&path);
+                return Err(e);
+            }
+            tracing::info!(path = path.as_str(), "wrote output file");
+        }
+    }
 
-    let rendered = render_any(&transcript, cmd.to, cfg)?;
+    Ok(())
+}
 
-    if cmd.stdout {
-        print!("{rendered}");
-        tracing::info!(mode = "stdout", "wrote output");
-        return Ok(());
+/// Converts each text track, tier or SAMI class of the input on its own, to
+/// the output template with `{track}` and `{lang}` filled in per track.
+fn run_split(cmd: ConvertCmd, cfg: &Config, registry: &Registry) -> Result<()> {
+    if cmd.input == "-" {
+        return Err(anyhow!("--split needs an input file, not stdin"));
     }
+    let template = match &cmd.output {
+        Some(o) if o.contains("{track}") || o.contains("{lang}") => o.clone(),
+        Some(o) => {
+            return Err(anyhow!(
+                "--split output '{o}' needs a {{track}} or {{lang}} placeholder"
+            ));
+        }
+        None => {
+            let p = derive_output_path(&cmd, registry.get(&cmd.to)?)?;
+            match p.rsplit_once('.') {
+                Some((base, ext)) => format!("{base}.{{track}}.{ext}"),
+                None => format!("{p}.{{track}}"),
+            }
+        }
+    };
 
-    let out_path = derive_output_path(&cmd)?;
-    write_output(&out_path, &rendered, cmd.overwrite)?;
-    tracing::info!(path = out_path.as_str(), "wrote output file");
+    let tracks: Vec<SubtitleTrack> = read_tracks(&cmd.input, registry)?
+        .into_iter()
+        .filter(|t| t.text)
+        .collect();
+    if tracks.is_empty() {
+        return Err(anyhow!("no text tracks to split in {}", cmd.input));
+    }
+    let paths: Vec<String> = tracks
+        .iter()
+        .map(|t| {
+            template
+                .replace("{track}", &t.number.to_string())
+                .replace("{lang}", t.language.as_deref().unwrap_or("und"))
+        })
+        .collect();
+    for (i, p) in paths.iter().enumerate() {
+        if paths[..i].contains(p) {
+            return Err(anyhow!(
+                "--split would write {p} more than once; add {{track}} to the output"
+            ));
+        }
+        check_overwrite(p, cmd.overwrite)?;
+    }
 
+    for (track, path) in tracks.iter().zip(paths) {
+        tracing::info!(
+            track = track.number,
+            path = path.as_str(),
+            "splitting track"
+        );
+        run_convert_with(
+            ConvertCmd {
+                output: Some(path),
+                track: Some(track.number),
+                split: false,
+                ..cmd.clone()
+            },
+            cfg,
+            registry,
+        )?;
+    }
     Ok(())
 }
 
-fn infer_format_from_path_or_dash(input: &str) -> Format {
-    if input == "-" {
-        return Format::Txt;
-    }
-    let p = Path::new(input);
-    match p
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_lowercase()
-        .as_str()
-    {
-        "srt" => Format::Srt,
-        "vtt" => Format::Vtt,
-        "ass" => Format::Ass,
-        "ssa" => Format::Ass,
-        "txt" => Format::Txt,
-        "tsv" => Format::Tsv,
-        "json" => Format::Json,
-        _ => Format::Txt,
+/// Pulls cues through the policy stage into the writer one at a time, so
+/// neither the input nor the rendered output is held in memory.
+fn convert_stream(
+    stream: CueStream<'_>,
+    input_format: &str,
+    writer: &dyn FormatWriter,
+    cfg: &Config,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut policies = PolicyState::new(cfg);
+    let mut summary = Summary::default();
+
+    let mut sink = writer.begin(&stream.meta, cfg, out)?;
+    for cue in stream.cues {
+        let mut cue = cue.with_context(|| format!("failed parsing input as {input_format}"))?;
+        policies.apply(&mut cue);
+        summary.observe(&cue, cfg);
+        sink.push(&cue)?;
     }
+    sink.finish()?;
+
+    tracing::info!(
+        cues = summary.cues,
+        duration_ms = summary.duration_ms,
+        "transcript summary"
+    );
+
+    Ok(())
 }
 
-fn read_input_to_string(input: &str) -> Result<String> {
-    if input == "-" {
-        use std::io::Read;
-        let mut buf = String::new();
-        std::io::stdin().read_to_string(&mut buf)?;
-        Ok(buf)
-    } else {
-        Ok(fs::read_to_string(input)?)
+/// Applies command-line settings that live in the config, if any were given.
+fn cmd_overrides(cmd: &ConvertCmd, cfg: &Config) -> Option<Config> {
+    if cmd.fps.is_none() && cmd.track.is_none() {
+        return None;
     }
+    let mut c = cfg.clone();
+    if let Some(fps) = cmd.fps {
+        c.formats.microdvd.fps = Some(fps);
+        c.formats.microdvd.trust_header = false;
+    }
+    if let Some(track) = cmd.track {
+        c.formats.mkv.track = Some(track);
+        c.formats.mp4.track = Some(track);
+        // A number on the command line wins over a configured name.
+        c.formats.textgrid.tier = None;
+        c.formats.textgrid.track = Some(track);
+        c.formats.eaf.tier = None;
+        c.formats.eaf.track = Some(track);
+        c.formats.sami.class = None;
+        c.formats.sami.track = Some(track);
+    }
+    Some(c)
+}
+
+/// Lists the subtitle tracks of a container, the tiers of an annotation file
+/// or the language classes of a SAMI file, recognised by its content.
+pub fn list_tracks(cmd: &ListTracksCmd, registry: &Registry) -> Result<String> {
+    Ok(format_track_list(&read_tracks(&cmd.input, registry)?))
 }
 
-fn parse_any(raw: &str, fmt: Format, cfg: &Config) -> Result<Transcript> {
-    let trimmed = raw.trim_start();
-    if fmt == Format::Txt && (trimmed.starts_with('{') || trimmed.starts_with('[')) {
-        tracing::info!("stdin looks like JSON; attempting JSON parse");
-        if let Ok(t) = formats::json::parse_json(raw) {
-            return Ok(t);
+fn read_tracks(path: &str, registry: &Registry) -> Result<Vec<SubtitleTrack>> {
+    let mut input = open_input(path)?;
+    let head = String::from_utf8_lossy(input.fill_buf()?).into_owned();
+    let container = registry
+        .sniff(&head)
+        .into_iter()
+        .map(|e| e.name)
+        .find(|n| matches!(*n, "mkv" | "mp4" | "textgrid" | "eaf" | "sami"));
+    match container {
+        Some("mkv") => mkv::list_tracks(&mut input),
+        Some("mp4") => mp4::list_tracks(&mut input),
+        Some("textgrid") => {
+            let mut raw = Vec::new();
+            input.read_to_end(&mut raw)?;
+            textgrid::list_tiers(&textgrid::decode(&raw))
+        }
+        Some("eaf") => {
+            let mut raw = String::new();
+            input.read_to_string(&mut raw)?;
+            eaf::list_tiers(&raw)
+        }
+        Some("sami") => {
+            let mut raw = String::new();
+            input.read_to_string(&mut raw)?;
+            sami::list_classes(&raw)
+        }
+        _ => {
+            return Err(anyhow!(
+                "not a Matroska/WebM or MP4 container, TextGrid, EAF or SAMI file: {path}"
+            ));
         }
     }
+    .with_context(|| format!("failed reading tracks from {path}"))
+}
 
-    match fmt {
-        Format::Srt | Format::Vtt => parse_srt_or_vtt_via_aspasia(raw, fmt),
-        Format::Ass => formats::ass::parse_ass(raw),
-        Format::Txt => formats::txt::parse_txt(raw, cfg),
-        Format::Tsv => formats::tsv::parse_tsv(raw, cfg),
-        Format::Json => formats::json::parse_json(raw),
+pub fn list_formats(registry: &Registry) -> String {
+    let mut out = String::new();
+    for e in registry.entries() {
+        let caps = match (e.reader.is_some(), e.writer.is_some()) {
+            (true, true) => "read/write",
+            (true, false) => "read",
+            (false, true) => "write",
+            (false, false) => "-",
+        };
+        out.push_str(&format!(
+            "{:<8} {:<10} {}\n",
+            e.name,
+            caps,
+            e.extensions
+                .iter()
+                .map(|x| format!(".{x}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
     }
+    out
 }
 
-fn parse_srt_or_vtt_via_aspasia(raw: &str, fmt: Format) -> Result<Transcript> {
-    if fmt == Format::Vtt {
-        if let Ok(vtt) = raw.parse::<aspasia::WebVttSubtitle>() {
-            tracing::info!("parsed as VTT via aspasia");
-            let plain = aspasia::PlainSubtitle::from(&vtt);
-            return Ok(plain_to_transcript(&plain));
+fn warn_unknown_config_sections(cfg: &Config, registry: &Registry) {
+    for name in cfg.formats.extra.keys() {
+        if !registry.is_known_config_section(name) {
+            tracing::warn!(
+                section = name.as_str(),
+                "config section matches no registered format"
+            );
         }
+    }
+}
 
-        if let Ok(srt) = raw.parse::<aspasia::SubRipSubtitle>() {
-            tracing::info!("parsed as SRT via aspasia (fallback)");
-            let plain = aspasia::PlainSubtitle::from(&srt);
-            return Ok(plain_to_transcript(&plain));
-        }
+fn read_stream_with<'a>(
+    entry: &FormatEntry,
+    input: Input<'a>,
+    cfg: &'a Config,
+) -> Result<CueStream<'a>> {
+    let reader = entry
+        .reader
+        .as_ref()
+        .ok_or_else(|| anyhow!("format '{}' cannot be read", entry.name))?;
+    match input {
+        Input::Stream(r) => reader.read_stream(r, cfg),
+        Input::File(f) => reader.read_seekable(Box::new(f), cfg),
+    }
+    .with_context(|| format!("failed parsing input as {}", entry.name))
+}
+
+/// Picks the input format from the file extension. Content sniffers only run
+/// for stdin or a missing or unknown extension, and to choose between formats
+/// sharing one; an extension naming a single format is taken at its word,
+/// except that `.txt` files holding JSON are read as JSON. A sniffed
+/// candidate has to parse the whole input, so that path is not incremental.
+fn read_inferred<'a>(
+    input_path: &str,
+    mut input: Input<'a>,
+    cfg: &'a Config,
+    registry: &'a Registry,
+) -> Result<(&'static str, CueStream<'a>)> {
+    let ext_matches = if input_path == "-" {
+        Vec::new()
     } else {
-        if let Ok(srt) = raw.parse::<aspasia::SubRipSubtitle>() {
-            tracing::info!("parsed as SRT via aspasia");
-            let plain = aspasia::PlainSubtitle::from(&srt);
-            return Ok(plain_to_transcript(&plain));
+        Path::new(input_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| registry.all_by_extension(e))
+            .unwrap_or_default()
+    };
+
+    let by_ext = match ext_matches.as_slice() {
+        [] => None,
+        [only] => Some(*only),
+        shared => {
+            let head = String::from_utf8_lossy(input.fill_buf()?).into_owned();
+            let sniffed = shared
+                .iter()
+                .find(|e| e.sniff.is_some_and(|f| f(&head)))
+                .copied();
+            Some(sniffed.unwrap_or(shared[0]))
         }
-
-        if let Ok(vtt) = raw.parse::<aspasia::WebVttSubtitle>() {
-            tracing::info!("parsed as VTT via aspasia (fallback)");
-            let plain = aspasia::PlainSubtitle::from(&vtt);
-            return Ok(plain_to_transcript(&plain));
+    };
+    let fallback = registry.fallback()?;
+    let candidates: Vec<&FormatEntry> = match by_ext {
+        // Plain text files holding JSON are still read as JSON.
+        Some(selected) if selected.name == fallback.name => {
+            let head = String::from_utf8_lossy(input.fill_buf()?).into_owned();
+            registry
+                .sniff(&head)
+                .into_iter()
+                .filter(|c| c.name == "json")
+                .collect()
+        }
+        Some(selected) => {
+            tracing::info!(input_format = selected.name, "input format selected");
+            return Ok((selected.name, read_stream_with(selected, input, cfg)?));
         }
+        None => {
+            let head = String::from_utf8_lossy(input.fill_buf()?).into_owned();
+            registry
+                .sniff(&head)
+                .into_iter()
+                .filter(|c| c.name != fallback.name)
+                .collect()
+        }
+    };
+
+    if !candidates.is_empty() {
+        // Kept as bytes so binary formats can be sniffed too.
+        let mut raw = Vec::new();
+        input.read_to_end(&mut raw)?;
+        tracing::info!(bytes = raw.len(), "read input");
+
+        for candidate in candidates {
+            tracing::info!(
+                input_format = candidate.name,
+                "input looks like {}; attempting parse",
+                candidate.name
+            );
+            if let Some(reader) = &candidate.reader
+                && let Ok(t) = reader
+                    .read_stream(Box::new(raw.as_slice()), cfg)
+                    .and_then(CueStream::collect_transcript)
+            {
+                return Ok((candidate.name, CueStream::from_transcript(t)));
+            }
+        }
+
+        tracing::info!(input_format = fallback.name, "input format selected");
+        let raw = String::from_utf8(raw).context("input is not valid UTF-8")?;
+        let t = registry
+            .read(fallback.name, &raw, cfg)
+            .with_context(|| format!("failed parsing input as {}", fallback.name))?;
+        return Ok((fallback.name, CueStream::from_transcript(t)));
     }
 
-    Err(anyhow!("failed to parse as SRT or VTT"))
+    tracing::info!(input_format = fallback.name, "input format selected");
+    Ok((fallback.name, read_stream_with(fallback, input, cfg)?))
 }
 
-fn plain_to_transcript(plain: &aspasia::PlainSubtitle) -> Transcript {
-    let cues: Vec<Cue> = plain
-        .events()
-        .iter()
-        .map(|e| Cue {
-            start_ms: moment_to_ms(&e.start),
-            end_ms: moment_to_ms(&e.end),
-            text: e.text.clone(),
-            speaker: None,
-        })
-        .collect();
-
-    Transcript::new(cues)
+/// Stdin, or a file that readers may seek in.
+enum Input<'a> {
+    Stream(Box<dyn BufRead + 'a>),
+    File(BufReader<fs::File>),
 }
 
-fn moment_to_ms(m: &aspasia::Moment) -> i64 {
-    let h = m.hours();
-    let min = m.minutes();
-    let s = m.seconds();
-    let ms = m.ms();
-    (((h * 60 + min) * 60 + s) * 1000 + ms) as i64
+impl Read for Input<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Stream(r) => r.read(buf),
+            Self::File(f) => f.read(buf),
+        }
+    }
 }
 
-fn apply_policies(t: &mut Transcript, cfg: &Config) {
-    let span = tracing::info_span!("apply_policies");
-    let _g = span.enter();
+impl BufRead for Input<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Stream(r) => r.fill_buf(),
+            Self::File(f) => f.fill_buf(),
+        }
+    }
 
-    if cfg.policy.trim_text || cfg.policy.normalize_whitespace {
-        for c in &mut t.cues {
-            if cfg.policy.trim_text {
-                c.text = c.text.trim().to_string();
-            }
-            if cfg.policy.normalize_whitespace {
-                c.text = normalize_ws(&c.text);
-            }
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Stream(r) => r.consume(amt),
+            Self::File(f) => f.consume(amt),
         }
     }
+}
+
+fn open_input(input: &str) -> Result<Input<'static>> {
+    if input == "-" {
+        Ok(Input::Stream(Box::new(io::stdin().lock())))
+    } else {
+        let f = fs::File::open(input).with_context(|| format!("failed opening input: {input}"))?;
+        Ok(Input::File(BufReader::new(f)))
+    }
+}
+
+/// Per-cue text and timing policies; the cursor carries synthesized timings
+/// forward across cues.
+struct PolicyState<'a> {
+    cfg: &'a Config,
+    cursor: i64,
+}
+
+impl<'a> PolicyState<'a> {
+    fn new(cfg: &'a Config) -> Self {
+        Self { cfg, cursor: 0 }
+    }
+
+    fn apply(&mut self, c: &mut Cue) {
+        let cfg = self.cfg;
+        if cfg.policy.trim_text {
+            c.text = c.text.trim().to_string();
+        }
+        if cfg.policy.normalize_whitespace {
+            c.text = normalize_ws(&c.text);
+        }
 
-    if cfg.policy.synthesize_timings {
-        let mut cursor = 0i64;
-        for c in &mut t.cues {
+        if cfg.policy.synthesize_timings {
             if c.end_ms <= c.start_ms {
                 let dur = synth_duration_ms(&c.text, cfg);
-                c.start_ms = cursor;
-                c.end_ms = cursor + dur;
-                cursor = c.end_ms + cfg.policy.gap_ms;
-            } else {
-                cursor = c.end_ms + cfg.policy.gap_ms;
+                c.start_ms = self.cursor;
+                c.end_ms = self.cursor + dur;
             }
+            self.cursor = c.end_ms + cfg.policy.gap_ms;
         }
     }
 }
 
-fn synth_duration_ms(text: &str, cfg: &Config) -> i64 {
-    let cps = cfg.policy.chars_per_second.max(1.0);
-    let raw = (text.chars().count() as f64 / cps * 1000.0).round() as i64;
-    raw.clamp(cfg.policy.min_duration_ms, cfg.policy.max_duration_ms)
-}
-
 fn normalize_ws(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     let mut prev_space = false;
@@ -200,43 +483,29 @@ fn normalize_ws(s: &str) -> String {
     out.trim().to_string()
 }
 
-fn log_transcript_summary(t: &Transcript, cfg: &Config) {
-    tracing::info!(
-        cues = t.cues.len(),
-        duration_ms = t.duration_ms(),
-        "transcript summary"
-    );
+#[derive(Default)]
+struct Summary {
+    cues: usize,
+    duration_ms: i64,
+}
 
-    if tracing::enabled!(tracing::Level::DEBUG) {
-        let n = cfg.logging.debug_cue_samples.min(t.cues.len());
-        for (i, c) in t.cues.iter().take(n).enumerate() {
+impl Summary {
+    fn observe(&mut self, c: &Cue, cfg: &Config) {
+        if self.cues < cfg.logging.debug_cue_samples && tracing::enabled!(tracing::Level::DEBUG) {
             tracing::debug!(
-                idx = i,
+                idx = self.cues,
                 start_ms = c.start_ms,
                 end_ms = c.end_ms,
                 chars = c.text.chars().count(),
                 "cue sample"
             );
         }
+        self.cues += 1;
+        self.duration_ms = c.end_ms.max(0);
     }
 }
 
-fn render_any(t: &Transcript, fmt: Format, cfg: &Config) -> Result<String> {
-    match fmt {
-        Format::Srt => Ok(formats::srt::write_srt(t, cfg)),
-        Format::Vtt => Ok(formats::vtt::write_vtt(t, cfg)),
-        Format::Ass => Ok(formats::ass::write_ass(t, cfg)),
-        Format::Txt => Ok(formats::txt::write_txt(t, cfg)),
-        Format::Tsv => formats::tsv::write_tsv(t, cfg),
-        Format::Json => formats::json::write_json(
-            t,
-            cfg.formats.json.time_units.as_str(),
-            cfg.formats.json.wrapped,
-        ),
-    }
-}
-
-fn derive_output_path(cmd: &ConvertCmd) -> Result<String> {
+fn derive_output_path(cmd: &ConvertCmd, output_format: &FormatEntry) -> Result<String> {
     if let Some(o) = &cmd.output {
         return Ok(o.clone());
     }
@@ -254,16 +523,206 @@ fn derive_output_path(cmd: &ConvertCmd) -> Result<String> {
         .ok_or_else(|| anyhow!("bad input filename"))?;
 
     let parent = p.parent().unwrap_or_else(|| Path::new("."));
-    let out = parent.join(format!("{stem}.{}", cmd.to.extension()));
+    let ext = output_format
+        .extensions
+        .first()
+        .copied()
+        .unwrap_or(output_format.name);
+    let out = parent.join(format!("{stem}.{ext}"));
     Ok(out.to_string_lossy().to_string())
 }
 
-fn write_output(path: &str, data: &str, overwrite: bool) -> Result<()> {
+fn check_overwrite(path: &str, overwrite: bool) -> Result<()> {
     if Path::new(path).exists() && !overwrite {
         return Err(anyhow!(
             "refusing to overwrite existing file (pass --overwrite): {path}"
         ));
     }
-    fs::write(path, data)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer(path: &str, input: &'static str) -> (&'static str, Vec<Cue>) {
+        let cfg = Box::leak(Box::new(Config::default()));
+        let registry = Box::leak(Box::new(Registry::builtin()));
+        let (name, stream) = read_inferred(
+            path,
+            Input::Stream(Box::new(input.as_bytes())),
+            cfg,
+            registry,
+        )
+        .unwrap();
+        (name, stream.collect_transcript().unwrap().cues)
+    }
+
+    #[test]
+    fn extension_wins_over_content() {
+        let (name, cues) = infer("notes.txt", "f 1 0.5 0.3 hello\n");
+        assert_eq!(name, "txt");
+        assert_eq!(cues[0].text, "f 1 0.5 0.3 hello");
+    }
+
+    #[test]
+    fn json_in_txt_file_is_read_as_json() {
+        let json = "{\"cues\": [{\"start\": 1.0, \"end\": 2.0, \"text\": \"Hi\"}]}\n";
+        let (name, cues) = infer("notes.txt", json);
+        assert_eq!(name, "json");
+        assert_eq!((cues[0].start_ms, cues[0].text.as_str()), (1000, "Hi"));
+
+        let (name, cues) = infer("notes.txt", "[00:00:01] Alice: Hi\n");
+        assert_eq!(name, "txt");
+        assert_eq!(cues[0].speaker.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn stdin_and_unknown_extensions_are_sniffed() {
+        assert_eq!(infer("-", "{\"cues\": []}").0, "json");
+        assert_eq!(infer("captions.dat", "{\"cues\": []}").0, "json");
+        assert_eq!(infer("no_extension", "{\"cues\": []}").0, "json");
+    }
+
+    #[test]
+    fn failed_sniff_falls_back_to_text() {
+        let (name, cues) = infer("-", "[not json at all\n");
+        assert_eq!(name, "txt");
+        assert_eq!(cues.len(), 1);
+    }
+
+    #[test]
+    fn policies_synthesize_missing_timings() {
+        let cfg = Config::default();
+        let mut state = PolicyState::new(&cfg);
+        let mut a = Cue {
+            start_ms: 0,
+            end_ms: 0,
+            text: "  two   words ".to_string(),
+            speaker: None,
+            words: Vec::new(),
+        };
+        let mut b = a.clone();
+        state.apply(&mut a);
+        state.apply(&mut b);
+        assert_eq!(a.text, "two words");
+        assert_eq!((a.start_ms, a.end_ms), (0, 600));
+        assert_eq!(b.start_ms, 600 + cfg.policy.gap_ms);
+    }
+
+    #[test]
+    fn convert_stream_applies_policies_per_cue() {
+        let mut cfg = Config::default();
+        cfg.policy.timestamp_offset_ms = 1000;
+        let registry = Registry::builtin();
+        let txt = registry.get("txt").unwrap();
+        let stream = read_stream_with(
+            txt,
+            Input::Stream(Box::new("one\n\n  two  \n".as_bytes())),
+            &cfg,
+        )
+        .unwrap();
+
+        let srt = registry.get("srt").unwrap().writer.as_deref().unwrap();
+        let mut out = Vec::new();
+        convert_stream(stream, "txt", srt, &cfg, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("1\n00:00:01,000 --> "));
+        assert!(out.contains("\n2\n"));
+        assert!(out.contains("\ntwo\n"));
+    }
+
+    #[test]
+    fn convert_stream_names_the_input_format_on_error() {
+        let cfg = Config::default();
+        let stream = CueStream {
+            meta: Default::default(),
+            cues: Box::new(std::iter::once(Err(anyhow::anyhow!("bad cue")))),
+        };
+        let srt = Registry::builtin();
+        let srt = srt.get("srt").unwrap().writer.as_deref().unwrap();
+        let err = convert_stream(stream, "lrc", srt, &cfg, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.to_string(), "failed parsing input as lrc");
+    }
+
+    #[test]
+    fn shared_sub_extension_is_sniffed() {
+        assert_eq!(infer("film.sub", "{1}{1}25\n{25}{50}Hi\n").0, "microdvd");
+        assert_eq!(
+            infer("film.sub", "[INFORMATION]\n[END INFORMATION]\n").0,
+            "subviewer"
+        );
+        assert_eq!(
+            infer("film.sub", "00:00:01.00,00:00:02.00\nHi\n").0,
+            "subviewer"
+        );
+    }
+
+    #[test]
+    fn audacity_labels_are_sniffed_only_without_txt_extension() {
+        let labels = "0.000000\t1.500000\tHello\n";
+        assert_eq!(infer("labels.txt", labels).0, "txt");
+        let (name, cues) = infer("-", labels);
+        assert_eq!(name, "audacity");
+        assert_eq!(cues[0].text, "Hello");
+    }
+
+    #[test]
+    fn timestamped_script_on_stdin_is_text() {
+        let (name, cues) = infer("-", "[00:00:01] Alice: Hi\n[00:00:04] Bob: Yo\n");
+        assert_eq!(name, "txt");
+        assert_eq!(cues[1].start_ms, 4000);
+        assert_eq!(cues[1].speaker.as_deref(), Some("Bob"));
+    }
+
+    #[test]
+    fn split_writes_one_file_per_sami_class() {
+        let dir = std::env::temp_dir().join(format!("split-sami-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("demo.smi");
+        fs::write(
+            &input,
+            "<SAMI><HEAD><STYLE TYPE=\"text/css\"><!--\n\
+             .ENUSCC { Name: English; lang: en-US; }\n\
+             .KRCC { Name: Korean; lang: ko-KR; }\n\
+             --></STYLE></HEAD><BODY>\n\
+             <SYNC Start=1000><P Class=ENUSCC>Hello\n\
+             <P Class=KRCC>안녕\n\
+             <SYNC Start=2000><P Class=ENUSCC>&nbsp;<P Class=KRCC>&nbsp;\n\
+             </BODY></SAMI>\n",
+        )
+        .unwrap();
+        let mut cmd = ConvertCmd {
+            input: input.to_string_lossy().to_string(),
+            output: None,
+            to: "srt".to_string(),
+            from: None,
+            stdout: false,
+            overwrite: false,
+            fps: None,
+            track: None,
+            split: true,
+        };
+        let mut cfg = Config::default();
+        // The class comes from the split, not the config.
+        cfg.formats.sami.class = Some("ENUSCC".to_string());
+        let registry = Registry::builtin();
+
+        run_convert_with(cmd.clone(), &cfg, &registry).unwrap();
+        let one = fs::read_to_string(dir.join("demo.1.srt")).unwrap();
+        let two = fs::read_to_string(dir.join("demo.2.srt")).unwrap();
+        assert!(one.contains("Hello"));
+        assert!(two.contains("안녕"));
+        assert!(run_convert_with(cmd.clone(), &cfg, &registry).is_err());
+
+        cmd.output = Some(dir.join("demo.{lang}.vtt").to_string_lossy().to_string());
+        cmd.to = "vtt".to_string();
+        run_convert_with(cmd.clone(), &cfg, &registry).unwrap();
+        assert!(dir.join("demo.ko-KR.vtt").exists());
+        assert!(dir.join("demo.en-US.vtt").exists());
+
+        cmd.output = Some(dir.join("demo.vtt").to_string_lossy().to_string());
+        assert!(run_convert_with(cmd, &cfg, &registry).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}